@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::Serialize;
+use tauri_plugin_log::log;
+
+use crate::textfile;
+
+/// Leading bytes inspected for NULs, like `git` and GNU diff do.
+const BINARY_SNIFF_LEN: usize = 8000;
+/// Differing ranges reported individually; the rest are only counted.
+const MAX_REPORTED_RANGES: usize = 1000;
+/// Upper bound for one `read_binary_chunk` call.
+const MAX_CHUNK_LEN: u64 = 64 * 1024;
+
+/// Whether `bytes` look like binary data rather than text.
+///
+/// UTF-16 text is full of NULs too, so a BOM or a UTF-16 NUL pattern keeps it text.
+pub(crate) fn is_binary(bytes: &[u8]) -> bool {
+    let sample = &bytes[..bytes.len().min(BINARY_SNIFF_LEN)];
+    sample.contains(&0)
+        && encoding_rs::Encoding::for_bom(sample).is_none()
+        && textfile::sniff_utf16(sample).is_none()
+}
+
+/// A run of differing bytes, `[offset, offset + length)`.
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ByteRange {
+    pub(crate) offset: u64,
+    pub(crate) length: u64,
+}
+
+#[derive(Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BinaryCompareSummary {
+    pub(crate) left_size: u64,
+    pub(crate) right_size: u64,
+    pub(crate) identical: bool,
+    pub(crate) first_difference: Option<u64>,
+    /// Differing ranges in offset order; the tail of the longer file counts as one range.
+    pub(crate) ranges: Vec<ByteRange>,
+    /// Total number of differing ranges, including ones past `MAX_REPORTED_RANGES`.
+    pub(crate) range_count: usize,
+    pub(crate) differing_bytes: u64,
+    #[serde(skip)]
+    last_end: u64,
+}
+
+impl BinaryCompareSummary {
+    fn push_difference(&mut self, offset: u64, length: u64) {
+        self.differing_bytes += length;
+        // Chunk boundaries can split one run of differences; join it back together.
+        let adjacent = self.range_count > 0 && self.last_end == offset;
+        self.last_end = offset + length;
+        if adjacent {
+            if self.ranges.len() == self.range_count {
+                if let Some(last) = self.ranges.last_mut() {
+                    last.length += length;
+                }
+            }
+            return;
+        }
+        self.range_count += 1;
+        if self.ranges.len() < MAX_REPORTED_RANGES {
+            self.ranges.push(ByteRange { offset, length });
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<(File, u64), String> {
+    let file = File::open(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    let size = file
+        .metadata()
+        .map_err(|error| format!("{}: {error}", path.display()))?
+        .len();
+    Ok((file, size))
+}
+
+/// Fills `buffer` as far as the reader allows, returning the number of bytes read.
+fn read_full(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+/// Byte-by-byte comparison of two files at equal offsets.
+pub(crate) fn compare(left_path: &Path, right_path: &Path) -> Result<BinaryCompareSummary, String> {
+    let (left, left_size) = open(left_path)?;
+    let (right, right_size) = open(right_path)?;
+    let mut left = BufReader::new(left);
+    let mut right = BufReader::new(right);
+    let mut summary = BinaryCompareSummary {
+        left_size,
+        right_size,
+        ..BinaryCompareSummary::default()
+    };
+
+    let mut left_buffer = vec![0u8; 64 * 1024];
+    let mut right_buffer = vec![0u8; 64 * 1024];
+    let mut offset = 0u64;
+    loop {
+        let left_read = read_full(&mut left, &mut left_buffer)
+            .map_err(|error| format!("{}: {error}", left_path.display()))?;
+        let right_read = read_full(&mut right, &mut right_buffer)
+            .map_err(|error| format!("{}: {error}", right_path.display()))?;
+        let common = left_read.min(right_read);
+        let mut index = 0;
+        while index < common {
+            if left_buffer[index] == right_buffer[index] {
+                index += 1;
+                continue;
+            }
+            let start = index;
+            while index < common && left_buffer[index] != right_buffer[index] {
+                index += 1;
+            }
+            summary.push_difference(offset + start as u64, (index - start) as u64);
+        }
+        offset += common as u64;
+        if left_read != right_read || left_read == 0 {
+            break;
+        }
+    }
+
+    let (shorter, longer) = (left_size.min(right_size), left_size.max(right_size));
+    if longer > shorter {
+        summary.push_difference(shorter, longer - shorter);
+    }
+    summary.first_difference = summary.ranges.first().map(|range| range.offset);
+    summary.identical = summary.range_count == 0;
+    log::info!(
+        "binary compare left={} right={} ranges={} differing_bytes={}",
+        left_path.display(),
+        right_path.display(),
+        summary.range_count,
+        summary.differing_bytes
+    );
+    Ok(summary)
+}
+
+/// Reads up to `length` bytes (capped at [`MAX_CHUNK_LEN`]) starting at `offset`.
+pub(crate) fn read_chunk(path: &Path, offset: u64, length: u64) -> Result<Vec<u8>, String> {
+    let (mut file, size) = open(path)?;
+    if offset >= size {
+        return Ok(Vec::new());
+    }
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    let length = length.min(MAX_CHUNK_LEN).min(size - offset) as usize;
+    let mut buffer = vec![0u8; length];
+    let read = read_full(&mut file, &mut buffer)
+        .map_err(|error| format!("{}: {error}", path.display()))?;
+    buffer.truncate(read);
+    Ok(buffer)
+}
@@ -0,0 +1,462 @@
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::bincmp;
+use crate::diff::{self, DiffAlgorithm, DiffHunk, DiffLine, DiffLineKind, DiffOptions, DiffResult};
+use crate::gittool;
+use crate::textfile;
+
+/// diff(1)-style exit codes for `--diff`.
+pub(crate) const EXIT_SAME: i32 = 0;
+pub(crate) const EXIT_DIFFERENT: i32 = 1;
+pub(crate) const EXIT_ERROR: i32 = 2;
+
+const DEFAULT_WIDTH: usize = 130;
+const TAB_WIDTH: usize = 8;
+
+const USAGE: &str = "\
+Usage: gcompare --diff <original> <modified> [options]
+
+Options:
+  --format <unified|context|side-by-side|json>   Output format (default: unified)
+  -U, --context <lines>                          Context lines around each change
+  --algorithm <myers|patience>                   Diff algorithm (default: myers)
+  -W, --width <columns>                          Line width for side-by-side output
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Unified,
+    Context,
+    SideBySide,
+    Json,
+}
+
+#[derive(Debug)]
+struct DiffCommand {
+    original: PathBuf,
+    modified: PathBuf,
+    format: OutputFormat,
+    context_lines: Option<usize>,
+    algorithm: DiffAlgorithm,
+    width: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonOutput<'a> {
+    original: String,
+    modified: String,
+    #[serde(flatten)]
+    result: &'a DiffResult,
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} requires a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {flag}: {value}"))
+}
+
+/// Splits `--flag=value` into its parts; other arguments are returned unchanged.
+fn split_flag(arg: String) -> (String, Option<String>) {
+    if arg.starts_with("--") {
+        if let Some((flag, value)) = arg.split_once('=') {
+            return (flag.to_string(), Some(value.to_string()));
+        }
+    }
+    (arg, None)
+}
+
+fn parse_diff_args(args: Vec<OsString>) -> Result<DiffCommand, String> {
+    let mut args = args.into_iter();
+    let mut paths = Vec::new();
+    let mut format = OutputFormat::Unified;
+    let mut context_lines = None;
+    let mut algorithm = DiffAlgorithm::default();
+    let mut width = DEFAULT_WIDTH;
+
+    while let Some(raw) = args.next() {
+        let Some(arg) = raw.to_str().map(str::to_string) else {
+            paths.push(PathBuf::from(raw));
+            continue;
+        };
+        let (flag, inline_value) = split_flag(arg);
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next().map(|v| v.to_string_lossy().to_string()))
+        };
+        match flag.as_str() {
+            "--diff" => {}
+            "--format" => {
+                let name: String = parse_value(&flag, value())?;
+                format = match name.as_str() {
+                    "unified" => OutputFormat::Unified,
+                    "context" => OutputFormat::Context,
+                    "side-by-side" => OutputFormat::SideBySide,
+                    "json" => OutputFormat::Json,
+                    _ => return Err(format!("Unknown format: {name}")),
+                };
+            }
+            "-U" | "--context" => context_lines = Some(parse_value(&flag, value())?),
+            "--algorithm" => {
+                let name: String = parse_value(&flag, value())?;
+                algorithm = match name.as_str() {
+                    "myers" => DiffAlgorithm::Myers,
+                    "patience" => DiffAlgorithm::Patience,
+                    _ => return Err(format!("Unknown algorithm: {name}")),
+                };
+            }
+            "-W" | "--width" => width = parse_value(&flag, value())?,
+            _ if flag.starts_with('-') && flag.len() > 1 => {
+                return Err(format!("Unknown option: {flag}"));
+            }
+            _ => paths.push(PathBuf::from(flag)),
+        }
+    }
+
+    let [original, modified]: [PathBuf; 2] = paths
+        .try_into()
+        .map_err(|_| "--diff expects exactly two files".to_string())?;
+    Ok(DiffCommand {
+        original,
+        modified,
+        format,
+        context_lines,
+        algorithm,
+        width,
+    })
+}
+
+fn read_input(path: &Path) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|error| format!("{}: {error}", path.display()))
+}
+
+/// `start,len` as printed in unified hunk headers; the length is omitted when it is 1.
+fn unified_range(start: usize, len: usize) -> String {
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{start},{len}")
+    }
+}
+
+/// `first,last` as printed in context hunk headers.
+fn context_range(start: usize, len: usize) -> String {
+    if len <= 1 {
+        start.to_string()
+    } else {
+        format!("{start},{}", start + len - 1)
+    }
+}
+
+fn push_line(out: &mut String, prefix: &str, line: &DiffLine) {
+    let _ = writeln!(out, "{prefix}{}", line.text);
+    if line.missing_newline {
+        out.push_str("\\ No newline at end of file\n");
+    }
+}
+
+fn write_unified(out: &mut String, command: &DiffCommand, result: &DiffResult) {
+    let _ = writeln!(out, "--- {}", command.original.display());
+    let _ = writeln!(out, "+++ {}", command.modified.display());
+    for hunk in &result.hunks {
+        let _ = writeln!(
+            out,
+            "@@ -{} +{} @@",
+            unified_range(hunk.old_start, hunk.old_lines),
+            unified_range(hunk.new_start, hunk.new_lines)
+        );
+        for line in &hunk.lines {
+            let prefix = match line.kind {
+                DiffLineKind::Equal => " ",
+                DiffLineKind::Delete => "-",
+                DiffLineKind::Insert => "+",
+            };
+            push_line(out, prefix, line);
+        }
+    }
+}
+
+/// Marks every line of a hunk for context output: runs that both delete and insert
+/// are changes (`!`), the rest are plain deletions or insertions.
+fn context_markers(hunk: &DiffHunk) -> Vec<&'static str> {
+    let mut markers = vec!["  "; hunk.lines.len()];
+    let mut start = 0;
+    while start < hunk.lines.len() {
+        if hunk.lines[start].kind == DiffLineKind::Equal {
+            start += 1;
+            continue;
+        }
+        let end = hunk.lines[start..]
+            .iter()
+            .position(|line| line.kind == DiffLineKind::Equal)
+            .map_or(hunk.lines.len(), |offset| start + offset);
+        let run = &hunk.lines[start..end];
+        let changed = run.iter().any(|line| line.kind == DiffLineKind::Delete)
+            && run.iter().any(|line| line.kind == DiffLineKind::Insert);
+        for (marker, line) in markers[start..end].iter_mut().zip(run) {
+            *marker = match (changed, line.kind) {
+                (true, _) => "! ",
+                (false, DiffLineKind::Delete) => "- ",
+                (false, _) => "+ ",
+            };
+        }
+        start = end;
+    }
+    markers
+}
+
+fn write_context(out: &mut String, command: &DiffCommand, result: &DiffResult) {
+    let _ = writeln!(out, "*** {}", command.original.display());
+    let _ = writeln!(out, "--- {}", command.modified.display());
+    for hunk in &result.hunks {
+        let markers = context_markers(hunk);
+        out.push_str("***************\n");
+
+        let _ = writeln!(
+            out,
+            "*** {} ****",
+            context_range(hunk.old_start, hunk.old_lines)
+        );
+        if hunk
+            .lines
+            .iter()
+            .any(|line| line.kind == DiffLineKind::Delete)
+        {
+            for (line, marker) in hunk.lines.iter().zip(&markers) {
+                if line.kind != DiffLineKind::Insert {
+                    push_line(out, marker, line);
+                }
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "--- {} ----",
+            context_range(hunk.new_start, hunk.new_lines)
+        );
+        if hunk
+            .lines
+            .iter()
+            .any(|line| line.kind == DiffLineKind::Insert)
+        {
+            for (line, marker) in hunk.lines.iter().zip(&markers) {
+                if line.kind != DiffLineKind::Delete {
+                    push_line(out, marker, line);
+                }
+            }
+        }
+    }
+}
+
+/// Expands tabs and pads or truncates `text` to exactly `width` columns.
+fn fit_column(text: &str, width: usize) -> String {
+    let mut column = String::with_capacity(width);
+    let mut used = 0;
+    for ch in text.chars() {
+        if ch == '\t' {
+            let spaces = TAB_WIDTH - used % TAB_WIDTH;
+            column.extend(std::iter::repeat_n(' ', spaces.min(width - used)));
+            used += spaces.min(width - used);
+        } else {
+            column.push(ch);
+            used += 1;
+        }
+        if used >= width {
+            return column;
+        }
+    }
+    column.extend(std::iter::repeat_n(' ', width - used));
+    column
+}
+
+type SideBySideRow<'a> = (Option<&'a DiffLine>, char, Option<&'a DiffLine>);
+
+/// Pairs pending deletions with insertions in order; leftovers become one-sided rows.
+fn flush_changes<'a>(
+    rows: &mut Vec<SideBySideRow<'a>>,
+    deletes: &mut Vec<&'a DiffLine>,
+    inserts: &mut Vec<&'a DiffLine>,
+) {
+    for offset in 0..deletes.len().max(inserts.len()) {
+        let left = deletes.get(offset).copied();
+        let right = inserts.get(offset).copied();
+        let marker = match (left, right) {
+            (Some(_), Some(_)) => '|',
+            (Some(_), None) => '<',
+            _ => '>',
+        };
+        rows.push((left, marker, right));
+    }
+    deletes.clear();
+    inserts.clear();
+}
+
+fn side_by_side_rows(hunk: &DiffHunk) -> Vec<SideBySideRow<'_>> {
+    let mut rows = Vec::new();
+    let mut deletes = Vec::new();
+    let mut inserts = Vec::new();
+    for line in &hunk.lines {
+        match line.kind {
+            DiffLineKind::Delete => deletes.push(line),
+            DiffLineKind::Insert => inserts.push(line),
+            DiffLineKind::Equal => {
+                flush_changes(&mut rows, &mut deletes, &mut inserts);
+                rows.push((Some(line), ' ', Some(line)));
+            }
+        }
+    }
+    flush_changes(&mut rows, &mut deletes, &mut inserts);
+    rows
+}
+
+fn write_side_by_side(out: &mut String, command: &DiffCommand, result: &DiffResult) {
+    let column = command.width.saturating_sub(3) / 2;
+    for (index, hunk) in result.hunks.iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        for (left, marker, right) in side_by_side_rows(hunk) {
+            let left = fit_column(left.map_or("", |line| line.text.as_str()), column);
+            let right = right.map_or("", |line| line.text.as_str());
+            let row = format!("{left} {marker} {right}");
+            let _ = writeln!(out, "{}", row.trim_end());
+        }
+    }
+}
+
+/// Decodes the charset like the editor does, so UTF-16 and legacy encodings diff as
+/// text. Line endings are kept: files that differ only in them still differ.
+fn decode_input(path: &Path, bytes: &[u8]) -> String {
+    let (text, encoding, had_errors) = textfile::decode_charset(bytes);
+    if had_errors {
+        eprintln!(
+            "gcompare: {}: invalid {encoding} bytes were replaced",
+            path.display()
+        );
+    }
+    text
+}
+
+fn run_diff(command: &DiffCommand) -> Result<i32, String> {
+    let original = read_input(&command.original)?;
+    let modified = read_input(&command.modified)?;
+
+    let mut out = String::new();
+    if bincmp::is_binary(&original) || bincmp::is_binary(&modified) {
+        if original == modified {
+            return Ok(EXIT_SAME);
+        }
+        let _ = writeln!(
+            out,
+            "Binary files {} and {} differ",
+            command.original.display(),
+            command.modified.display()
+        );
+        print(&out);
+        return Ok(EXIT_DIFFERENT);
+    }
+
+    let original = decode_input(&command.original, &original);
+    let modified = decode_input(&command.modified, &modified);
+    // More context than the longer file has lines just means "everything"; side-by-side
+    // shows the whole file unless asked otherwise.
+    let line_count = [&original, &modified]
+        .iter()
+        .map(|text| text.lines().count() + 1)
+        .max()
+        .unwrap_or(0);
+    let default_context = match command.format {
+        OutputFormat::SideBySide => line_count,
+        _ => DiffOptions::default().context_lines,
+    };
+    let options = DiffOptions {
+        algorithm: command.algorithm,
+        context_lines: command
+            .context_lines
+            .unwrap_or(default_context)
+            .min(line_count),
+        ..DiffOptions::default()
+    };
+    let result = diff::compute(&original, &modified, &options);
+
+    match command.format {
+        OutputFormat::Json => {
+            let output = JsonOutput {
+                original: command.original.to_string_lossy().to_string(),
+                modified: command.modified.to_string_lossy().to_string(),
+                result: &result,
+            };
+            let json = serde_json::to_string_pretty(&output)
+                .map_err(|error| format!("Failed to serialize diff: {error}"))?;
+            let _ = writeln!(out, "{json}");
+        }
+        _ if result.identical => {}
+        OutputFormat::Unified => write_unified(&mut out, command, &result),
+        OutputFormat::Context => write_context(&mut out, command, &result),
+        OutputFormat::SideBySide => write_side_by_side(&mut out, command, &result),
+    }
+    print(&out);
+
+    Ok(if result.identical {
+        EXIT_SAME
+    } else {
+        EXIT_DIFFERENT
+    })
+}
+
+fn print(output: &str) {
+    // A closed pipe (`| head`) is not an error worth reporting.
+    let _ = io::stdout().lock().write_all(output.as_bytes());
+}
+
+/// Release builds on Windows use the GUI subsystem and start without a console, so
+/// borrow the terminal's when printing.
+#[cfg(windows)]
+pub(crate) fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn attach_parent_console() {}
+
+/// Runs a headless command if the arguments ask for one.
+///
+/// Returns the process exit code, or `None` when the GUI should start as usual.
+pub(crate) fn run_headless(args: Vec<OsString>) -> Option<i32> {
+    if args.iter().any(|arg| arg == "--install-git-tool") {
+        attach_parent_console();
+        return Some(gittool::run_install());
+    }
+    if !args.iter().any(|arg| arg == "--diff") {
+        return None;
+    }
+    attach_parent_console();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        print(USAGE);
+        return Some(EXIT_SAME);
+    }
+
+    let command = match parse_diff_args(args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("gcompare: {error}");
+            eprint!("{USAGE}");
+            return Some(EXIT_ERROR);
+        }
+    };
+    let code = run_diff(&command).unwrap_or_else(|error| {
+        eprintln!("gcompare: {error}");
+        EXIT_ERROR
+    });
+    Some(code)
+}
@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, ChangeTag, DiffOp, DiffTag, TextDiff};
+
+/// Upper bound for a single diff; past it `similar` falls back to a coarser result
+/// instead of blocking on pathological inputs.
+const DIFF_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_CONTEXT_LINES: usize = 3;
+
+#[derive(Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum DiffAlgorithm {
+    #[default]
+    Myers,
+    Patience,
+}
+
+impl From<DiffAlgorithm> for Algorithm {
+    fn from(value: DiffAlgorithm) -> Self {
+        match value {
+            DiffAlgorithm::Myers => Algorithm::Myers,
+            DiffAlgorithm::Patience => Algorithm::Patience,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum DiffGranularity {
+    /// Whole changed lines only.
+    #[default]
+    Line,
+    /// Changed lines additionally carry word-level segments.
+    Word,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct DiffOptions {
+    pub(crate) algorithm: DiffAlgorithm,
+    pub(crate) granularity: DiffGranularity,
+    /// Unchanged lines kept around each hunk.
+    pub(crate) context_lines: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: DiffAlgorithm::default(),
+            granularity: DiffGranularity::default(),
+            context_lines: DEFAULT_CONTEXT_LINES,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum DiffLineKind {
+    Equal,
+    Delete,
+    Insert,
+}
+
+impl From<ChangeTag> for DiffLineKind {
+    fn from(tag: ChangeTag) -> Self {
+        match tag {
+            ChangeTag::Equal => DiffLineKind::Equal,
+            ChangeTag::Delete => DiffLineKind::Delete,
+            ChangeTag::Insert => DiffLineKind::Insert,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiffSegment {
+    /// Whether this part of the line actually changed.
+    pub(crate) changed: bool,
+    pub(crate) text: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiffLine {
+    pub(crate) kind: DiffLineKind,
+    /// 1-based line number in the original text.
+    pub(crate) old_line: Option<usize>,
+    /// 1-based line number in the modified text.
+    pub(crate) new_line: Option<usize>,
+    /// Line content without its line terminator.
+    pub(crate) text: String,
+    pub(crate) missing_newline: bool,
+    /// Word-level breakdown of a replaced line; only set with word granularity.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) segments: Option<Vec<DiffSegment>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiffHunk {
+    /// 1-based start line in the original text (0 when the range is empty at the start).
+    pub(crate) old_start: usize,
+    pub(crate) old_lines: usize,
+    pub(crate) new_start: usize,
+    pub(crate) new_lines: usize,
+    pub(crate) lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiffStats {
+    pub(crate) insertions: usize,
+    pub(crate) deletions: usize,
+    pub(crate) hunks: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiffResult {
+    pub(crate) identical: bool,
+    /// Similarity between 0.0 and 1.0.
+    pub(crate) ratio: f32,
+    pub(crate) stats: DiffStats,
+    pub(crate) hunks: Vec<DiffHunk>,
+}
+
+fn strip_line_ending(value: &str) -> &str {
+    value
+        .strip_suffix("\r\n")
+        .or_else(|| value.strip_suffix('\n'))
+        .unwrap_or(value)
+}
+
+/// Converts a 0-based half-open range into unified-diff style `(start, len)`.
+fn hunk_range(start: usize, end: usize) -> (usize, usize) {
+    let len = end - start;
+    if len == 0 {
+        (start, 0)
+    } else {
+        (start + 1, len)
+    }
+}
+
+fn build_hunk<'a>(
+    diff: &'a TextDiff<'a, 'a, 'a, str>,
+    ops: &[DiffOp],
+    word_level: bool,
+) -> DiffHunk {
+    let first = &ops[0];
+    let last = &ops[ops.len() - 1];
+    let (old_start, old_lines) = hunk_range(first.old_range().start, last.old_range().end);
+    let (new_start, new_lines) = hunk_range(first.new_range().start, last.new_range().end);
+
+    let mut lines = Vec::new();
+    for op in ops {
+        if word_level {
+            for change in diff.iter_inline_changes(op) {
+                let kind = DiffLineKind::from(change.tag());
+                let mut segments: Vec<DiffSegment> = change
+                    .iter_strings_lossy()
+                    .map(|(changed, text)| DiffSegment {
+                        changed,
+                        text: text.into_owned(),
+                    })
+                    .collect();
+                if let Some(last) = segments.last_mut() {
+                    let len = strip_line_ending(&last.text).len();
+                    last.text.truncate(len);
+                }
+                segments.retain(|segment| !segment.text.is_empty());
+                let text: String = segments
+                    .iter()
+                    .map(|segment| segment.text.as_str())
+                    .collect();
+                lines.push(DiffLine {
+                    kind,
+                    old_line: change.old_index().map(|index| index + 1),
+                    new_line: change.new_index().map(|index| index + 1),
+                    text,
+                    missing_newline: change.missing_newline(),
+                    segments: (op.tag() == DiffTag::Replace && kind != DiffLineKind::Equal)
+                        .then_some(segments),
+                });
+            }
+        } else {
+            for change in diff.iter_changes(op) {
+                lines.push(DiffLine {
+                    kind: change.tag().into(),
+                    old_line: change.old_index().map(|index| index + 1),
+                    new_line: change.new_index().map(|index| index + 1),
+                    text: strip_line_ending(change.value()).to_string(),
+                    missing_newline: change.missing_newline(),
+                    segments: None,
+                });
+            }
+        }
+    }
+
+    DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines,
+    }
+}
+
+/// Runs a line diff between `original` and `modified`.
+pub(crate) fn compute(original: &str, modified: &str, options: &DiffOptions) -> DiffResult {
+    let diff = TextDiff::configure()
+        .algorithm(options.algorithm.into())
+        .timeout(DIFF_TIMEOUT)
+        .diff_lines(original, modified);
+
+    let word_level = options.granularity == DiffGranularity::Word;
+    let hunks: Vec<DiffHunk> = diff
+        .grouped_ops(options.context_lines)
+        .iter()
+        .filter(|group| group.iter().any(|op| op.tag() != DiffTag::Equal))
+        .map(|group| build_hunk(&diff, group, word_level))
+        .collect();
+
+    let mut stats = DiffStats {
+        hunks: hunks.len(),
+        ..DiffStats::default()
+    };
+    for line in hunks.iter().flat_map(|hunk| &hunk.lines) {
+        match line.kind {
+            DiffLineKind::Insert => stats.insertions += 1,
+            DiffLineKind::Delete => stats.deletions += 1,
+            DiffLineKind::Equal => {}
+        }
+    }
+
+    DiffResult {
+        identical: hunks.is_empty(),
+        ratio: diff.ratio(),
+        stats,
+        hunks,
+    }
+}
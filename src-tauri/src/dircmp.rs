@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fs::{File, Metadata};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri_plugin_log::log;
+use walkdir::WalkDir;
+
+/// Entries are flushed to the caller in batches of this size.
+const BATCH_SIZE: usize = 200;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct DirCompareOptions {
+    /// File or directory names skipped on both sides.
+    pub(crate) ignore: Vec<String>,
+    /// Whether identical files are reported at all.
+    pub(crate) include_identical: bool,
+}
+
+impl Default for DirCompareOptions {
+    fn default() -> Self {
+        Self {
+            ignore: [".git", ".svn", ".hg", ".jj", ".DS_Store"]
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            include_identical: true,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum DirEntryStatus {
+    /// Only present in the right (modified) tree.
+    Added,
+    /// Only present in the left (original) tree.
+    Removed,
+    Modified,
+    Identical,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DirEntryDiff {
+    /// Path relative to both roots, always with `/` separators.
+    pub(crate) relative_path: String,
+    pub(crate) status: DirEntryStatus,
+    pub(crate) left_path: Option<String>,
+    pub(crate) right_path: Option<String>,
+    pub(crate) left_size: Option<u64>,
+    pub(crate) right_size: Option<u64>,
+    /// Set when one of the files could not be read; the entry is reported as modified.
+    pub(crate) error: Option<String>,
+}
+
+/// Payload of the `gcompare://dir-compare-entries` event.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DirCompareBatch {
+    pub(crate) request_id: String,
+    pub(crate) entries: Vec<DirEntryDiff>,
+}
+
+#[derive(Serialize, Clone, Default, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DirCompareSummary {
+    pub(crate) left_root: String,
+    pub(crate) right_root: String,
+    pub(crate) added: usize,
+    pub(crate) removed: usize,
+    pub(crate) modified: usize,
+    pub(crate) identical: usize,
+}
+
+struct FileInfo {
+    path: PathBuf,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileInfo {
+    fn new(path: PathBuf, metadata: &Metadata) -> Self {
+        Self {
+            path,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }
+    }
+}
+
+/// Lists every regular file under `root`, keyed by its `/`-separated relative path.
+fn collect_files(root: &Path, ignore: &[String]) -> BTreeMap<String, FileInfo> {
+    let mut files = BTreeMap::new();
+    let walker = WalkDir::new(root)
+        .follow_links(false)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || !ignore
+                    .iter()
+                    .any(|name| entry.file_name().to_string_lossy() == name.as_str())
+        });
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::warn!(
+                    "dir compare walk error root={} error={error}",
+                    root.display()
+                );
+                continue;
+            }
+        };
+        // Symlinked files are compared by target content; symlinked directories are not
+        // descended into so cycles can't occur.
+        let metadata = match std::fs::metadata(entry.path()) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => continue,
+        };
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let key = relative.to_string_lossy().replace('\\', "/");
+        files.insert(key, FileInfo::new(entry.path().to_path_buf(), &metadata));
+    }
+    files
+}
+
+fn hash_file(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Size and mtime first, content hash only when those can't decide.
+fn compare_files(left: &FileInfo, right: &FileInfo) -> Result<bool, String> {
+    if left.size != right.size {
+        return Ok(false);
+    }
+    if left.modified.is_some() && left.modified == right.modified {
+        return Ok(true);
+    }
+    let left_hash =
+        hash_file(&left.path).map_err(|error| format!("{}: {error}", left.path.display()))?;
+    let right_hash =
+        hash_file(&right.path).map_err(|error| format!("{}: {error}", right.path.display()))?;
+    Ok(left_hash == right_hash)
+}
+
+fn path_string(info: Option<&FileInfo>) -> Option<String> {
+    info.map(|info| info.path.to_string_lossy().to_string())
+}
+
+/// Recursively compares two directory trees.
+///
+/// Results are handed to `on_batch` in relative-path order as they are classified, so
+/// callers can stream them while large files are still being hashed.
+pub(crate) fn compare(
+    left_root: &Path,
+    right_root: &Path,
+    options: &DirCompareOptions,
+    mut on_batch: impl FnMut(Vec<DirEntryDiff>),
+) -> Result<DirCompareSummary, String> {
+    if !left_root.is_dir() {
+        return Err(format!("Not a directory: {}", left_root.display()));
+    }
+    if !right_root.is_dir() {
+        return Err(format!("Not a directory: {}", right_root.display()));
+    }
+
+    let mut left_files = collect_files(left_root, &options.ignore);
+    let right_files = collect_files(right_root, &options.ignore);
+    log::info!(
+        "dir compare left={} ({} files) right={} ({} files)",
+        left_root.display(),
+        left_files.len(),
+        right_root.display(),
+        right_files.len()
+    );
+
+    let mut merged: BTreeMap<String, (Option<FileInfo>, Option<FileInfo>)> = BTreeMap::new();
+    for (key, info) in right_files {
+        let left = left_files.remove(&key);
+        merged.insert(key, (left, Some(info)));
+    }
+    for (key, info) in left_files {
+        merged.insert(key, (Some(info), None));
+    }
+
+    let mut summary = DirCompareSummary {
+        left_root: left_root.to_string_lossy().to_string(),
+        right_root: right_root.to_string_lossy().to_string(),
+        ..DirCompareSummary::default()
+    };
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+    for (relative_path, (left, right)) in merged {
+        let mut error = None;
+        let status = match (&left, &right) {
+            (Some(left), Some(right)) => match compare_files(left, right) {
+                Ok(true) => DirEntryStatus::Identical,
+                Ok(false) => DirEntryStatus::Modified,
+                Err(message) => {
+                    log::warn!("dir compare read failed path={relative_path} error={message}");
+                    error = Some(message);
+                    DirEntryStatus::Modified
+                }
+            },
+            (Some(_), None) => DirEntryStatus::Removed,
+            (None, Some(_)) => DirEntryStatus::Added,
+            (None, None) => continue,
+        };
+
+        match status {
+            DirEntryStatus::Added => summary.added += 1,
+            DirEntryStatus::Removed => summary.removed += 1,
+            DirEntryStatus::Modified => summary.modified += 1,
+            DirEntryStatus::Identical => summary.identical += 1,
+        }
+        if status == DirEntryStatus::Identical && !options.include_identical {
+            continue;
+        }
+
+        batch.push(DirEntryDiff {
+            relative_path,
+            status,
+            left_path: path_string(left.as_ref()),
+            right_path: path_string(right.as_ref()),
+            left_size: left.as_ref().map(|info| info.size),
+            right_size: right.as_ref().map(|info| info.size),
+            error,
+        });
+        if batch.len() >= BATCH_SIZE {
+            on_batch(std::mem::replace(
+                &mut batch,
+                Vec::with_capacity(BATCH_SIZE),
+            ));
+        }
+    }
+
+    if !batch.is_empty() {
+        on_batch(batch);
+    }
+
+    Ok(summary)
+}
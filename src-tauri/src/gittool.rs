@@ -0,0 +1,282 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri_plugin_log::log;
+
+use crate::cli::EXIT_ERROR;
+use crate::vcs::git::run_git;
+
+/// Name the tool is registered under, as in `git difftool --tool=gcompare`.
+const TOOL_NAME: &str = "gcompare";
+/// Directory under the system temp dir that holds copies of git's temporary files.
+const SNAPSHOT_DIR: &str = "gcompare-git";
+/// Snapshots older than this are removed the next time one is taken.
+const SNAPSHOT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// Suffixes `git mergetool` appends to the stems of the files it writes next to the
+/// conflicted file, e.g. `main_LOCAL_1234.rs`.
+const MERGETOOL_SUFFIXES: [&str; 4] = ["_BASE_", "_LOCAL_", "_REMOTE_", "_BACKUP_"];
+
+static SNAPSHOT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// One global git config entry written by the installer.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitConfigEntry {
+    pub(crate) key: String,
+    pub(crate) value: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GitToolInstall {
+    pub(crate) executable: String,
+    pub(crate) entries: Vec<GitConfigEntry>,
+}
+
+/// The executable path as git's shell should see it inside double quotes.
+fn executable_for_git() -> Result<String, String> {
+    let exe = std::env::current_exe()
+        .map_err(|error| format!("Failed to locate the GCompare executable: {error}"))?;
+    let exe = exe.to_string_lossy().to_string();
+    let exe = exe.strip_prefix(r"\\?\").map(str::to_string).unwrap_or(exe);
+    // Git for Windows runs tool commands through sh, which accepts forward slashes.
+    Ok(if cfg!(windows) {
+        exe.replace('\\', "/")
+    } else {
+        exe
+    })
+}
+
+fn config_entries(exe: &str) -> Vec<GitConfigEntry> {
+    [
+        (
+            format!("difftool.{TOOL_NAME}.cmd"),
+            format!(r#""{exe}" --wait "$LOCAL" "$REMOTE""#),
+        ),
+        (
+            format!("mergetool.{TOOL_NAME}.cmd"),
+            format!(r#""{exe}" "$BASE" "$LOCAL" "$REMOTE" -o "$MERGED""#),
+        ),
+        (
+            format!("mergetool.{TOOL_NAME}.trustExitCode"),
+            "true".to_string(),
+        ),
+    ]
+    .into_iter()
+    .map(|(key, value)| GitConfigEntry { key, value })
+    .collect()
+}
+
+/// Registers GCompare as `difftool.gcompare` and `mergetool.gcompare` in the global git
+/// config, then reads every entry back to make sure git accepted it.
+pub(crate) fn install() -> Result<GitToolInstall, String> {
+    let executable = executable_for_git()?;
+    let entries = config_entries(&executable);
+    // Global config does not depend on the working directory; any existing one will do.
+    let cwd = std::env::temp_dir();
+    for entry in &entries {
+        run_git(
+            &[
+                "config",
+                "--global",
+                entry.key.as_str(),
+                entry.value.as_str(),
+            ],
+            &cwd,
+        )
+        .map_err(|error| format!("Failed to set {}: {error}", entry.key))?;
+    }
+    for entry in &entries {
+        let stored = run_git(&["config", "--global", "--get", entry.key.as_str()], &cwd)
+            .map_err(|error| format!("Failed to read back {}: {error}", entry.key))?;
+        if stored.trim_end_matches(['\r', '\n']) != entry.value {
+            return Err(format!(
+                "git config has {} = {} instead of {}",
+                entry.key,
+                stored.trim(),
+                entry.value
+            ));
+        }
+    }
+    log::info!("git tool installed executable={executable}");
+    Ok(GitToolInstall {
+        executable,
+        entries,
+    })
+}
+
+/// Headless `--install-git-tool`: prints what was written and returns the exit code.
+pub(crate) fn run_install() -> i32 {
+    match install() {
+        Ok(result) => {
+            for entry in &result.entries {
+                println!("{} = {}", entry.key, entry.value);
+            }
+            println!(
+                "GCompare is registered with git. Use `git difftool --tool={TOOL_NAME}` and \
+                 `git mergetool --tool={TOOL_NAME}`, or set diff.tool / merge.tool to {TOOL_NAME}."
+            );
+            0
+        }
+        Err(error) => {
+            eprintln!("gcompare: {error}");
+            EXIT_ERROR
+        }
+    }
+}
+
+/// `$LOCAL` or `$REMOTE` for a file that was added or deleted.
+pub(crate) fn is_null_device(arg: &OsStr) -> bool {
+    arg == "/dev/null" || (cfg!(windows) && arg.eq_ignore_ascii_case("nul"))
+}
+
+/// Whether `path` is one of the temporary files git removes once the tool exits.
+///
+/// The `right/` side of a `--dir-diff` is left alone: with `--no-symlinks` git copies
+/// edits made there back into the worktree, which a snapshot would lose.
+pub(crate) fn is_git_temp_file(path: &Path) -> bool {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    if MERGETOOL_SUFFIXES.iter().any(|suffix| {
+        stem.rsplit_once(suffix)
+            .is_some_and(|(_, pid)| !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit()))
+    }) {
+        return true;
+    }
+
+    let temp_dir = std::env::temp_dir();
+    let temp_dir = temp_dir.canonicalize().unwrap_or(temp_dir);
+    let Ok(relative) = path.strip_prefix(&temp_dir) else {
+        return false;
+    };
+    let mut components = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy());
+    match components.next() {
+        Some(first) if first.starts_with("git-blob-") => true,
+        Some(first) if first.starts_with("git-difftool.") => {
+            components.next().is_none_or(|side| side != "right")
+        }
+        _ => false,
+    }
+}
+
+fn remove_stale_snapshots(root: &Path) {
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > SNAPSHOT_MAX_AGE);
+        if stale {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
+}
+
+fn new_snapshot_dir() -> Result<PathBuf, String> {
+    let root = std::env::temp_dir().join(SNAPSHOT_DIR);
+    remove_stale_snapshots(&root);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    let dir = root.join(format!(
+        "{nanos}-{}-{}",
+        std::process::id(),
+        SNAPSHOT_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir).map_err(|error| format!("{}: {error}", dir.display()))?;
+    Ok(dir)
+}
+
+fn copy_recursive(source: &Path, target: &Path) -> Result<(), String> {
+    if source.is_dir() {
+        for entry in walkdir::WalkDir::new(source) {
+            let entry = entry.map_err(|error| error.to_string())?;
+            let Ok(relative) = entry.path().strip_prefix(source) else {
+                continue;
+            };
+            let destination = target.join(relative);
+            let result = if entry.file_type().is_dir() {
+                std::fs::create_dir_all(&destination)
+            } else {
+                std::fs::copy(entry.path(), &destination).map(|_| ())
+            };
+            result.map_err(|error| format!("{}: {error}", entry.path().display()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(source, target)
+            .map(|_| ())
+            .map_err(|error| format!("{}: {error}", source.display()))
+    }
+}
+
+/// Copies a git temp file (or `--dir-diff` directory) somewhere git will not delete it.
+pub(crate) fn snapshot(path: &Path) -> Result<PathBuf, String> {
+    let name = path.file_name().unwrap_or(OsStr::new("file"));
+    let target = new_snapshot_dir()?.join(name);
+    copy_recursive(path, &target)?;
+    log::info!(
+        "git temp file snapshot {} -> {}",
+        path.display(),
+        target.display()
+    );
+    Ok(target)
+}
+
+/// An empty stand-in for `/dev/null`, so the other side keeps its position.
+pub(crate) fn empty_placeholder() -> Result<PathBuf, String> {
+    let target = new_snapshot_dir()?.join("(empty)");
+    std::fs::write(&target, b"").map_err(|error| format!("{}: {error}", target.display()))?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn in_temp_dir(relative: &str) -> PathBuf {
+        let temp_dir = std::env::temp_dir();
+        temp_dir.canonicalize().unwrap_or(temp_dir).join(relative)
+    }
+
+    #[test]
+    fn git_temp_files_are_recognized() {
+        for path in [
+            in_temp_dir("git-blob-a1b2c3/main.rs"),
+            in_temp_dir("git-difftool.Xy12Z/left/src/main.rs"),
+            in_temp_dir("git-difftool.Xy12Z/left"),
+            PathBuf::from("/repo/src/main_LOCAL_1234.rs"),
+            PathBuf::from("/repo/src/main_BASE_99"),
+        ] {
+            assert!(is_git_temp_file(&path), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn user_files_are_not_git_temp_files() {
+        for path in [
+            in_temp_dir("report_draft.txt"),
+            in_temp_dir("backup_x.log"),
+            in_temp_dir("a1b2c3_main.rs"),
+            in_temp_dir("git-difftool.Xy12Z/right/src/main.rs"),
+            in_temp_dir("git-difftool.Xy12Z/right"),
+            PathBuf::from("/repo/src/main.rs"),
+            PathBuf::from("/repo/src/main_LOCAL_.rs"),
+            PathBuf::from("/repo/src/main_LOCAL_12a.rs"),
+        ] {
+            assert!(!is_git_temp_file(&path), "{}", path.display());
+        }
+    }
+}
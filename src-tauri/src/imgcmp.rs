@@ -0,0 +1,223 @@
+use std::io::Cursor;
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::{ImageFormat, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_log::log;
+
+/// Changed pixels are grouped on a grid of this many pixels before boxing, so nearby
+/// specks end up in one box instead of hundreds.
+const CELL_SIZE: u32 = 16;
+/// Bounding boxes reported at most; the rest are only counted.
+const MAX_BOXES: usize = 64;
+const MASK_COLOR: Rgba<u8> = Rgba([255, 0, 80, 200]);
+
+/// Where to read one side of an image comparison from.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(crate) enum ImageSource {
+    File {
+        path: String,
+    },
+    /// A file at a VCS revision, with the same fields as `vcs_show_file`.
+    #[serde(rename_all = "camelCase")]
+    Revision {
+        provider: String,
+        revision: String,
+        path: String,
+        repo_root: Option<String>,
+        working_path: Option<String>,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct ImageDiffOptions {
+    /// Largest per-channel difference still treated as equal.
+    pub(crate) tolerance: u8,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImageInfo {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// The decoded image re-encoded as a PNG `data:` URL.
+    pub(crate) data_url: String,
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PixelRect {
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+impl PixelRect {
+    fn union(self, other: PixelRect) -> PixelRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        PixelRect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImageDiffResult {
+    pub(crate) original: ImageInfo,
+    pub(crate) modified: ImageInfo,
+    /// Size of the compared canvas: the larger of both images in each dimension.
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) changed_pixels: u64,
+    pub(crate) total_pixels: u64,
+    /// Transparent PNG `data:` URL of the canvas size with changed pixels highlighted.
+    pub(crate) mask_url: String,
+    /// Regions containing changes, largest first.
+    pub(crate) boxes: Vec<PixelRect>,
+    /// Total number of regions, including ones past the reported limit.
+    pub(crate) box_count: usize,
+}
+
+fn decode(bytes: &[u8], side: &str) -> Result<RgbaImage, String> {
+    image::load_from_memory(bytes)
+        .map(|image| image.to_rgba8())
+        .map_err(|error| format!("Failed to decode {side} image: {error}"))
+}
+
+fn to_data_url(image: &RgbaImage) -> Result<String, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|error| format!("Failed to encode image: {error}"))?;
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(png)))
+}
+
+fn image_info(image: &RgbaImage) -> Result<ImageInfo, String> {
+    Ok(ImageInfo {
+        width: image.width(),
+        height: image.height(),
+        data_url: to_data_url(image)?,
+    })
+}
+
+fn pixels_differ(a: Option<&Rgba<u8>>, b: Option<&Rgba<u8>>, tolerance: u8) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            a.0.iter()
+                .zip(b.0.iter())
+                .any(|(a, b)| a.abs_diff(*b) > tolerance)
+        }
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+fn pixel(image: &RgbaImage, x: u32, y: u32) -> Option<&Rgba<u8>> {
+    (x < image.width() && y < image.height()).then(|| image.get_pixel(x, y))
+}
+
+/// Merges touching grid cells (8-neighbourhood) into one bounding box each.
+fn group_cells(cells: &[Option<PixelRect>], columns: usize) -> Vec<PixelRect> {
+    let rows = cells.len() / columns.max(1);
+    let mut visited = vec![false; cells.len()];
+    let mut boxes = Vec::new();
+    for start in 0..cells.len() {
+        if visited[start] || cells[start].is_none() {
+            continue;
+        }
+        visited[start] = true;
+        let mut bounds: Option<PixelRect> = None;
+        let mut stack = vec![start];
+        while let Some(index) = stack.pop() {
+            if let Some(rect) = cells[index] {
+                bounds = Some(bounds.map_or(rect, |bounds| bounds.union(rect)));
+            }
+            let (row, column) = (index / columns, index % columns);
+            for next_row in row.saturating_sub(1)..=(row + 1).min(rows - 1) {
+                for next_column in column.saturating_sub(1)..=(column + 1).min(columns - 1) {
+                    let next = next_row * columns + next_column;
+                    if !visited[next] && cells[next].is_some() {
+                        visited[next] = true;
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+        boxes.extend(bounds);
+    }
+    boxes
+}
+
+/// Pixel-by-pixel comparison of two encoded images (PNG, JPEG or BMP).
+///
+/// Images of different sizes are aligned at the top-left corner; pixels covered by only
+/// one of them count as changed.
+pub(crate) fn compare(
+    original: &[u8],
+    modified: &[u8],
+    options: &ImageDiffOptions,
+) -> Result<ImageDiffResult, String> {
+    let original = decode(original, "left")?;
+    let modified = decode(modified, "right")?;
+    let width = original.width().max(modified.width());
+    let height = original.height().max(modified.height());
+
+    let columns = width.div_ceil(CELL_SIZE) as usize;
+    let rows = height.div_ceil(CELL_SIZE) as usize;
+    let mut cells: Vec<Option<PixelRect>> = vec![None; columns * rows];
+    let mut mask = RgbaImage::new(width, height);
+    let mut changed_pixels = 0u64;
+    for y in 0..height {
+        for x in 0..width {
+            if !pixels_differ(
+                pixel(&original, x, y),
+                pixel(&modified, x, y),
+                options.tolerance,
+            ) {
+                continue;
+            }
+            changed_pixels += 1;
+            mask.put_pixel(x, y, MASK_COLOR);
+            let cell = &mut cells[(y / CELL_SIZE) as usize * columns + (x / CELL_SIZE) as usize];
+            let rect = PixelRect {
+                x,
+                y,
+                width: 1,
+                height: 1,
+            };
+            *cell = Some(cell.map_or(rect, |cell| cell.union(rect)));
+        }
+    }
+
+    let mut boxes = group_cells(&cells, columns);
+    boxes.sort_by_key(|rect| std::cmp::Reverse(u64::from(rect.width) * u64::from(rect.height)));
+    let box_count = boxes.len();
+    boxes.truncate(MAX_BOXES);
+    log::info!(
+        "image compare {}x{} changed_pixels={changed_pixels} boxes={box_count}",
+        width,
+        height
+    );
+
+    Ok(ImageDiffResult {
+        original: image_info(&original)?,
+        modified: image_info(&modified)?,
+        width,
+        height,
+        changed_pixels,
+        total_pixels: u64::from(width) * u64::from(height),
+        mask_url: to_data_url(&mask)?,
+        boxes,
+        box_count,
+    })
+}
@@ -1,11 +1,10 @@
 use std::fs::OpenOptions;
-use std::io::{ErrorKind, Write};
+use std::io::Write;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use std::sync::{Arc, Mutex};
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-use serde::Serialize;
 use tauri::{
     menu::{
         CheckMenuItem, Menu, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu,
@@ -15,7 +14,37 @@ use tauri::{
     Emitter, Manager,
 };
 use tauri_plugin_log::{log, Builder as LogBuilder, RotationStrategy};
-use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+mod bincmp;
+mod cli;
+mod diff;
+mod dircmp;
+mod gittool;
+mod imgcmp;
+mod merge;
+mod sessions;
+mod textfile;
+mod vcs;
+mod wait;
+
+use bincmp::BinaryCompareSummary;
+use diff::{DiffOptions, DiffResult};
+use dircmp::{DirCompareBatch, DirCompareOptions, DirCompareSummary};
+use gittool::GitToolInstall;
+use imgcmp::{ImageDiffOptions, ImageDiffResult, ImageSource};
+use merge::{MergeOptions, MergeResult, MergeSession, MergeSessionInfo};
+use sessions::{CompareSession, CompareSessions};
+use textfile::{TextFile, TextFileMeta};
+use vcs::p4::{
+    answer_login_prompt, ensure_login, set_global_p4_settings, set_login_prompt_hook,
+    set_p4_profiles, P4Profile, P4Settings,
+};
+use vcs::runner::{self, CancelToken, HistoryProgress};
+use vcs::{
+    BlameRequest, BlameResult, ChangesetRequest, ShowRequest, VcsChangeset, VcsError,
+    VcsHistoryBatch, VcsHistoryResult, VcsRegistry, VcsRevisionCompare, VcsStatusResult,
+};
+use wait::PendingWaits;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -23,98 +52,64 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct GitHistoryEntry {
-    hash: String,
-    timestamp: i64,
-    author: String,
-    summary: String,
-    path: String,
-    deleted: bool,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct GitHistoryResult {
-    repo_root: String,
-    relative_path: String,
-    entries: Vec<GitHistoryEntry>,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct VcsHistoryEntry {
-    provider: String,
-    hash: String,
-    timestamp: i64,
-    author: String,
-    summary: String,
-    path: String,
-    deleted: bool,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct VcsHistoryResult {
-    provider: String,
-    repo_root: Option<String>,
-    relative_path: String,
-    entries: Vec<VcsHistoryEntry>,
-}
-
-#[derive(Default)]
-struct PendingOpenPaths(Mutex<Vec<String>>);
-
-#[derive(Default, Clone, serde::Deserialize)]
-struct P4Settings {
+#[tauri::command]
+fn update_p4_settings(
     port: String,
     user: String,
     client: String,
-}
-
-impl P4Settings {
-    fn is_empty(&self) -> bool {
-        self.port.is_empty() && self.user.is_empty() && self.client.is_empty()
-    }
-}
-
-static GLOBAL_P4_SETTINGS: std::sync::OnceLock<Mutex<P4Settings>> = std::sync::OnceLock::new();
-
-fn get_global_p4_settings() -> P4Settings {
-    GLOBAL_P4_SETTINGS
-        .get_or_init(|| Mutex::new(P4Settings::default()))
-        .lock()
-        .map(|guard| guard.clone())
-        .unwrap_or_default()
-}
-
-fn set_global_p4_settings(settings: P4Settings) {
-    if let Some(mutex) = GLOBAL_P4_SETTINGS.get() {
-        if let Ok(mut guard) = mutex.lock() {
-            *guard = settings;
-        }
-    } else {
-        let _ = GLOBAL_P4_SETTINGS.set(Mutex::new(settings));
-    }
-}
-
-#[tauri::command]
-fn update_p4_settings(port: String, user: String, client: String) {
+    tickets: Option<String>,
+    trust: Option<String>,
+) {
     let settings = P4Settings {
         port: port.trim().to_string(),
         user: user.trim().to_string(),
         client: client.trim().to_string(),
+        tickets: tickets.unwrap_or_default().trim().to_string(),
+        trust: trust.unwrap_or_default().trim().to_string(),
     };
     log::info!(
-        "P4 settings updated: port={}, user={}, client={}",
+        "P4 settings updated: port={}, user={}, client={}, tickets={}, trust={}",
         if settings.port.is_empty() { "(empty)" } else { &settings.port },
         if settings.user.is_empty() { "(empty)" } else { &settings.user },
-        if settings.client.is_empty() { "(empty)" } else { &settings.client }
+        if settings.client.is_empty() { "(empty)" } else { &settings.client },
+        if settings.tickets.is_empty() { "(default)" } else { &settings.tickets },
+        if settings.trust.is_empty() { "(default)" } else { &settings.trust }
     );
     set_global_p4_settings(settings);
 }
 
+#[tauri::command]
+fn update_p4_profiles(profiles: Vec<P4Profile>) {
+    log::info!(
+        "P4 profiles updated: {}",
+        profiles
+            .iter()
+            .map(|profile| format!("{} ({})", profile.name, profile.root_pattern))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    set_p4_profiles(profiles);
+}
+
+/// Answers a `gcompare://p4-login-required` prompt; no password means the user
+/// dismissed it and the waiting command fails with its login error.
+#[tauri::command]
+fn p4_login_reply(id: u64, password: Option<String>) {
+    answer_login_prompt(id, password);
+}
+
+/// Logs in to P4 for the workspace containing `path`, prompting for the password. Used
+/// after a history probe reported that the ticket has expired.
+#[tauri::command]
+async fn p4_login(path: String) -> Result<(), VcsError> {
+    run_vcs_task("P4 login", move || ensure_login(Path::new(&path))).await
+}
+
+#[tauri::command]
+fn update_vcs_settings(timeout_secs: u64) {
+    runner::set_timeout_secs(timeout_secs);
+}
+
 #[tauri::command]
 fn update_theme_menu(app: tauri::AppHandle, theme: String) {
     let menu = app
@@ -158,11 +153,27 @@ fn restart_app(app: tauri::AppHandle) {
 }
 
 #[tauri::command]
-fn consume_open_paths(state: tauri::State<PendingOpenPaths>) -> Vec<String> {
-    let mut pending = state.0.lock().expect("pending open paths lock");
-    let paths = pending.clone();
-    pending.clear();
-    paths
+fn consume_open_sessions(sessions: tauri::State<CompareSessions>) -> Vec<CompareSession> {
+    sessions.consume_pending()
+}
+
+#[tauri::command]
+fn create_session(sessions: tauri::State<CompareSessions>, paths: Vec<String>) -> CompareSession {
+    sessions.create(paths)
+}
+
+#[tauri::command]
+fn update_session(
+    sessions: tauri::State<CompareSessions>,
+    id: u64,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    sessions.update(id, paths)
+}
+
+#[tauri::command]
+fn close_session(app: tauri::AppHandle, id: u64) {
+    sessions::close(&app, id);
 }
 
 fn canonicalize_path(path: &std::path::Path) -> Option<String> {
@@ -171,16 +182,15 @@ fn canonicalize_path(path: &std::path::Path) -> Option<String> {
         Ok(canonical) => {
             let path_str = canonical.to_string_lossy().to_string();
             // Remove Windows extended-length path prefix (\\?\)
-            let normalized = if path_str.starts_with(r"\\?\") {
-                path_str[4..].to_string()
-            } else {
-                path_str
+            let normalized = match path_str.strip_prefix(r"\\?\") {
+                Some(stripped) => stripped.to_string(),
+                None => path_str,
             };
             Some(normalized)
         }
         Err(_) => {
             // Fallback to original path if canonicalization fails
-            if path.is_file() {
+            if path.is_file() || path.is_dir() {
                 Some(path.to_string_lossy().to_string())
             } else {
                 None
@@ -189,14 +199,36 @@ fn canonicalize_path(path: &std::path::Path) -> Option<String> {
     }
 }
 
+/// Turns one launch argument into a path to open, following git's difftool conventions:
+/// `/dev/null` becomes an empty file, and unless the caller waits for the comparison
+/// (`--wait`), temp files git deletes on exit are copied first.
+fn resolve_launch_path(arg: &std::ffi::OsStr, waits: bool) -> Option<String> {
+    let path = if gittool::is_null_device(arg) {
+        gittool::empty_placeholder()
+    } else {
+        let path = PathBuf::from(arg);
+        let path = path.canonicalize().unwrap_or(path);
+        if !waits && gittool::is_git_temp_file(&path) && path.exists() {
+            gittool::snapshot(&path)
+        } else {
+            Ok(path)
+        }
+    };
+    match path {
+        Ok(path) => canonicalize_path(&path),
+        Err(error) => {
+            log::warn!("failed to open launch argument {arg:?}: {error}");
+            None
+        }
+    }
+}
+
 fn collect_startup_paths() -> Vec<String> {
     let args: Vec<_> = std::env::args_os().skip(1).collect();
+    let waits = wait::is_wait_launch(&args);
     let paths: Vec<String> = args
         .iter()
-        .filter_map(|arg| {
-            let path = std::path::PathBuf::from(arg);
-            canonicalize_path(&path)
-        })
+        .filter_map(|arg| resolve_launch_path(arg, waits))
         .collect();
     log::info!("startup args={args:?} paths={paths:?}");
     paths
@@ -204,6 +236,7 @@ fn collect_startup_paths() -> Vec<String> {
 
 fn collect_cli_paths(args: Vec<String>) -> Vec<String> {
     let exe_path = std::env::current_exe().ok();
+    let waits = args.iter().any(|arg| arg == "--wait");
     let paths: Vec<String> = args
         .iter()
         .filter_map(|arg| {
@@ -213,7 +246,10 @@ fn collect_cli_paths(args: Vec<String>) -> Vec<String> {
                     return None;
                 }
             }
-            canonicalize_path(&path)
+            // The forwarding instance exits right after this, and git then removes its
+            // temp files, so without `--wait` they are copied before the paths reach the
+            // frontend.
+            resolve_launch_path(arg.as_ref(), waits)
         })
         .collect();
     log::info!("single instance args={args:?} paths={paths:?}");
@@ -231,1284 +267,424 @@ fn append_boot_log(message: &str) {
     }
 }
 
-fn run_git(args: &[String], cwd: &Path) -> Result<String, String> {
-    let output = Command::new("git")
-        .current_dir(cwd)
-        .args(args)
-        .output()
-        .map_err(|error| {
-            if error.kind() == ErrorKind::NotFound {
-                "git is not installed or not available on PATH.".to_string()
-            } else {
-                format!("Failed to run git: {error}")
-            }
-        })?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let fallback = format!("git exited with status {}", output.status);
-        let message = if stderr.is_empty() { fallback } else { stderr };
-        log::warn!("git failed cwd={} args={args:?} error={message}", cwd.display());
-        return Err(message);
-    }
-
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
-}
-
-fn apply_p4_env(command: &mut Command, cwd: &Path) {
-    // 1. 首先尝试在目录层级中查找 p4config 文件（最精确，与文件路径相关）
-    if let Some(info) = find_p4config_info(cwd) {
-        command.env("P4CONFIG", &info.name);
-        log::info!(
-            "P4CONFIG resolved name={} path={}",
-            info.name,
-            info.path.display()
-        );
-        return;
-    }
-
-    // 2. 然后使用 UI 中配置的备用设置（用户主动为当前场景配置的）
-    let global_settings = get_global_p4_settings();
-    if !global_settings.is_empty() {
-        if !global_settings.port.is_empty() {
-            command.env("P4PORT", &global_settings.port);
-        }
-        if !global_settings.user.is_empty() {
-            command.env("P4USER", &global_settings.user);
-        }
-        if !global_settings.client.is_empty() {
-            command.env("P4CLIENT", &global_settings.client);
-        }
-        log::info!(
-            "P4 env from UI settings: port={}, user={}, client={}",
-            if global_settings.port.is_empty() { "(not set)" } else { &global_settings.port },
-            if global_settings.user.is_empty() { "(not set)" } else { &global_settings.user },
-            if global_settings.client.is_empty() { "(not set)" } else { &global_settings.client }
-        );
-        return;
-    }
-
-    // 3. 最后检查系统环境变量 P4CONFIG（可能是其他项目留下的全局配置）
-    let env_config = std::env::var("P4CONFIG")
-        .ok()
-        .map(|value| value.trim().to_string())
-        .filter(|value| !value.is_empty());
-
-    if let Some(config) = &env_config {
-        log::info!("P4CONFIG from system env: {config}");
-        return;
-    }
-
-    log::info!("No P4 config found from {} (no local p4config, no UI settings, no P4CONFIG env)", cwd.display());
-}
+/// Provider registry shared by the `vcs_*` commands.
+#[derive(Default)]
+struct VcsProviders(Arc<VcsRegistry>);
 
-fn log_p4_info_for_path(path: &str) {
-    let file_path = PathBuf::from(path);
-    let cwd = file_path.parent().unwrap_or_else(|| Path::new("."));
-    let mut command = Command::new("p4");
-    command.current_dir(cwd).args(["info"]);
-    apply_p4_env(&mut command, cwd);
-    match command.output() {
-        Ok(output) => {
-            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-            let message = if !stdout.is_empty() { stdout } else { stderr };
-            if output.status.success() {
-                if !message.is_empty() {
-                    log::info!("p4 info output={}", truncate_for_log(&message, 4000));
-                } else {
-                    log::info!("p4 info returned no output");
-                }
-            } else {
-                log::warn!(
-                    "p4 info failed cwd={} status={} output={}",
-                    cwd.display(),
-                    output.status,
-                    truncate_for_log(&message, 4000)
-                );
-            }
-        }
-        Err(error) => {
-            log::warn!("p4 info failed to run: {error}");
+/// Cancellation tokens of running `vcs_*` requests that the frontend tagged with an id.
+#[derive(Default)]
+struct VcsRequests(Mutex<HashMap<String, CancelToken>>);
+
+impl VcsRequests {
+    fn register(&self, request_id: Option<&str>) -> CancelToken {
+        let token = CancelToken::default();
+        if let Some(request_id) = request_id {
+            self.0
+                .lock()
+                .expect("vcs requests lock")
+                .insert(request_id.to_string(), token.clone());
         }
+        token
     }
-}
 
-fn run_p4(args: &[String], cwd: &Path) -> Result<String, String> {
-    let mut command = Command::new("p4");
-    command.current_dir(cwd).args(args);
-    apply_p4_env(&mut command, cwd);
-
-    let output = command.output().map_err(|error| {
-        if error.kind() == ErrorKind::NotFound {
-            "p4 is not installed or not available on PATH.".to_string()
-        } else {
-            format!("Failed to run p4: {error}")
+    fn finish(&self, request_id: Option<&str>) {
+        if let Some(request_id) = request_id {
+            self.0.lock().expect("vcs requests lock").remove(request_id);
         }
-    })?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let fallback = format!("p4 exited with status {}", output.status);
-        let message = if !stderr.is_empty() {
-            stderr
-        } else if !stdout.is_empty() {
-            stdout
-        } else {
-            fallback
-        };
-        log::warn!("p4 failed cwd={} args={args:?} error={message}", cwd.display());
-        return Err(message);
     }
-
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
-fn run_svn(args: &[String], cwd: &Path) -> Result<String, String> {
-    let output = Command::new("svn")
-        .current_dir(cwd)
-        .args(args)
-        .output()
-        .map_err(|error| {
-            if error.kind() == ErrorKind::NotFound {
-                "svn is not installed or not available on PATH.".to_string()
-            } else {
-                format!("Failed to run svn: {error}")
-            }
-        })?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let fallback = format!("svn exited with status {}", output.status);
-        let message = if !stderr.is_empty() {
-            stderr
-        } else if !stdout.is_empty() {
-            stdout
-        } else {
-            fallback
-        };
-        log::warn!("svn failed cwd={} args={args:?} error={message}", cwd.display());
-        return Err(message);
-    }
-
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
-}
-
-struct P4ConfigInfo {
-    name: String,
-    path: PathBuf,
+async fn run_vcs_task<T, E, F>(label: &str, task: F) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+{
+    run_vcs_request(label, CancelToken::default(), None, task).await
 }
 
-fn find_p4config_info(cwd: &Path) -> Option<P4ConfigInfo> {
-    let candidates = ["p4config.txt", ".p4config", "p4config"];
-    let mut current = Some(cwd);
-    while let Some(dir) = current {
-        for name in candidates {
-            let path = dir.join(name);
-            if path.is_file() {
-                return Some(P4ConfigInfo {
-                    name: name.to_string(),
-                    path,
-                });
-            }
-        }
-        current = dir.parent();
-    }
-    None
-}
-
-fn truncate_for_log(value: &str, max_len: usize) -> String {
-    let normalized = value.replace('\r', "").replace('\n', "\\n");
-    if normalized.len() <= max_len {
-        return normalized;
-    }
-    let mut truncated = normalized;
-    truncated.truncate(max_len);
-    truncated.push_str("...(truncated)");
-    truncated
-}
-
-fn extract_xml_attr(line: &str, attr: &str) -> Option<String> {
-    let needle = format!("{attr}=\"");
-    let start = line.find(&needle)? + needle.len();
-    let rest = &line[start..];
-    let end = rest.find('"')?;
-    Some(rest[..end].to_string())
-}
-
-fn extract_xml_value(line: &str, tag: &str) -> Option<String> {
-    let open = format!("<{tag}>");
-    let close = format!("</{tag}>");
-    let start = line.find(&open)? + open.len();
-    let end = line.find(&close)?;
-    if end < start {
-        return None;
-    }
-    Some(line[start..end].to_string())
+/// Runs `task` off the async runtime; every command it starts stops on `cancel` or timeout.
+async fn run_vcs_request<T, E, F>(
+    label: &str,
+    cancel: CancelToken,
+    progress: Option<HistoryProgress>,
+    task: F,
+) -> Result<T, E>
+where
+    T: Send + 'static,
+    E: From<String> + Send + 'static,
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(move || runner::with_request(cancel, progress, task))
+        .await
+        .map_err(|error| E::from(format!("{label} task failed: {error}")))?
 }
 
-fn parse_svn_time(value: &str) -> i64 {
-    OffsetDateTime::parse(value, &Rfc3339)
-        .map(|dt| dt.unix_timestamp())
-        .unwrap_or(0)
+#[tauri::command]
+async fn vcs_history(
+    app: tauri::AppHandle,
+    providers: tauri::State<'_, VcsProviders>,
+    requests: tauri::State<'_, VcsRequests>,
+    path: String,
+    request_id: Option<String>,
+) -> Result<VcsHistoryResult, VcsError> {
+    let registry = Arc::clone(&providers.0);
+    let cancel = requests.register(request_id.as_deref());
+    let progress = request_id.clone().map(|request_id| -> HistoryProgress {
+        Box::new(move |entries| {
+            let _ = app.emit(
+                "gcompare://vcs-history-progress",
+                VcsHistoryBatch {
+                    request_id: request_id.clone(),
+                    entries: entries.to_vec(),
+                },
+            );
+        })
+    });
+    let result =
+        run_vcs_request("History", cancel, progress, move || registry.history(&path)).await;
+    requests.finish(request_id.as_deref());
+    result
 }
 
-fn parse_svn_log_entries(output: &str, path: &str) -> Vec<VcsHistoryEntry> {
-    struct PendingSvnEntry {
-        revision: String,
-        timestamp: i64,
-        author: String,
-        summary: String,
-        deleted: bool,
-    }
-
-    let mut entries = Vec::new();
-    let mut pending: Option<PendingSvnEntry> = None;
-    let mut in_msg = false;
-    let mut msg_lines: Vec<String> = Vec::new();
-
-    for raw_line in output.lines() {
-        let line = raw_line.trim();
-
-        if line.starts_with("<logentry") {
-            let revision = extract_xml_attr(line, "revision").unwrap_or_default();
-            pending = Some(PendingSvnEntry {
-                revision,
-                timestamp: 0,
-                author: String::new(),
-                summary: String::new(),
-                deleted: false,
-            });
-            in_msg = false;
-            msg_lines.clear();
-            continue;
-        }
-
-        if line.starts_with("</logentry") {
-            if in_msg {
-                if let Some(entry) = pending.as_mut() {
-                    entry.summary = msg_lines.join("\n");
-                }
-                in_msg = false;
-            }
-            if let Some(entry) = pending.take() {
-                if !entry.revision.is_empty() {
-                    entries.push(VcsHistoryEntry {
-                        provider: "svn".to_string(),
-                        hash: entry.revision,
-                        timestamp: entry.timestamp,
-                        author: entry.author,
-                        summary: entry.summary,
-                        path: path.to_string(),
-                        deleted: entry.deleted,
-                    });
-                }
-            }
-            continue;
-        }
-
-        let Some(entry) = pending.as_mut() else {
-            continue;
+#[tauri::command]
+async fn vcs_blame(
+    providers: tauri::State<'_, VcsProviders>,
+    requests: tauri::State<'_, VcsRequests>,
+    path: String,
+    commit: Option<String>,
+    repo_root: Option<String>,
+    provider: Option<String>,
+    request_id: Option<String>,
+) -> Result<BlameResult, VcsError> {
+    let registry = Arc::clone(&providers.0);
+    let cancel = requests.register(request_id.as_deref());
+    let result = run_vcs_request("Blame", cancel, None, move || {
+        let request = BlameRequest {
+            path,
+            revision: commit,
+            repo_root,
         };
-
-        if in_msg {
-            if let Some(end_idx) = line.find("</msg>") {
-                msg_lines.push(line[..end_idx].to_string());
-                entry.summary = msg_lines.join("\n");
-                msg_lines.clear();
-                in_msg = false;
-            } else {
-                msg_lines.push(line.to_string());
-            }
-            continue;
-        }
-
-        if let Some(author) = extract_xml_value(line, "author") {
-            entry.author = author;
-            continue;
-        }
-
-        if let Some(date) = extract_xml_value(line, "date") {
-            entry.timestamp = parse_svn_time(&date);
-            continue;
-        }
-
-        if let Some(msg) = extract_xml_value(line, "msg") {
-            entry.summary = msg;
-            continue;
-        }
-
-        if line.contains("<msg>") {
-            let start = line.find("<msg>").map(|idx| idx + 5).unwrap_or(0);
-            let remainder = &line[start..];
-            if let Some(end_idx) = remainder.find("</msg>") {
-                entry.summary = remainder[..end_idx].to_string();
-            } else {
-                in_msg = true;
-                msg_lines.push(remainder.to_string());
-            }
-            continue;
-        }
-
-        if line.contains("<path") && line.contains("action=\"D\"") {
-            entry.deleted = true;
-        }
-    }
-
-    entries
+        registry.blame(&request, provider.as_deref())
+    })
+    .await;
+    requests.finish(request_id.as_deref());
+    result
 }
 
-fn parse_commit_line(line: &str) -> Option<(String, i64, String, String)> {
-    let mut parts = line.splitn(4, '\t');
-    let hash = parts.next()?;
-    let timestamp = parts.next()?.parse::<i64>().ok()?;
-    let author = parts.next()?.to_string();
-    let summary = parts.next().unwrap_or("").to_string();
-    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
-        return None;
+/// Stops the commands of a running `vcs_history` / `vcs_blame` request.
+#[tauri::command]
+fn vcs_cancel(requests: tauri::State<'_, VcsRequests>, request_id: String) {
+    if let Some(token) = requests.0.lock().expect("vcs requests lock").get(&request_id) {
+        log::info!("vcs request cancelled id={request_id}");
+        token.cancel();
     }
-    Some((hash.to_string(), timestamp, author, summary))
 }
 
-fn to_git_path(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
-}
-
-// ===== Blame types and implementations =====
-
-#[derive(Serialize, Clone)]
-#[serde(rename_all = "camelCase")]
-struct BlameEntry {
-    line: usize,
-    hash: String,
-    author: String,
-    timestamp: i64,
-    summary: String,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct BlameResult {
+#[tauri::command]
+async fn vcs_show_file(
+    providers: tauri::State<'_, VcsProviders>,
     provider: String,
-    entries: Vec<BlameEntry>,
-}
-
-fn git_blame_blocking(path: String, commit: Option<String>, repo_root_override: Option<String>) -> Result<BlameResult, String> {
-    log::info!("Git blame: starting for {} commit={:?}", path, commit);
-    
-    let (repo_root, relative_path) = if let Some(root) = repo_root_override {
-        // Use provided repo root and path is already relative
-        (PathBuf::from(root), path.clone())
-    } else {
-        let file_path = PathBuf::from(&path);
-        if !file_path.is_file() {
-            return Err("Path is not a file.".to_string());
-        }
-        let parent = file_path
-            .parent()
-            .ok_or_else(|| "Invalid file path.".to_string())?;
-
-        let repo_root_output =
-            run_git(&vec!["rev-parse".into(), "--show-toplevel".into()], parent)?;
-        let repo_root_line = repo_root_output
-            .lines()
-            .next()
-            .ok_or_else(|| "Unable to resolve repository root.".to_string())?;
-        let repo_root = PathBuf::from(repo_root_line.trim());
-
-        log::info!("Git blame: repo root = {}", repo_root.display());
-
-        let relative_path = file_path
-            .strip_prefix(&repo_root)
-            .map_err(|_| "File is not inside the repository.".to_string())?;
-        (repo_root, to_git_path(relative_path))
-    };
-
-    log::info!("Git blame: running git blame for {}", relative_path);
-
-    // git blame --porcelain gives machine-readable output
-    // If commit is specified, blame that specific commit
-    let mut args = vec![
-        "blame".into(),
-        "--porcelain".into(),
-    ];
-    if let Some(ref c) = commit {
-        args.push(c.clone());
-    }
-    args.push("--".into());
-    args.push(relative_path);
-    
-    let blame_output = run_git(&args, &repo_root)?;
-
-    log::info!("Git blame: output lines = {}", blame_output.lines().count());
-
-    let mut entries: Vec<BlameEntry> = Vec::new();
-    let mut current_hash = String::new();
-    let mut current_author = String::new();
-    let mut current_timestamp: i64 = 0;
-    let mut current_summary = String::new();
-    let mut line_number: usize = 0;
-
-    // Cache commit info to avoid reparsing
-    let mut commit_cache: std::collections::HashMap<String, (String, i64, String)> =
-        std::collections::HashMap::new();
-
-    for line in blame_output.lines() {
-        if line.starts_with('\t') {
-            // This is the actual content line, meaning we have a complete entry
-            line_number += 1;
-            entries.push(BlameEntry {
-                line: line_number,
-                hash: current_hash.clone(),
-                author: current_author.clone(),
-                timestamp: current_timestamp,
-                summary: current_summary.clone(),
-            });
-        } else if line.len() >= 40 && line.chars().take(40).all(|c| c.is_ascii_hexdigit()) {
-            // This is a commit hash line
-            let hash = &line[..40];
-            current_hash = hash.to_string();
-
-            // Check if we have cached info for this commit
-            if let Some((author, ts, summary)) = commit_cache.get(hash) {
-                current_author = author.clone();
-                current_timestamp = *ts;
-                current_summary = summary.clone();
-            }
-        } else if let Some(author) = line.strip_prefix("author ") {
-            current_author = author.to_string();
-            commit_cache
-                .entry(current_hash.clone())
-                .or_insert_with(|| (current_author.clone(), 0, String::new()))
-                .0 = current_author.clone();
-        } else if let Some(time) = line.strip_prefix("author-time ") {
-            if let Ok(ts) = time.parse::<i64>() {
-                current_timestamp = ts;
-                commit_cache
-                    .entry(current_hash.clone())
-                    .or_insert_with(|| (String::new(), ts, String::new()))
-                    .1 = ts;
-            }
-        } else if let Some(summary) = line.strip_prefix("summary ") {
-            current_summary = summary.to_string();
-            commit_cache
-                .entry(current_hash.clone())
-                .or_insert_with(|| (String::new(), 0, current_summary.clone()))
-                .2 = current_summary.clone();
-        }
-    }
-
-    log::info!("Git blame: returning {} entries", entries.len());
-
-    Ok(BlameResult {
-        provider: "git".to_string(),
-        entries,
+    revision: String,
+    path: String,
+    repo_root: Option<String>,
+    working_path: Option<String>,
+) -> Result<String, VcsError> {
+    let registry = Arc::clone(&providers.0);
+    run_vcs_task("Show", move || {
+        let request = ShowRequest {
+            revision,
+            path,
+            repo_root,
+            working_path,
+        };
+        registry.show(&provider, &request)
     })
+    .await
 }
 
-fn p4_blame_blocking(path: String) -> Result<BlameResult, String> {
-    let file_path = PathBuf::from(&path);
-    if !file_path.is_file() {
-        return Err("Path is not a file.".to_string());
-    }
-    let parent = file_path
-        .parent()
-        .ok_or_else(|| "Invalid file path.".to_string())?;
-
-    log::info!("P4 blame: running p4 annotate for {}", path);
-
-    // p4 annotate -c shows changelist numbers
-    let output = run_p4(
-        &vec!["annotate".into(), "-c".into(), path.clone()],
-        parent,
-    )?;
-
-    log::info!("P4 annotate output lines: {}", output.lines().count());
-
-    // Collect unique changelists
-    let mut changelists: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for line in output.lines() {
-        // Format: "changelist: content"
-        if let Some(cl) = line.split(':').next() {
-            let cl = cl.trim();
-            if !cl.is_empty() && cl.chars().all(|c| c.is_ascii_digit()) {
-                changelists.insert(cl.to_string());
-            }
-        }
-    }
-
-    // Get describe info for all changelists
-    let mut cl_info: std::collections::HashMap<String, (String, i64, String)> =
-        std::collections::HashMap::new();
-
-    for cl in &changelists {
-        if let Ok(describe) = run_p4(
-            &vec!["describe".into(), "-s".into(), cl.clone()],
-            parent,
-        ) {
-            let mut author = String::new();
-            let mut timestamp: i64 = 0;
-            let mut summary = String::new();
-
-            for desc_line in describe.lines() {
-                if desc_line.starts_with("Change") && desc_line.contains(" by ") {
-                    // "Change 12345 by user@client on 2024/01/01 12:00:00"
-                    if let Some(by_part) = desc_line.split(" by ").nth(1) {
-                        if let Some(user) = by_part.split('@').next() {
-                            author = user.to_string();
-                        }
-                        // Parse date
-                        if let Some(on_part) = by_part.split(" on ").nth(1) {
-                            // Try to parse P4 date format
-                            if let Ok(dt) = time::PrimitiveDateTime::parse(
-                                on_part.trim(),
-                                time::macros::format_description!(
-                                    "[year]/[month]/[day] [hour]:[minute]:[second]"
-                                ),
-                            ) {
-                                timestamp = dt.assume_utc().unix_timestamp();
-                            }
-                        }
-                    }
-                } else if desc_line.starts_with('\t') && summary.is_empty() {
-                    summary = desc_line.trim().to_string();
-                }
-            }
-
-            cl_info.insert(cl.clone(), (author, timestamp, summary));
-        }
-    }
-
-    // Build entries
-    let mut entries: Vec<BlameEntry> = Vec::new();
-    let mut line_number: usize = 0;
-
-    for line in output.lines() {
-        line_number += 1;
-        if let Some(cl) = line.split(':').next() {
-            let cl = cl.trim();
-            let (author, timestamp, summary) = cl_info
-                .get(cl)
-                .cloned()
-                .unwrap_or_else(|| (String::new(), 0, String::new()));
-
-            entries.push(BlameEntry {
-                line: line_number,
-                hash: cl.to_string(),
-                author,
-                timestamp,
-                summary,
-            });
-        }
-    }
-
-    log::info!("P4 blame: returning {} entries", entries.len());
-
-    Ok(BlameResult {
-        provider: "p4".to_string(),
-        entries,
+#[tauri::command]
+async fn vcs_changeset(
+    providers: tauri::State<'_, VcsProviders>,
+    provider: String,
+    revision: String,
+    repo_root: Option<String>,
+    working_path: Option<String>,
+) -> Result<VcsChangeset, VcsError> {
+    let registry = Arc::clone(&providers.0);
+    run_vcs_task("Changeset", move || {
+        let request = ChangesetRequest {
+            revision,
+            repo_root,
+            working_path,
+        };
+        registry.changeset(&provider, &request)
     })
+    .await
 }
 
-fn svn_blame_blocking(path: String) -> Result<BlameResult, String> {
-    let file_path = PathBuf::from(&path);
-    if !file_path.is_file() {
-        return Err("Path is not a file.".to_string());
-    }
-    let parent = file_path
-        .parent()
-        .ok_or_else(|| "Invalid file path.".to_string())?;
-
-    // svn blame --xml gives structured output
-    let output = run_svn(&vec!["blame".into(), "--xml".into(), path.clone()], parent)?;
-
-    let mut entries: Vec<BlameEntry> = Vec::new();
-    let mut line_number: usize = 0;
-
-    // Simple XML parsing for svn blame output
-    for line in output.lines() {
-        if line.contains("<commit") {
-            line_number += 1;
-            let mut revision = String::new();
-            let author = String::new();
-            let timestamp: i64 = 0;
-
-            // Extract revision
-            if let Some(rev_start) = line.find("revision=\"") {
-                let rest = &line[rev_start + 10..];
-                if let Some(rev_end) = rest.find('"') {
-                    revision = rest[..rev_end].to_string();
-                }
-            }
-
-            // Look ahead for author and date in subsequent lines
-            // This is a simplified parser - real XML would need proper parsing
-            entries.push(BlameEntry {
-                line: line_number,
-                hash: revision,
-                author,
-                timestamp,
-                summary: String::new(),
-            });
-        } else if line.contains("<author>") {
-            if let Some(entry) = entries.last_mut() {
-                if let Some(start) = line.find("<author>") {
-                    let rest = &line[start + 8..];
-                    if let Some(end) = rest.find("</author>") {
-                        entry.author = rest[..end].to_string();
-                    }
-                }
-            }
-        } else if line.contains("<date>") {
-            if let Some(entry) = entries.last_mut() {
-                if let Some(start) = line.find("<date>") {
-                    let rest = &line[start + 6..];
-                    if let Some(end) = rest.find("</date>") {
-                        let date_str = &rest[..end];
-                        if let Ok(dt) = OffsetDateTime::parse(date_str, &Rfc3339) {
-                            entry.timestamp = dt.unix_timestamp();
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(BlameResult {
-        provider: "svn".to_string(),
-        entries,
+#[tauri::command]
+async fn vcs_shelved_changeset(
+    providers: tauri::State<'_, VcsProviders>,
+    provider: String,
+    revision: String,
+    working_path: Option<String>,
+) -> Result<VcsChangeset, VcsError> {
+    let registry = Arc::clone(&providers.0);
+    run_vcs_task("Shelved changeset", move || {
+        let request = ChangesetRequest {
+            revision,
+            repo_root: None,
+            working_path,
+        };
+        registry.shelved_changeset(&provider, &request)
     })
+    .await
 }
 
 #[tauri::command]
-async fn vcs_blame(
+async fn vcs_compare_revisions(
+    providers: tauri::State<'_, VcsProviders>,
     path: String,
-    commit: Option<String>,
-    repo_root: Option<String>,
+    original_revision: String,
+    modified_revision: String,
     provider: Option<String>,
-) -> Result<BlameResult, String> {
-    log::info!("vcs_blame requested path={} commit={:?} provider={:?}", path, commit, provider);
-
-    // If provider is specified, only try that one
-    let try_git = provider.as_ref().map_or(true, |p| p == "git");
-    let try_p4 = provider.as_ref().map_or(true, |p| p == "p4");
-    let try_svn = provider.as_ref().map_or(true, |p| p == "svn");
-
-    // Try Git first
-    if try_git {
-        match tauri::async_runtime::spawn_blocking({
-            let p = path.clone();
-            let c = commit.clone();
-            let r = repo_root.clone();
-            move || git_blame_blocking(p, c, r)
-        })
-        .await
-        {
-            Ok(Ok(result)) => return Ok(result),
-            Ok(Err(e)) => log::warn!("Git blame failed path={} error={}", path, e),
-            Err(e) => log::warn!("Git blame task failed path={} error={}", path, e),
-        }
-    }
-
-    // Try P4 (only for working copy, not historical)
-    if try_p4 && commit.is_none() {
-        match tauri::async_runtime::spawn_blocking({
-            let p = path.clone();
-            move || p4_blame_blocking(p)
-        })
-        .await
-        {
-            Ok(Ok(result)) => return Ok(result),
-            Ok(Err(e)) => log::warn!("P4 blame failed path={} error={}", path, e),
-            Err(e) => log::warn!("P4 blame task failed path={} error={}", path, e),
-        }
-    }
-
-    // Try SVN (only for working copy, not historical)
-    if try_svn && commit.is_none() {
-        match tauri::async_runtime::spawn_blocking({
-            let p = path.clone();
-            move || svn_blame_blocking(p)
-        })
-        .await
-        {
-            Ok(Ok(result)) => return Ok(result),
-            Ok(Err(e)) => log::warn!("SVN blame failed path={} error={}", path, e),
-            Err(e) => log::warn!("SVN blame task failed path={} error={}", path, e),
-        }
-    }
-
-    Err("No VCS blame available for this file".to_string())
-}
-
-fn git_history_blocking(path: String) -> Result<GitHistoryResult, String> {
-    let file_path = PathBuf::from(path);
-    if !file_path.is_file() {
-        return Err("Path is not a file.".to_string());
-    }
-    let parent = file_path
-        .parent()
-        .ok_or_else(|| "Invalid file path.".to_string())?;
-
-    let repo_root_output =
-        run_git(&vec!["rev-parse".into(), "--show-toplevel".into()], parent)?;
-    let repo_root_line = repo_root_output
-        .lines()
-        .next()
-        .ok_or_else(|| "Unable to resolve repository root.".to_string())?;
-    let repo_root = PathBuf::from(repo_root_line.trim());
-    if repo_root.as_os_str().is_empty() {
-        return Err("Unable to resolve repository root.".to_string());
-    }
-
-    let relative_path = file_path
-        .strip_prefix(&repo_root)
-        .map_err(|_| "File is not inside the repository.".to_string())?;
-    let relative_path = to_git_path(relative_path);
-
-    run_git(
-        &vec![
-            "ls-files".into(),
-            "--error-unmatch".into(),
-            "--".into(),
-            relative_path.clone(),
-        ],
-        &repo_root,
-    )
-    .map_err(|_| "File is not tracked in git.".to_string())?;
-
-    let log_output = run_git(
-        &vec![
-            "--no-pager".into(),
-            "log".into(),
-            "--follow".into(),
-            "--name-status".into(),
-            "--format=%H\t%ct\t%an\t%s".into(),
-            "--".into(),
-            relative_path.clone(),
-        ],
-        &repo_root,
-    )?;
-
-    struct PendingCommit {
-        hash: String,
-        timestamp: i64,
-        author: String,
-        summary: String,
-        path: String,
-        deleted: bool,
-        touched: bool,
-    }
-
-    let mut entries = Vec::new();
-    let mut current_path = relative_path.clone();
-    let mut pending: Option<PendingCommit> = None;
-
-    let mut flush_pending = |pending: &mut Option<PendingCommit>| {
-        if let Some(entry) = pending.take() {
-            if entry.touched {
-                entries.push(GitHistoryEntry {
-                    hash: entry.hash,
-                    timestamp: entry.timestamp,
-                    author: entry.author,
-                    summary: entry.summary,
-                    path: entry.path,
-                    deleted: entry.deleted,
-                });
-            }
-        }
-    };
-
-    for line in log_output.lines() {
-        if line.trim().is_empty() {
-            continue;
-        }
-
-        if let Some((hash, timestamp, author, summary)) = parse_commit_line(line) {
-            flush_pending(&mut pending);
-            pending = Some(PendingCommit {
-                hash,
-                timestamp,
-                author,
-                summary,
-                path: current_path.clone(),
-                deleted: false,
-                touched: false,
-            });
-            continue;
-        }
-
-        let mut parts = line.split('\t');
-        let status = parts.next().unwrap_or("");
-        if status.is_empty() {
-            continue;
-        }
-
-        let Some(entry) = pending.as_mut() else {
-            continue;
-        };
-
-        if status.starts_with('R') || status.starts_with('C') {
-            let old_path = parts.next().unwrap_or("");
-            let new_path = parts.next().unwrap_or("");
-            if !old_path.is_empty() && !new_path.is_empty() {
-                if new_path == current_path || old_path == current_path {
-                    entry.touched = true;
-                }
-                if status.starts_with('R') && new_path == current_path {
-                    current_path = old_path.to_string();
-                }
-            }
-        } else {
-            let path = parts.next().unwrap_or("");
-            if path == current_path {
-                entry.touched = true;
-                if status.starts_with('D') {
-                    entry.deleted = true;
-                }
-            }
-        }
-    }
-
-    flush_pending(&mut pending);
-
-    Ok(GitHistoryResult {
-        repo_root: repo_root.to_string_lossy().to_string(),
-        relative_path,
-        entries,
+) -> Result<VcsRevisionCompare, VcsError> {
+    let registry = Arc::clone(&providers.0);
+    run_vcs_task("Compare revisions", move || {
+        registry.compare_revisions(
+            &path,
+            &original_revision,
+            &modified_revision,
+            provider.as_deref(),
+        )
     })
+    .await
 }
 
-fn git_show_file_blocking(repo_root: String, commit: String, path: String) -> Result<String, String> {
-    let repo_root = PathBuf::from(repo_root);
-    if !repo_root.is_dir() {
-        return Err("Repository root does not exist.".to_string());
-    }
-    let path = path.replace('\\', "/");
-    let spec = format!("{commit}:{path}");
-    run_git(
-        &vec!["--no-pager".into(), "show".into(), spec],
-        &repo_root,
-    )
+#[tauri::command]
+async fn vcs_status(
+    providers: tauri::State<'_, VcsProviders>,
+    path: String,
+) -> Result<VcsStatusResult, VcsError> {
+    let registry = Arc::clone(&providers.0);
+    run_vcs_task("Status", move || registry.status(&path)).await
 }
 
-fn map_git_entry(entry: GitHistoryEntry) -> VcsHistoryEntry {
-    VcsHistoryEntry {
-        provider: "git".to_string(),
-        hash: entry.hash,
-        timestamp: entry.timestamp,
-        author: entry.author,
-        summary: entry.summary,
-        path: entry.path,
-        deleted: entry.deleted,
-    }
+#[tauri::command]
+async fn compute_diff(
+    original: String,
+    modified: String,
+    options: Option<DiffOptions>,
+) -> Result<DiffResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        diff::compute(&original, &modified, &options.unwrap_or_default())
+    })
+    .await
+    .map_err(|error| format!("Diff task failed: {error}"))
 }
 
-fn map_git_result(result: GitHistoryResult) -> VcsHistoryResult {
-    VcsHistoryResult {
-        provider: "git".to_string(),
-        repo_root: Some(result.repo_root),
-        relative_path: result.relative_path,
-        entries: result.entries.into_iter().map(map_git_entry).collect(),
-    }
+#[tauri::command]
+async fn read_text_file(path: String) -> Result<TextFile, String> {
+    tauri::async_runtime::spawn_blocking(move || textfile::read(Path::new(&path)))
+        .await
+        .map_err(|error| format!("Read task failed: {error}"))?
 }
 
-fn p4_history_blocking(path: String) -> Result<VcsHistoryResult, String> {
-    let file_path = PathBuf::from(&path);
-    if !file_path.is_file() {
-        return Err("Path is not a file.".to_string());
-    }
-    let parent = file_path
-        .parent()
-        .ok_or_else(|| "Invalid file path.".to_string())?;
-
-    let log_output = run_p4(
-        &vec![
-            "-ztag".into(),
-            "filelog".into(),
-            "-t".into(),
-            "-l".into(),
-            path.clone(),
-        ],
-        parent,
-    )?;
-
-    struct PendingP4Entry {
-        change: String,
-        timestamp: i64,
-        author: String,
-        summary: String,
-        path: String,
-        deleted: bool,
-    }
-
-    let mut entries = Vec::new();
-    let mut current_depot_path: Option<String> = None;
-    let mut pending: Option<PendingP4Entry> = None;
-
-    let mut flush_pending = |pending: &mut Option<PendingP4Entry>| {
-        if let Some(entry) = pending.take() {
-            entries.push(VcsHistoryEntry {
-                provider: "p4".to_string(),
-                hash: entry.change,
-                timestamp: entry.timestamp,
-                author: entry.author,
-                summary: entry.summary,
-                path: entry.path,
-                deleted: entry.deleted,
-            });
-        }
-    };
-
-    for line in log_output.lines() {
-        let trimmed = line.trim_end();
-        let Some(rest) = trimmed.strip_prefix("... ") else {
-            continue;
-        };
-
-        let mut parts = rest.splitn(2, ' ');
-        let key = parts.next().unwrap_or("");
-        let base_key = key
-            .trim_end_matches(|c: char| c.is_ascii_digit() || c == ',')
-            .trim();
-        let value = parts.next().unwrap_or("").trim();
-
-        match base_key {
-            "depotFile" => {
-                if !value.is_empty() {
-                    current_depot_path = Some(value.to_string());
-                }
-            }
-            "change" => {
-                flush_pending(&mut pending);
-                let entry_path = current_depot_path
-                    .clone()
-                    .unwrap_or_else(|| path.clone());
-                pending = Some(PendingP4Entry {
-                    change: value.to_string(),
-                    timestamp: 0,
-                    author: String::new(),
-                    summary: String::new(),
-                    path: entry_path,
-                    deleted: false,
-                });
-            }
-            "time" => {
-                if let Some(entry) = pending.as_mut() {
-                    entry.timestamp = value.parse::<i64>().unwrap_or(0);
-                }
-            }
-            "user" => {
-                if let Some(entry) = pending.as_mut() {
-                    entry.author = value.to_string();
-                }
-            }
-            "desc" => {
-                if let Some(entry) = pending.as_mut() {
-                    if entry.summary.is_empty() {
-                        entry.summary = value.to_string();
-                    }
-                }
-            }
-            "action" => {
-                if let Some(entry) = pending.as_mut() {
-                    if value.contains("delete") {
-                        entry.deleted = true;
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-
-    flush_pending(&mut pending);
-
-    let relative_path = current_depot_path.unwrap_or(path);
-    if entries.is_empty() {
-        if log_output.trim().is_empty() {
-            log::warn!("p4 filelog returned empty output path={relative_path}");
-        } else {
-            let output_preview = truncate_for_log(&log_output, 4000);
-            log::warn!(
-                "p4 history parsed 0 entries path={relative_path} output_preview={output_preview}"
-            );
-        }
-    }
-
-    Ok(VcsHistoryResult {
-        provider: "p4".to_string(),
-        repo_root: None,
-        relative_path,
-        entries,
+#[tauri::command]
+async fn write_text_file(
+    path: String,
+    text: String,
+    meta: Option<TextFileMeta>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        textfile::write(Path::new(&path), &text, &meta.unwrap_or_default())
     })
+    .await
+    .map_err(|error| format!("Write task failed: {error}"))?
 }
 
-fn svn_history_blocking(path: String) -> Result<VcsHistoryResult, String> {
-    let file_path = PathBuf::from(&path);
-    if !file_path.is_file() {
-        return Err("Path is not a file.".to_string());
-    }
-    let parent = file_path
-        .parent()
-        .ok_or_else(|| "Invalid file path.".to_string())?;
-
-    let wc_root = run_svn(
-        &vec![
-            "info".into(),
-            "--show-item".into(),
-            "wc-root".into(),
-            path.clone(),
-        ],
-        parent,
-    )
-    .ok()
-    .and_then(|output| {
-        output
-            .lines()
-            .next()
-            .map(|line| line.trim())
-            .filter(|line| !line.is_empty())
-            .map(PathBuf::from)
-    });
-
-    let relative_path = wc_root
-        .as_ref()
-        .and_then(|root| file_path.strip_prefix(root).ok())
-        .map(to_git_path)
-        .unwrap_or_else(|| fallback_relative_path(&path));
-
-    let log_output = run_svn(
-        &vec![
-            "log".into(),
-            "--xml".into(),
-            "--verbose".into(),
-            path.clone(),
-        ],
-        parent,
-    )?;
-
-    if log_output.trim().is_empty() {
-        log::warn!("svn log returned empty output path={path}");
-    }
-
-    let entries = parse_svn_log_entries(&log_output, &relative_path);
-    if entries.is_empty() {
-        if log_output.trim().is_empty() {
-            log::warn!("svn log returned empty output path={relative_path}");
-        } else {
-            let output_preview = truncate_for_log(&log_output, 4000);
-            log::warn!(
-                "svn history parsed 0 entries path={relative_path} output_preview={output_preview}"
-            );
-        }
-    }
-
-    Ok(VcsHistoryResult {
-        provider: "svn".to_string(),
-        repo_root: wc_root.map(|root| root.to_string_lossy().to_string()),
-        relative_path,
-        entries,
+#[tauri::command]
+async fn compare_binary(left: String, right: String) -> Result<BinaryCompareSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        bincmp::compare(Path::new(&left), Path::new(&right))
     })
+    .await
+    .map_err(|error| format!("Binary compare task failed: {error}"))?
 }
 
-fn is_git_no_history(error: &str) -> bool {
-    let lower = error.to_lowercase();
-    error == "git is not installed or not available on PATH."
-        || error == "File is not inside the repository."
-        || error == "File is not tracked in git."
-        || error == "Unable to resolve repository root."
-        || lower.contains("not a git repository")
-        || lower.contains("not in a git directory")
-}
-
-fn is_p4_no_history(error: &str) -> bool {
-    let lower = error.to_lowercase();
-    error == "p4 is not installed or not available on PATH."
-        || lower.contains("not under client's root")
-        || lower.contains("not in client view")
-        || lower.contains("not in client")
-        || lower.contains("not on client")
-        || lower.contains("no such file")
-        || lower.contains("file(s) not in client")
-        || lower.contains("must create client")
-        || lower.contains("no such client")
-        || lower.contains("client unknown")
-        || (lower.contains("client") && lower.contains("unknown"))
-        || (lower.contains("client") && lower.contains("not found"))
-}
-
-fn is_svn_no_history(error: &str) -> bool {
-    let lower = error.to_lowercase();
-    error == "svn is not installed or not available on PATH."
-        || lower.contains("not a working copy")
-        || lower.contains("not under version control")
-        || lower.contains("is not a working copy")
-        || lower.contains("e155007")
-        || lower.contains("e155010")
-        || lower.contains("no such file")
-        || lower.contains("not found")
-        || lower.contains("does not exist")
-}
-
-fn fallback_relative_path(path: &str) -> String {
-    let file_path = PathBuf::from(path);
-    file_path
-        .file_name()
-        .map(|name| name.to_string_lossy().to_string())
-        .unwrap_or_else(|| path.to_string())
-}
-
-fn empty_history(path: String) -> VcsHistoryResult {
-    VcsHistoryResult {
-        provider: "none".to_string(),
-        repo_root: None,
-        relative_path: fallback_relative_path(&path),
-        entries: Vec::new(),
-    }
+#[tauri::command]
+async fn read_binary_chunk(path: String, offset: u64, length: u64) -> Result<Vec<u8>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        bincmp::read_chunk(Path::new(&path), offset, length)
+    })
+    .await
+    .map_err(|error| format!("Read task failed: {error}"))?
 }
 
-fn vcs_history_blocking(path: String) -> Result<VcsHistoryResult, String> {
-    log::info!("vcs_history requested path={path}");
-    let git_error = match git_history_blocking(path.clone()) {
-        Ok(result) => return Ok(map_git_result(result)),
-        Err(error) => {
-            if error == "Path is not a file." || error == "Invalid file path." {
-                return Err(error);
-            }
-            log::warn!("Git history failed path={path} error={error}");
-            error
+/// Reads the raw bytes of one side of an image comparison.
+fn load_image_source(registry: &VcsRegistry, source: ImageSource) -> Result<Vec<u8>, String> {
+    match source {
+        ImageSource::File { path } => {
+            std::fs::read(&path).map_err(|error| format!("{path}: {error}"))
         }
-    };
-
-    let p4_error = match p4_history_blocking(path.clone()) {
-        Ok(result) => return Ok(result),
-        Err(error) => {
-            log::warn!("P4 history failed path={path} error={error}");
-            log_p4_info_for_path(&path);
-            error
-        }
-    };
-
-    let svn_error = match svn_history_blocking(path.clone()) {
-        Ok(result) => return Ok(result),
-        Err(error) => {
-            log::warn!("SVN history failed path={path} error={error}");
-            error
+        ImageSource::Revision {
+            provider,
+            revision,
+            path,
+            repo_root,
+            working_path,
+        } => {
+            let request = ShowRequest {
+                revision,
+                path,
+                repo_root,
+                working_path,
+            };
+            registry.show_bytes(&provider, &request).map_err(String::from)
         }
-    };
-
-    if is_git_no_history(&git_error)
-        && is_p4_no_history(&p4_error)
-        && is_svn_no_history(&svn_error)
-    {
-        log::info!(
-            "No VCS history path={path} git_error={git_error} p4_error={p4_error} svn_error={svn_error}"
-        );
-        Ok(empty_history(path))
-    } else {
-        Err(format!(
-            "Git history unavailable: {git_error}. P4 history unavailable: {p4_error}. SVN history unavailable: {svn_error}"
-        ))
     }
 }
 
-fn p4_show_file_blocking(
-    path: String,
-    change: String,
-    working_path: String,
-) -> Result<String, String> {
-    if change.is_empty() || !change.chars().all(|c| c.is_ascii_digit()) {
-        return Err("Invalid changelist.".to_string());
-    }
-    let spec = format!("{path}@={change}");
-    let working_path = PathBuf::from(working_path);
-    let cwd = working_path
-        .parent()
-        .ok_or_else(|| "Invalid file path.".to_string())?;
-    run_p4(&vec!["print".into(), "-q".into(), spec], cwd)
-}
-
-fn svn_show_file_blocking(revision: String, working_path: String) -> Result<String, String> {
-    if revision.is_empty() || !revision.chars().all(|c| c.is_ascii_digit()) {
-        return Err("Invalid revision.".to_string());
-    }
-    let working_path = PathBuf::from(working_path);
-    let cwd = working_path
-        .parent()
-        .ok_or_else(|| "Invalid file path.".to_string())?;
-    run_svn(
-        &vec![
-            "cat".into(),
-            "-r".into(),
-            revision,
-            working_path.to_string_lossy().to_string(),
-        ],
-        cwd,
-    )
-}
-
 #[tauri::command]
-async fn git_history(path: String) -> Result<GitHistoryResult, String> {
-    tauri::async_runtime::spawn_blocking(move || git_history_blocking(path))
-        .await
-        .map_err(|error| format!("Git history task failed: {error}"))?
+async fn compare_images(
+    providers: tauri::State<'_, VcsProviders>,
+    original: ImageSource,
+    modified: ImageSource,
+    options: Option<ImageDiffOptions>,
+) -> Result<ImageDiffResult, String> {
+    let registry = Arc::clone(&providers.0);
+    run_vcs_task("Image compare", move || {
+        let original = load_image_source(&registry, original)?;
+        let modified = load_image_source(&registry, modified)?;
+        imgcmp::compare(&original, &modified, &options.unwrap_or_default())
+    })
+    .await
 }
 
 #[tauri::command]
-async fn git_show_file(repo_root: String, commit: String, path: String) -> Result<String, String> {
+async fn compare_directories(
+    app: tauri::AppHandle,
+    request_id: String,
+    left: String,
+    right: String,
+    options: Option<DirCompareOptions>,
+) -> Result<DirCompareSummary, String> {
+    log::info!("compare_directories requested id={request_id} left={left} right={right}");
     tauri::async_runtime::spawn_blocking(move || {
-        git_show_file_blocking(repo_root, commit, path)
+        let options = options.unwrap_or_default();
+        dircmp::compare(Path::new(&left), Path::new(&right), &options, |entries| {
+            let _ = app.emit(
+                "gcompare://dir-compare-entries",
+                DirCompareBatch {
+                    request_id: request_id.clone(),
+                    entries,
+                },
+            );
+        })
     })
     .await
-    .map_err(|error| format!("Git show task failed: {error}"))?
+    .map_err(|error| format!("Directory compare task failed: {error}"))?
 }
 
 #[tauri::command]
-async fn svn_history(path: String) -> Result<VcsHistoryResult, String> {
-    tauri::async_runtime::spawn_blocking(move || svn_history_blocking(path))
+async fn install_git_tool() -> Result<GitToolInstall, String> {
+    tauri::async_runtime::spawn_blocking(gittool::install)
         .await
-        .map_err(|error| format!("SVN history task failed: {error}"))?
+        .map_err(|error| format!("Git tool install task failed: {error}"))?
 }
 
-#[tauri::command]
-async fn vcs_history(path: String) -> Result<VcsHistoryResult, String> {
-    tauri::async_runtime::spawn_blocking(move || vcs_history_blocking(path))
-        .await
-        .map_err(|error| format!("History task failed: {error}"))?
-}
+/// Set when the app was launched as a merge tool (`base left right -o output`).
+#[derive(Default)]
+struct PendingMerge(Mutex<Option<MergeSession>>);
 
 #[tauri::command]
-async fn p4_show_file(path: String, change: String, working_path: String) -> Result<String, String> {
+async fn merge_files(
+    base: String,
+    left: String,
+    right: String,
+    options: Option<MergeOptions>,
+) -> Result<MergeResult, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        p4_show_file_blocking(path, change, working_path)
+        merge::merge(&base, &left, &right, &options.unwrap_or_default())
     })
     .await
-    .map_err(|error| format!("P4 show task failed: {error}"))?
+    .map_err(|error| format!("Merge task failed: {error}"))
 }
 
 #[tauri::command]
-async fn svn_show_file(revision: String, working_path: String) -> Result<String, String> {
-    tauri::async_runtime::spawn_blocking(move || {
-        svn_show_file_blocking(revision, working_path)
-    })
-    .await
-    .map_err(|error| format!("SVN show task failed: {error}"))?
+fn load_merge_session(
+    state: tauri::State<PendingMerge>,
+) -> Result<Option<MergeSessionInfo>, String> {
+    let session = state.0.lock().expect("merge session lock");
+    session.as_ref().map(MergeSession::load).transpose()
+}
+
+#[tauri::command]
+fn finish_merge(
+    app: tauri::AppHandle,
+    state: tauri::State<PendingMerge>,
+    contents: String,
+) -> Result<(), String> {
+    {
+        let mut session = state.0.lock().expect("merge session lock");
+        let session = session
+            .as_mut()
+            .ok_or_else(|| "No merge in progress.".to_string())?;
+        session.finish(&contents)?;
+    }
+    app.exit(merge::EXIT_RESOLVED);
+    Ok(())
+}
+
+#[tauri::command]
+fn abort_merge(app: tauri::AppHandle) {
+    log::info!("merge aborted");
+    app.exit(merge::EXIT_UNRESOLVED);
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    if let Some(code) = cli::run_headless(std::env::args_os().skip(1).collect()) {
+        std::process::exit(code);
+    }
+
     let start = Arc::new(Instant::now());
     append_boot_log("boot start");
 
+    let mut merge_session =
+        merge::parse_merge_args(std::env::args_os().skip(1)).map(MergeSession::new);
+    if let Some(session) = merge_session
+        .as_mut()
+        .filter(|session| session.launch.auto)
+    {
+        match session.try_auto_resolve() {
+            Ok(true) => std::process::exit(merge::EXIT_RESOLVED),
+            Ok(false) => {}
+            Err(error) => {
+                cli::attach_parent_console();
+                eprintln!("gcompare: {error}");
+                std::process::exit(merge::EXIT_ERROR);
+            }
+        }
+    }
+    let is_merge = merge_session.is_some();
+
+    // `--wait` hands the paths to a running window and blocks until that comparison is
+    // closed, so callers like P4V or `git difftool` don't clean up their temp files early.
+    if !is_merge && wait::is_wait_launch(&std::env::args_os().collect::<Vec<_>>()) {
+        if let Some(code) = wait::forward_and_wait(collect_startup_paths()) {
+            std::process::exit(code);
+        }
+    }
+
     let log_plugin = LogBuilder::new()
         .rotation_strategy(RotationStrategy::KeepAll)
         .build();
 
     let store_plugin = tauri_plugin_store::Builder::new().build();
 
-    let app = tauri::Builder::default()
+    let mut builder = tauri::Builder::default()
         .on_page_load({
             let start = Arc::clone(&start);
             move |_, payload| {
@@ -1523,8 +699,12 @@ pub fn run() {
             }
         })
         .plugin(log_plugin)
-        .plugin(store_plugin)
-        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+        .plugin(store_plugin);
+
+    // A merge launch blocks its caller (git mergetool, P4V) until it is resolved, so it
+    // runs as its own instance instead of handing the paths to an existing window.
+    if !is_merge {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
             if let Some(window) = app.get_webview_window("main") {
                 let _ = window.unminimize();
                 let _ = window.show();
@@ -1532,12 +712,12 @@ pub fn run() {
             }
             let paths = collect_cli_paths(argv);
             if !paths.is_empty() {
-                let state = app.state::<PendingOpenPaths>();
-                let mut pending = state.0.lock().expect("pending open paths lock");
-                pending.extend(paths.clone());
-                let _ = app.emit("gcompare://open-files", paths);
+                sessions::open_external(app, paths, None);
             }
-        }))
+        }));
+    }
+
+    let app = builder
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
@@ -1559,6 +739,13 @@ pub fn run() {
                 true,
                 None::<&str>,
             )?;
+            let compare_folders = MenuItem::with_id(
+                app,
+                "compare_folders",
+                "Compare Folders...",
+                true,
+                None::<&str>,
+            )?;
             let save_focused = MenuItem::with_id(
                 app,
                 "save_focused",
@@ -1573,8 +760,9 @@ pub fn run() {
                     if submenu.text().unwrap_or_default() == "File" {
                         submenu.insert(&open_left, 0)?;
                         submenu.insert(&open_right, 1)?;
-                        submenu.insert(&save_focused, 2)?;
-                        submenu.insert(&file_separator, 3)?;
+                        submenu.insert(&compare_folders, 2)?;
+                        submenu.insert(&save_focused, 3)?;
+                        submenu.insert(&file_separator, 4)?;
                         file_menu_found = true;
                         break;
                     }
@@ -1584,6 +772,7 @@ pub fn run() {
                 let file_menu = Submenu::with_id(app, "file", "File", true)?;
                 file_menu.append(&open_left)?;
                 file_menu.append(&open_right)?;
+                file_menu.append(&compare_folders)?;
                 file_menu.append(&save_focused)?;
                 file_menu.append(&file_separator)?;
                 file_menu.append(&PredefinedMenuItem::close_window(app, None)?)?;
@@ -1634,6 +823,13 @@ pub fn run() {
                 menu.append(&theme_menu)?;
             }
 
+            let install_git_tool = MenuItem::with_id(
+                app,
+                "install_git_tool",
+                "Register as Git Diff/Merge Tool",
+                true,
+                None::<&str>,
+            )?;
             if let Some(tauri::menu::MenuItemKind::Submenu(help_submenu)) =
                 menu.get(HELP_SUBMENU_ID)
             {
@@ -1646,19 +842,25 @@ pub fn run() {
                         None::<&str>,
                     )?)?;
                 }
+                if help_submenu.get("install_git_tool").is_none() {
+                    help_submenu.append(&install_git_tool)?;
+                }
             } else {
                 let help_menu = Submenu::with_id_and_items(
                     app,
                     HELP_SUBMENU_ID,
                     "Help",
                     true,
-                    &[&MenuItem::with_id(
-                        app,
-                        "check_updates",
-                        "Check for Updates...",
-                        true,
-                        None::<&str>,
-                    )?],
+                    &[
+                        &MenuItem::with_id(
+                            app,
+                            "check_updates",
+                            "Check for Updates...",
+                            true,
+                            None::<&str>,
+                        )?,
+                        &install_git_tool,
+                    ],
                 )?;
                 menu.append(&help_menu)?;
             }
@@ -1668,32 +870,42 @@ pub fn run() {
             match event.id().as_ref() {
                 "theme_system" => {
                     let _ = app.emit("gcompare://set-theme", "system");
-                    let _ = update_theme_menu(app.clone(), "system".to_string());
+                    update_theme_menu(app.clone(), "system".to_string());
                 }
                 "theme_light" => {
                     let _ = app.emit("gcompare://set-theme", "light");
-                    let _ = update_theme_menu(app.clone(), "light".to_string());
+                    update_theme_menu(app.clone(), "light".to_string());
                 }
                 "theme_dark" => {
                     let _ = app.emit("gcompare://set-theme", "dark");
-                    let _ = update_theme_menu(app.clone(), "dark".to_string());
+                    update_theme_menu(app.clone(), "dark".to_string());
                 }
                 "check_updates" => {
                     let _ = app.emit("gcompare://check-updates", ());
                 }
+                "install_git_tool" => {
+                    let _ = app.emit("gcompare://install-git-tool", ());
+                }
                 "open_left" => {
                     let _ = app.emit("gcompare://open-left", ());
                 }
                 "open_right" => {
                     let _ = app.emit("gcompare://open-right", ());
                 }
+                "compare_folders" => {
+                    let _ = app.emit("gcompare://compare-folders", ());
+                }
                 "save_focused" => {
                     let _ = app.emit("gcompare://save-focused", ());
                 }
                 _ => {}
             }
         })
-        .manage(PendingOpenPaths::default())
+        .manage(CompareSessions::default())
+        .manage(VcsProviders::default())
+        .manage(VcsRequests::default())
+        .manage(PendingWaits::default())
+        .manage(PendingMerge(Mutex::new(merge_session)))
         .setup({
             let start = Arc::clone(&start);
             move |app| {
@@ -1701,11 +913,27 @@ pub fn run() {
                 "setup start at {}ms",
                 start.elapsed().as_millis()
             ));
-            let startup_paths = collect_startup_paths();
+            let handle = app.handle().clone();
+            set_login_prompt_hook(move |prompt| {
+                let _ = handle.emit("gcompare://p4-login-required", prompt);
+            });
+            match app.path().app_data_dir() {
+                Ok(dir) => app
+                    .state::<VcsProviders>()
+                    .0
+                    .set_cache_dir(dir.join("vcs-cache")),
+                Err(error) => log::warn!("vcs cache disabled: {error}"),
+            }
+            let startup_paths = if is_merge {
+                Vec::new()
+            } else {
+                collect_startup_paths()
+            };
             if !startup_paths.is_empty() {
-                let state = app.state::<PendingOpenPaths>();
-                let mut pending = state.0.lock().expect("pending open paths lock");
-                pending.extend(startup_paths.clone());
+                sessions::open_external(app.handle(), startup_paths, None);
+            }
+            if !is_merge {
+                wait::start_listener(app.handle().clone());
             }
             append_boot_log(&format!(
                 "setup end at {}ms",
@@ -1718,15 +946,35 @@ pub fn run() {
             greet,
             update_theme_menu,
             update_p4_settings,
+            update_p4_profiles,
+            p4_login_reply,
+            p4_login,
+            update_vcs_settings,
             restart_app,
-            consume_open_paths,
-            git_history,
-            git_show_file,
-            svn_history,
+            consume_open_sessions,
+            create_session,
+            update_session,
+            close_session,
             vcs_history,
             vcs_blame,
-            p4_show_file,
-            svn_show_file
+            vcs_show_file,
+            vcs_changeset,
+            vcs_shelved_changeset,
+            vcs_compare_revisions,
+            vcs_status,
+            vcs_cancel,
+            compute_diff,
+            read_text_file,
+            write_text_file,
+            compare_binary,
+            compare_images,
+            read_binary_chunk,
+            compare_directories,
+            install_git_tool,
+            merge_files,
+            load_merge_session,
+            finish_merge,
+            abort_merge
         ])
         .build(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1746,21 +994,27 @@ pub fn run() {
             ));
         }
 
+        // Closing the window without saving tells the merge caller nothing was resolved.
+        if let tauri::RunEvent::Exit = &event {
+            app_handle.state::<PendingWaits>().shutdown();
+            let state = app_handle.state::<PendingMerge>();
+            let session = state.0.lock().expect("merge session lock");
+            if session.as_ref().is_some_and(|session| !session.resolved) {
+                std::process::exit(merge::EXIT_UNRESOLVED);
+            }
+        }
+
         #[cfg(any(target_os = "macos", target_os = "ios"))]
         if let tauri::RunEvent::Opened { urls } = &event {
             let paths: Vec<String> = urls
                 .iter()
                 .filter_map(|url| url.to_file_path().ok())
-                .filter(|path| path.is_file())
+                .filter(|path| path.is_file() || path.is_dir())
                 .map(|path| path.to_string_lossy().to_string())
                 .collect();
 
             if !paths.is_empty() {
-                let state = app_handle.state::<PendingOpenPaths>();
-                let mut pending = state.0.lock().expect("pending open paths lock");
-                pending.extend(paths.clone());
-
-                let _ = app_handle.emit("gcompare://open-files", paths);
+                sessions::open_external(app_handle, paths, None);
             }
         }
 
@@ -0,0 +1,454 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices_deadline, Algorithm, DiffOp};
+use tauri_plugin_log::log;
+
+use crate::textfile;
+
+/// Same budget as the two-way diff engine; past it `similar` returns a coarser match.
+const MERGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Process exit codes for `base left right -o output` launches, as expected by
+/// `git mergetool` with `trustExitCode = true`.
+pub(crate) const EXIT_RESOLVED: i32 = 0;
+pub(crate) const EXIT_UNRESOLVED: i32 = 1;
+pub(crate) const EXIT_ERROR: i32 = 2;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct MergeOptions {
+    pub(crate) left_label: String,
+    pub(crate) base_label: String,
+    pub(crate) right_label: String,
+    /// Also write the base section (`|||||||`) into conflict markers, like git's diff3 style.
+    pub(crate) show_base: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            left_label: "LEFT".to_string(),
+            base_label: "BASE".to_string(),
+            right_label: "RIGHT".to_string(),
+            show_base: false,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum MergeSide {
+    Left,
+    Right,
+    /// Both sides made the same change.
+    Both,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub(crate) enum MergeRegion {
+    /// Identical in base, left and right.
+    Unchanged { text: String },
+    /// Changed on one side only, or identically on both.
+    Resolved { side: MergeSide, text: String },
+    /// Changed differently on both sides.
+    Conflict {
+        base: String,
+        left: String,
+        right: String,
+    },
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MergeResult {
+    pub(crate) regions: Vec<MergeRegion>,
+    pub(crate) conflicts: usize,
+    /// Merged text, with conflict markers around unresolved regions.
+    pub(crate) merged: String,
+}
+
+/// For every base line, the index of the matching line in `other` (if any).
+fn match_lines(base: &[&str], other: &[&str], deadline: Instant) -> Vec<Option<usize>> {
+    let mut matches = vec![None; base.len()];
+    for op in capture_diff_slices_deadline(Algorithm::Myers, base, other, Some(deadline)) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for (slot, index) in matches[old_index..old_index + len]
+                .iter_mut()
+                .zip(new_index..)
+            {
+                *slot = Some(index);
+            }
+        }
+    }
+    matches
+}
+
+fn push_unchanged(regions: &mut Vec<MergeRegion>, lines: &[&str]) {
+    if let Some(MergeRegion::Unchanged { text }) = regions.last_mut() {
+        text.push_str(&lines.concat());
+        return;
+    }
+    regions.push(MergeRegion::Unchanged {
+        text: lines.concat(),
+    });
+}
+
+fn push_change(regions: &mut Vec<MergeRegion>, base: &[&str], left: &[&str], right: &[&str]) {
+    if base.is_empty() && left.is_empty() && right.is_empty() {
+        return;
+    }
+    let region = if left == base {
+        MergeRegion::Resolved {
+            side: MergeSide::Right,
+            text: right.concat(),
+        }
+    } else if right == base {
+        MergeRegion::Resolved {
+            side: MergeSide::Left,
+            text: left.concat(),
+        }
+    } else if left == right {
+        MergeRegion::Resolved {
+            side: MergeSide::Both,
+            text: left.concat(),
+        }
+    } else {
+        MergeRegion::Conflict {
+            base: base.concat(),
+            left: left.concat(),
+            right: right.concat(),
+        }
+    };
+    regions.push(region);
+}
+
+fn push_section(output: &mut String, text: &str, eol: &str) {
+    output.push_str(text);
+    if !text.is_empty() && !text.ends_with('\n') {
+        output.push_str(eol);
+    }
+}
+
+fn render(regions: &[MergeRegion], options: &MergeOptions, eol: &str) -> String {
+    let mut output = String::new();
+    for region in regions {
+        match region {
+            MergeRegion::Unchanged { text } | MergeRegion::Resolved { text, .. } => {
+                output.push_str(text);
+            }
+            MergeRegion::Conflict { base, left, right } => {
+                if !output.is_empty() && !output.ends_with('\n') {
+                    output.push_str(eol);
+                }
+                output.push_str(&format!("<<<<<<< {}{eol}", options.left_label));
+                push_section(&mut output, left, eol);
+                if options.show_base {
+                    output.push_str(&format!("||||||| {}{eol}", options.base_label));
+                    push_section(&mut output, base, eol);
+                }
+                output.push_str(&format!("======={eol}"));
+                push_section(&mut output, right, eol);
+                output.push_str(&format!(">>>>>>> {}{eol}", options.right_label));
+            }
+        }
+    }
+    output
+}
+
+/// Three-way line merge of `left` and `right` against their common `base`.
+///
+/// Walks the base text alongside both sides (diff3 style): runs of base lines matched
+/// by both sides are stable, everything between them is a change region that is taken
+/// from whichever side touched it, or reported as a conflict when both did.
+pub(crate) fn merge(base: &str, left: &str, right: &str, options: &MergeOptions) -> MergeResult {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let left_lines: Vec<&str> = left.split_inclusive('\n').collect();
+    let right_lines: Vec<&str> = right.split_inclusive('\n').collect();
+
+    let deadline = Instant::now() + MERGE_TIMEOUT;
+    let left_matches = match_lines(&base_lines, &left_lines, deadline);
+    let right_matches = match_lines(&base_lines, &right_lines, deadline);
+
+    let mut regions = Vec::new();
+    let (mut base_pos, mut left_pos, mut right_pos) = (0, 0, 0);
+    loop {
+        let mut stable = 0;
+        while base_pos + stable < base_lines.len()
+            && left_matches[base_pos + stable] == Some(left_pos + stable)
+            && right_matches[base_pos + stable] == Some(right_pos + stable)
+        {
+            stable += 1;
+        }
+        if stable > 0 {
+            push_unchanged(&mut regions, &base_lines[base_pos..base_pos + stable]);
+            base_pos += stable;
+            left_pos += stable;
+            right_pos += stable;
+            continue;
+        }
+
+        let next = (base_pos..base_lines.len())
+            .find_map(|index| Some((index, left_matches[index]?, right_matches[index]?)));
+        let (base_end, left_end, right_end) =
+            next.unwrap_or((base_lines.len(), left_lines.len(), right_lines.len()));
+        push_change(
+            &mut regions,
+            &base_lines[base_pos..base_end],
+            &left_lines[left_pos..left_end],
+            &right_lines[right_pos..right_end],
+        );
+        if next.is_none() {
+            break;
+        }
+        base_pos = base_end;
+        left_pos = left_end;
+        right_pos = right_end;
+    }
+
+    let eol = if [base, left, right].iter().any(|text| text.contains("\r\n")) {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let conflicts = regions
+        .iter()
+        .filter(|region| matches!(region, MergeRegion::Conflict { .. }))
+        .count();
+    let merged = render(&regions, options, eol);
+
+    MergeResult {
+        regions,
+        conflicts,
+        merged,
+    }
+}
+
+/// A `base left right -o output` launch from the command line.
+#[derive(Clone, Debug)]
+pub(crate) struct MergeLaunch {
+    pub(crate) base: PathBuf,
+    pub(crate) left: PathBuf,
+    pub(crate) right: PathBuf,
+    pub(crate) output: PathBuf,
+    /// Write the result and exit without a window when there are no conflicts.
+    pub(crate) auto: bool,
+}
+
+fn absolute_path(value: OsString) -> PathBuf {
+    let path = PathBuf::from(value);
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+/// Recognizes `base left right -o output` (in any order, `--output` also accepted).
+///
+/// Returns `None` for anything else so the normal two-file startup path applies.
+pub(crate) fn parse_merge_args<I>(args: I) -> Option<MergeLaunch>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut args = args.into_iter();
+    let mut positional = Vec::new();
+    let mut output = None;
+    let mut auto = false;
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("-o" | "--output") => output = Some(absolute_path(args.next()?)),
+            Some("--auto") => auto = true,
+            _ => positional.push(absolute_path(arg)),
+        }
+    }
+
+    let output = output?;
+    let [base, left, right]: [PathBuf; 3] = positional.try_into().ok()?;
+    Some(MergeLaunch {
+        base,
+        left,
+        right,
+        output,
+        auto,
+    })
+}
+
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+/// Reads a merge input like the editor does; line endings come back as `\n`.
+fn read_input(path: &Path) -> Result<textfile::TextFile, String> {
+    let file = textfile::read(path)?;
+    if file.binary {
+        return Err(format!("{}: binary files can't be merged", path.display()));
+    }
+    Ok(file)
+}
+
+/// Merge state for a window opened via [`MergeLaunch`].
+#[derive(Debug)]
+pub(crate) struct MergeSession {
+    pub(crate) launch: MergeLaunch,
+    /// Set once the result has been written to the output path.
+    pub(crate) resolved: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MergeSessionInfo {
+    pub(crate) base_path: String,
+    pub(crate) left_path: String,
+    pub(crate) right_path: String,
+    pub(crate) output_path: String,
+    pub(crate) result: MergeResult,
+}
+
+impl MergeSession {
+    pub(crate) fn new(launch: MergeLaunch) -> Self {
+        Self {
+            launch,
+            resolved: false,
+        }
+    }
+
+    fn options(&self) -> MergeOptions {
+        MergeOptions {
+            left_label: file_label(&self.launch.left),
+            base_label: file_label(&self.launch.base),
+            right_label: file_label(&self.launch.right),
+            show_base: false,
+        }
+    }
+
+    /// Reads the three inputs and merges them.
+    pub(crate) fn load(&self) -> Result<MergeSessionInfo, String> {
+        let launch = &self.launch;
+        let base = read_input(&launch.base)?.text;
+        let left = read_input(&launch.left)?.text;
+        let right = read_input(&launch.right)?.text;
+        let result = merge(&base, &left, &right, &self.options());
+        log::info!(
+            "merge session loaded output={} regions={} conflicts={}",
+            launch.output.display(),
+            result.regions.len(),
+            result.conflicts
+        );
+        Ok(MergeSessionInfo {
+            base_path: launch.base.to_string_lossy().to_string(),
+            left_path: launch.left.to_string_lossy().to_string(),
+            right_path: launch.right.to_string_lossy().to_string(),
+            output_path: launch.output.to_string_lossy().to_string(),
+            result,
+        })
+    }
+
+    /// Writes the resolved text to the output path in the encoding, BOM and line ending
+    /// of the left input (git's `$LOCAL`), and marks the session resolved.
+    pub(crate) fn finish(&mut self, contents: &str) -> Result<(), String> {
+        let meta = read_input(&self.launch.left)?.meta;
+        textfile::write(&self.launch.output, contents, &meta)?;
+        self.resolved = true;
+        log::info!(
+            "merge result written output={}",
+            self.launch.output.display()
+        );
+        Ok(())
+    }
+
+    /// For `--auto` launches: writes the merge straight away when it has no conflicts.
+    ///
+    /// Returns whether the output was written.
+    pub(crate) fn try_auto_resolve(&mut self) -> Result<bool, String> {
+        let info = self.load()?;
+        if info.result.conflicts > 0 {
+            return Ok(false);
+        }
+        self.finish(&info.result.merged)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(base: &str, left: &str, right: &str) -> MergeResult {
+        merge(base, left, right, &MergeOptions::default())
+    }
+
+    #[test]
+    fn changes_on_different_lines_merge_cleanly() {
+        let result = merged("a\nb\nc\n", "A\nb\nc\n", "a\nb\nC\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.merged, "A\nb\nC\n");
+    }
+
+    #[test]
+    fn different_changes_to_one_line_conflict() {
+        let result = merged("a\nb\nc\n", "a\nleft\nc\n", "a\nright\nc\n");
+        assert_eq!(result.conflicts, 1);
+        assert_eq!(
+            result.merged,
+            "a\n<<<<<<< LEFT\nleft\n=======\nright\n>>>>>>> RIGHT\nc\n"
+        );
+    }
+
+    #[test]
+    fn identical_changes_on_both_sides_resolve_once() {
+        let result = merged("a\nb\nc\n", "a\nB\nc\n", "a\nB\nc\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.merged, "a\nB\nc\n");
+        assert!(result.regions.iter().any(|region| matches!(
+            region,
+            MergeRegion::Resolved {
+                side: MergeSide::Both,
+                ..
+            }
+        )));
+    }
+
+    #[test]
+    fn insertion_at_end_of_file_is_kept() {
+        let result = merged("a\nb\n", "a\nb\n", "a\nb\nc\n");
+        assert_eq!(result.conflicts, 0);
+        assert_eq!(result.merged, "a\nb\nc\n");
+
+        let result = merged("a\nb\n", "a\nb\nleft\n", "a\nb\nright\n");
+        assert_eq!(result.conflicts, 1);
+    }
+
+    #[test]
+    fn finish_writes_the_encoding_and_line_endings_of_local() {
+        let dir = std::env::temp_dir().join(format!("gcompare-merge-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let utf16 = |text: &str| {
+            let mut bytes = vec![0xFF, 0xFE];
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        };
+        let left = dir.join("left.txt");
+        std::fs::write(&left, utf16("a\r\nb\r\n")).unwrap();
+        let output = dir.join("merged.txt");
+        let mut session = MergeSession::new(MergeLaunch {
+            base: left.clone(),
+            left: left.clone(),
+            right: left,
+            output: output.clone(),
+            auto: false,
+        });
+
+        session.finish("a\nb\u{e9}\n").unwrap();
+        let written = std::fs::read(&output).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(session.resolved);
+        assert_eq!(written, utf16("a\r\nb\u{e9}\r\n"));
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+use tauri_plugin_log::log;
+
+use crate::wait::PendingWaits;
+
+/// Single-file launches this close together form one pair, like selecting two files in
+/// Explorer and choosing "Open with", which starts the app once per file.
+const PAIR_WINDOW: Duration = Duration::from_millis(500);
+
+/// One comparison pair, shown as a tab in the window.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CompareSession {
+    pub(crate) id: u64,
+    /// Up to two files or folders, left first.
+    pub(crate) paths: Vec<String>,
+    /// The `--wait` launcher released when this session is closed.
+    pub(crate) wait_id: Option<u64>,
+}
+
+#[derive(Default)]
+struct SessionState {
+    next_id: u64,
+    sessions: Vec<CompareSession>,
+    /// Sessions the frontend has not picked up yet; it may still be loading.
+    pending: Vec<u64>,
+    /// The last session opened from outside and when, for pairing single-file launches.
+    last_external: Option<(u64, Instant)>,
+}
+
+impl SessionState {
+    fn create(&mut self, paths: Vec<String>, wait_id: Option<u64>) -> CompareSession {
+        self.next_id += 1;
+        let session = CompareSession {
+            id: self.next_id,
+            paths,
+            wait_id,
+        };
+        self.sessions.push(session.clone());
+        session
+    }
+
+    fn get_mut(&mut self, id: u64) -> Option<&mut CompareSession> {
+        self.sessions.iter_mut().find(|session| session.id == id)
+    }
+
+    /// Adds a lone path to the previous single-path session if it arrived just before.
+    fn pair_with_last(&mut self, paths: &[String]) -> Option<CompareSession> {
+        let [path] = paths else {
+            return None;
+        };
+        let (id, opened_at) = self.last_external?;
+        if opened_at.elapsed() > PAIR_WINDOW {
+            return None;
+        }
+        let session = self.get_mut(id)?;
+        if session.paths.len() != 1 || session.wait_id.is_some() {
+            return None;
+        }
+        session.paths.push(path.clone());
+        Some(session.clone())
+    }
+}
+
+/// Every open comparison; the frontend mirrors these as tabs.
+#[derive(Default)]
+pub(crate) struct CompareSessions(Mutex<SessionState>);
+
+impl CompareSessions {
+    fn lock(&self) -> std::sync::MutexGuard<'_, SessionState> {
+        self.0.lock().expect("compare sessions lock")
+    }
+
+    pub(crate) fn create(&self, paths: Vec<String>) -> CompareSession {
+        self.lock().create(paths, None)
+    }
+
+    /// Records a session for paths handed over by the OS or another launch. It stays
+    /// pending until [`CompareSessions::consume_pending`] in case no window listens yet.
+    pub(crate) fn open_external(&self, paths: Vec<String>, wait_id: Option<u64>) -> CompareSession {
+        let mut state = self.lock();
+        // A waiting launcher gets a tab of its own so closing it releases only that one.
+        let paired = match wait_id {
+            Some(_) => None,
+            None => state.pair_with_last(&paths),
+        };
+        let session = paired.unwrap_or_else(|| state.create(paths, wait_id));
+        if !state.pending.contains(&session.id) {
+            state.pending.push(session.id);
+        }
+        state.last_external = Some((session.id, Instant::now()));
+        session
+    }
+
+    pub(crate) fn consume_pending(&self) -> Vec<CompareSession> {
+        let mut state = self.lock();
+        let pending = std::mem::take(&mut state.pending);
+        pending
+            .into_iter()
+            .filter_map(|id| state.get_mut(id).map(|session| session.clone()))
+            .collect()
+    }
+
+    pub(crate) fn update(&self, id: u64, paths: Vec<String>) -> Result<(), String> {
+        let mut state = self.lock();
+        let session = state
+            .get_mut(id)
+            .ok_or_else(|| format!("Unknown session: {id}"))?;
+        session.paths = paths;
+        Ok(())
+    }
+
+    /// Forgets the session, returning it so its `--wait` launcher can be released.
+    pub(crate) fn close(&self, id: u64) -> Option<CompareSession> {
+        let mut state = self.lock();
+        state.pending.retain(|pending| *pending != id);
+        let index = state.sessions.iter().position(|session| session.id == id)?;
+        Some(state.sessions.remove(index))
+    }
+}
+
+/// Opens `paths` from outside the window as a session and tells the frontend about it.
+pub(crate) fn open_external(app: &tauri::AppHandle, paths: Vec<String>, wait_id: Option<u64>) {
+    let session = app.state::<CompareSessions>().open_external(paths, wait_id);
+    log::info!(
+        "session opened id={} paths={:?} wait={:?}",
+        session.id,
+        session.paths,
+        session.wait_id
+    );
+    let _ = app.emit("gcompare://open-files", session);
+}
+
+/// Closes a session and releases the launcher waiting on it, if any.
+pub(crate) fn close(app: &tauri::AppHandle, id: u64) {
+    let Some(session) = app.state::<CompareSessions>().close(id) else {
+        return;
+    };
+    log::info!("session closed id={id}");
+    if let Some(wait_id) = session.wait_id {
+        app.state::<PendingWaits>().release(wait_id);
+    }
+}
@@ -0,0 +1,259 @@
+use std::path::Path;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, GB18030, GBK, UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Deserialize, Serialize};
+use tauri_plugin_log::log;
+
+use crate::bincmp;
+
+/// Bytes inspected when guessing a BOM-less UTF-16 file.
+const UTF16_SNIFF_LEN: usize = 4096;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+/// How a text file was stored on disk, so it can be written back byte-for-byte.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct TextFileMeta {
+    /// WHATWG encoding name, e.g. `UTF-8`, `UTF-16LE`, `gb18030`, `Shift_JIS`.
+    pub(crate) encoding: String,
+    pub(crate) bom: bool,
+    /// Dominant line ending; the text itself always uses `\n`.
+    pub(crate) line_ending: LineEnding,
+    /// The file mixes line endings, so saving normalizes them to `line_ending`.
+    pub(crate) mixed_line_endings: bool,
+    /// Some bytes were not valid in `encoding` and were replaced while decoding.
+    pub(crate) had_errors: bool,
+}
+
+impl Default for TextFileMeta {
+    fn default() -> Self {
+        Self {
+            encoding: UTF_8.name().to_string(),
+            bom: false,
+            line_ending: LineEnding::Lf,
+            mixed_line_endings: false,
+            had_errors: false,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TextFile {
+    pub(crate) text: String,
+    pub(crate) meta: TextFileMeta,
+    pub(crate) size: u64,
+    /// Binary content is not decoded; `text` is empty.
+    pub(crate) binary: bool,
+}
+
+/// Guesses UTF-16 without a BOM from the NUL bytes of mostly-ASCII text.
+pub(crate) fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(UTF16_SNIFF_LEN) & !1];
+    if sample.len() < 4 {
+        return None;
+    }
+    let pairs = sample.len() / 2;
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = sample
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+    if odd_zeros * 10 >= pairs * 4 && even_zeros * 10 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 >= pairs * 4 && odd_zeros * 10 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// Picks the encoding of `bytes`, returning it with the length of its BOM (0 if none).
+fn detect_encoding(bytes: &[u8]) -> (&'static Encoding, usize) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        return (encoding, bom_len);
+    }
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return (encoding, 0);
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return (UTF_8, 0);
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, false);
+    // GBK decoding already accepts the full GB18030 range; encode with GB18030 too so
+    // four-byte sequences survive a save.
+    let encoding = if encoding == GBK { GB18030 } else { encoding };
+    (encoding, 0)
+}
+
+/// Normalizes line endings to `\n`, returning the dominant original style and whether
+/// several styles were mixed.
+fn normalize_line_endings(text: &str) -> (String, LineEnding, bool) {
+    let (mut lf, mut crlf, mut cr) = (0usize, 0usize, 0usize);
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' if chars.peek() == Some(&'\n') => {
+                chars.next();
+                crlf += 1;
+                output.push('\n');
+            }
+            '\r' => {
+                cr += 1;
+                output.push('\n');
+            }
+            '\n' => {
+                lf += 1;
+                output.push('\n');
+            }
+            _ => output.push(c),
+        }
+    }
+
+    let line_ending = if crlf >= lf && crlf >= cr && crlf > 0 {
+        LineEnding::Crlf
+    } else if cr > lf {
+        LineEnding::Cr
+    } else {
+        LineEnding::Lf
+    };
+    let styles = [lf, crlf, cr].iter().filter(|&&count| count > 0).count();
+    (output, line_ending, styles > 1)
+}
+
+/// Decodes file contents, detecting their encoding, BOM and line-ending style.
+pub(crate) fn decode(bytes: &[u8]) -> TextFile {
+    if bincmp::is_binary(bytes) {
+        return TextFile {
+            text: String::new(),
+            meta: TextFileMeta::default(),
+            size: bytes.len() as u64,
+            binary: true,
+        };
+    }
+    let (encoding, bom_len) = detect_encoding(bytes);
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    let (text, line_ending, mixed_line_endings) = normalize_line_endings(&decoded);
+    TextFile {
+        text,
+        meta: TextFileMeta {
+            encoding: encoding.name().to_string(),
+            bom: bom_len > 0,
+            line_ending,
+            mixed_line_endings,
+            had_errors,
+        },
+        size: bytes.len() as u64,
+        binary: false,
+    }
+}
+
+/// Decodes only the charset of text `bytes` and leaves line endings untouched, for
+/// callers that must tell `\r\n` from `\n`. Returns the encoding name and whether
+/// invalid bytes were replaced.
+pub(crate) fn decode_charset(bytes: &[u8]) -> (String, &'static str, bool) {
+    let (encoding, bom_len) = detect_encoding(bytes);
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    (decoded.into_owned(), encoding.name(), had_errors)
+}
+
+/// Reads a text file, detecting its encoding, BOM and line-ending style.
+pub(crate) fn read(path: &Path) -> Result<TextFile, String> {
+    let bytes = std::fs::read(path).map_err(|error| format!("{}: {error}", path.display()))?;
+    let file = decode(&bytes);
+    let meta = &file.meta;
+    log::info!(
+        "text file read path={} binary={} encoding={} bom={} line_ending={:?} mixed={} errors={}",
+        path.display(),
+        file.binary,
+        meta.encoding,
+        meta.bom,
+        meta.line_ending,
+        meta.mixed_line_endings,
+        meta.had_errors
+    );
+    Ok(file)
+}
+
+/// Encodes `text` the way `meta` describes, converting every line break to its style.
+fn encode(text: &str, meta: &TextFileMeta) -> Result<Vec<u8>, String> {
+    let encoding = Encoding::for_label(meta.encoding.as_bytes())
+        .ok_or_else(|| format!("Unsupported encoding: {}", meta.encoding))?;
+    let (text, _, _) = normalize_line_endings(text);
+    let text = match meta.line_ending {
+        LineEnding::Lf => text,
+        ending => text.replace('\n', ending.as_str()),
+    };
+
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    // encoding_rs only decodes UTF-16; its encoder falls back to UTF-8 for them.
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let little_endian = encoding == UTF_16LE;
+        if meta.bom {
+            bytes.extend_from_slice(if little_endian {
+                &[0xFF, 0xFE]
+            } else {
+                &[0xFE, 0xFF]
+            });
+        }
+        for unit in text.encode_utf16() {
+            let pair = if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            };
+            bytes.extend_from_slice(&pair);
+        }
+        return Ok(bytes);
+    }
+
+    if meta.bom && encoding == UTF_8 {
+        bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+    }
+    let (encoded, _, had_errors) = encoding.encode(&text);
+    if had_errors {
+        return Err(format!(
+            "The text contains characters that can't be saved as {}.",
+            encoding.name()
+        ));
+    }
+    bytes.extend_from_slice(&encoded);
+    Ok(bytes)
+}
+
+/// Writes `text` back with the encoding, BOM and line endings recorded in `meta`.
+pub(crate) fn write(path: &Path, text: &str, meta: &TextFileMeta) -> Result<(), String> {
+    let bytes = encode(text, meta)?;
+    std::fs::write(path, &bytes).map_err(|error| format!("{}: {error}", path.display()))?;
+    log::info!(
+        "text file written path={} encoding={} bytes={}",
+        path.display(),
+        meta.encoding,
+        bytes.len()
+    );
+    Ok(())
+}
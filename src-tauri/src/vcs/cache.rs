@@ -0,0 +1,108 @@
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri_plugin_log::log;
+
+/// Bumped whenever a cached result type changes shape, so old files are ignored.
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct CachedValue<T> {
+    version: u32,
+    /// Head revision (and working-file digest) the value was computed at.
+    revision: String,
+    value: T,
+}
+
+/// On-disk cache of provider results such as history and blame.
+///
+/// Each entry is one JSON file per kind and identity (provider, file and requested
+/// revision). It stores the head revision it was computed at, so a moved head is a
+/// miss and the next result simply overwrites the stale file.
+#[derive(Default)]
+pub(crate) struct VcsCache {
+    dir: OnceLock<PathBuf>,
+}
+
+impl VcsCache {
+    /// Enables the cache; until then every lookup misses and nothing is written.
+    pub(crate) fn set_dir(&self, dir: PathBuf) {
+        log::info!("vcs cache dir={}", dir.display());
+        let _ = self.dir.set(dir);
+    }
+
+    fn entry_path(&self, kind: &str, identity: &str) -> Option<PathBuf> {
+        let dir = self.dir.get()?;
+        Some(
+            dir.join(kind)
+                .join(format!("{}.json", hex_digest(identity.as_bytes()))),
+        )
+    }
+
+    pub(crate) fn load<T: DeserializeOwned>(
+        &self,
+        kind: &str,
+        identity: &str,
+        revision: &str,
+    ) -> Option<T> {
+        let path = self.entry_path(kind, identity)?;
+        let bytes = std::fs::read(&path).ok()?;
+        let cached: CachedValue<T> = match serde_json::from_slice(&bytes) {
+            Ok(cached) => cached,
+            Err(error) => {
+                log::warn!("vcs cache unreadable path={} error={error}", path.display());
+                return None;
+            }
+        };
+        (cached.version == CACHE_VERSION && cached.revision == revision).then_some(cached.value)
+    }
+
+    pub(crate) fn store<T: Serialize>(
+        &self,
+        kind: &str,
+        identity: &str,
+        revision: &str,
+        value: &T,
+    ) {
+        let Some(path) = self.entry_path(kind, identity) else {
+            return;
+        };
+        let cached = CachedValue {
+            version: CACHE_VERSION,
+            revision: revision.to_string(),
+            value,
+        };
+        let written = serde_json::to_vec(&cached)
+            .map_err(|error| error.to_string())
+            .and_then(|bytes| write_atomic(&path, &bytes).map_err(|error| error.to_string()));
+        if let Err(error) = written {
+            log::warn!(
+                "vcs cache write failed path={} error={error}",
+                path.display()
+            );
+        }
+    }
+}
+
+/// Writes through a temp file so a concurrent reader never sees half a file.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+    std::fs::write(&temp, bytes)?;
+    std::fs::rename(&temp, path)
+}
+
+pub(crate) fn hex_digest(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    let mut hex = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::io;
+use std::process::Output;
+
+use serde::Serialize;
+
+/// Failure of a VCS operation, classified so the frontend can react to the cause
+/// (offer a login, point at an install) instead of parsing localized CLI output.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub(crate) enum VcsError {
+    /// The tool's executable could not be found.
+    NotInstalled { tool: String },
+    /// The path is not inside a repository, working copy or client of this tool.
+    NotARepository { message: String },
+    /// The path is inside the working copy but the tool does not track it.
+    Untracked { message: String },
+    /// The server refused the request until the user logs in (again).
+    AuthRequired { tool: String, message: String },
+    /// The tool could not connect to its server.
+    ServerUnreachable { tool: String, message: String },
+    /// A revision, changelist or other spec did not resolve.
+    InvalidRevision { message: String },
+    /// The command ran and failed for any other reason.
+    CommandFailed {
+        tool: String,
+        stderr: String,
+        status: Option<i32>,
+    },
+    /// The request was cancelled while a command was running.
+    Cancelled,
+    /// A command ran past the configured timeout.
+    TimedOut { message: String },
+    /// A failure outside the command itself, e.g. an invalid path or unreadable output.
+    Other { message: String },
+}
+
+impl VcsError {
+    /// Maps a failure to start or wait for `tool`.
+    pub(crate) fn launch(tool: &str, error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NotInstalled {
+                tool: tool.to_string(),
+            },
+            io::ErrorKind::Interrupted => Self::Cancelled,
+            io::ErrorKind::TimedOut => Self::TimedOut {
+                message: format!("{tool}: {error}"),
+            },
+            _ => Self::Other {
+                message: format!("Failed to run {tool}: {error}"),
+            },
+        }
+    }
+
+    /// A command that exited unsuccessfully; `message` is what it printed.
+    pub(crate) fn failed(tool: &str, output: &Output, message: String) -> Self {
+        Self::CommandFailed {
+            tool: tool.to_string(),
+            stderr: message,
+            status: output.status.code(),
+        }
+    }
+
+    /// Reinterprets a failed command whose failure can only mean one thing at this
+    /// call site, e.g. `rev-parse --show-toplevel` outside a repository. Launch
+    /// problems, cancellation and already classified errors are kept as they are.
+    pub(crate) fn or_else_failed(self, kind: impl FnOnce(String) -> Self) -> Self {
+        match self {
+            Self::CommandFailed { stderr, .. } => kind(stderr),
+            other => other,
+        }
+    }
+
+    /// Whether the request was stopped, so no other provider should be tried.
+    pub(crate) fn is_interruption(&self) -> bool {
+        matches!(self, Self::Cancelled | Self::TimedOut { .. })
+    }
+
+    /// Whether this only means "this file is not managed by this provider".
+    pub(crate) fn is_no_history(&self) -> bool {
+        matches!(self, Self::NotARepository { .. } | Self::Untracked { .. })
+    }
+}
+
+impl fmt::Display for VcsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotInstalled { tool } => {
+                write!(f, "{tool} is not installed or not available on PATH.")
+            }
+            Self::CommandFailed {
+                tool,
+                stderr,
+                status,
+            } => {
+                if !stderr.is_empty() {
+                    f.write_str(stderr)
+                } else if let Some(status) = status {
+                    write!(f, "{tool} exited with status {status}")
+                } else {
+                    write!(f, "{tool} was terminated")
+                }
+            }
+            Self::Cancelled => f.write_str("The request was cancelled."),
+            Self::NotARepository { message }
+            | Self::Untracked { message }
+            | Self::AuthRequired { message, .. }
+            | Self::ServerUnreachable { message, .. }
+            | Self::InvalidRevision { message }
+            | Self::TimedOut { message }
+            | Self::Other { message } => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for VcsError {
+    fn from(message: String) -> Self {
+        Self::Other { message }
+    }
+}
+
+impl From<&str> for VcsError {
+    fn from(message: &str) -> Self {
+        Self::Other {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<VcsError> for String {
+    fn from(error: VcsError) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,458 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tauri_plugin_log::log;
+use time::{format_description, Date, PrimitiveDateTime};
+
+use super::{
+    decode_text, find_ancestor_with, runner, to_git_path, truncate_for_log, working_dir,
+    working_file_parent, BlameEntry, BlameRequest, BlameResult, ChangesetRequest, ShowRequest,
+    VcsChangeKind, VcsChangeset, VcsChangesetFile, VcsError, VcsHistoryEntry, VcsHistoryResult,
+    VcsProvider, VcsRevisionFile, VcsStatusEntry, VcsStatusResult,
+};
+
+pub(crate) struct FossilProvider;
+
+const FIELD_SEP: char = '\x1f';
+/// `fossil timeline -F` format: full check-in hash, date, user and one-line comment.
+const TIMELINE_FORMAT: &str = "%H\x1f%d\x1f%a\x1f%c";
+/// Length of the abbreviated check-in hashes printed by `fossil blame`.
+const SHORT_HASH_LEN: usize = 10;
+
+pub(crate) fn run_fossil<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<String, VcsError> {
+    run_fossil_bytes(args, cwd).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like [`run_fossil`], but returns stdout untouched (file contents may be binary).
+pub(crate) fn run_fossil_bytes<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<Vec<u8>, VcsError> {
+    let output = runner::output(Command::new("fossil").current_dir(cwd).args(args), None)
+        .map_err(|error| VcsError::launch("fossil", error))?;
+
+    if !output.status.success() {
+        // Fossil reports some errors on stdout.
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let message = if stderr.is_empty() { stdout } else { stderr };
+        let error = VcsError::failed("fossil", &output, message);
+        log::warn!(
+            "fossil failed cwd={} args={args:?} error={error}",
+            cwd.display()
+        );
+        return Err(error);
+    }
+
+    Ok(output.stdout)
+}
+
+/// Value of a `key: value` line in `fossil info` output.
+fn info_value<'a>(info: &'a str, key: &str) -> Option<&'a str> {
+    info.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim())
+    })
+}
+
+fn resolve_checkout_root(cwd: &Path) -> Result<PathBuf, VcsError> {
+    let info = run_fossil(&["info"], cwd)
+        .map_err(|error| error.or_else_failed(|message| VcsError::NotARepository { message }))?;
+    let root = info_value(&info, "local-root")
+        .map(|root| root.trim_end_matches(['/', '\\']))
+        .unwrap_or_default();
+    if root.is_empty() {
+        return Err(VcsError::NotARepository {
+            message: "Unable to resolve repository root.".to_string(),
+        });
+    }
+    Ok(PathBuf::from(root))
+}
+
+/// Resolves the checkout root and the checkout-relative path of a working file.
+fn resolve_repo_path(file_path: &Path) -> Result<(PathBuf, String), VcsError> {
+    let parent = working_file_parent(file_path)?;
+    let repo_root = resolve_checkout_root(parent)?;
+    let relative_path =
+        file_path
+            .strip_prefix(&repo_root)
+            .map_err(|_| VcsError::NotARepository {
+                message: "File is not inside the repository.".to_string(),
+            })?;
+    Ok((repo_root, to_git_path(relative_path)))
+}
+
+/// Resolves a check-in name (hash prefix, tag, branch, `current`, `prev`, date) to its
+/// full hash.
+fn resolve_checkin(repo_root: &Path, revision: &str) -> Result<String, VcsError> {
+    let revision = revision.trim();
+    if revision.is_empty() || revision.starts_with('-') {
+        return Err(VcsError::InvalidRevision {
+            message: "Invalid revision.".to_string(),
+        });
+    }
+    let info = run_fossil(&["info", revision], repo_root).map_err(|error| {
+        error.or_else_failed(|_| VcsError::InvalidRevision {
+            message: format!("Unknown revision: {revision}"),
+        })
+    })?;
+    // Older releases label the check-in hash `uuid`.
+    info_value(&info, "hash")
+        .or_else(|| info_value(&info, "uuid"))
+        .and_then(|value| value.split_whitespace().next())
+        .map(str::to_string)
+        .ok_or_else(|| VcsError::InvalidRevision {
+            message: format!("Unknown revision: {revision}"),
+        })
+}
+
+/// Seconds from a timeline date, `YYYY-MM-DD HH:MM:SS` (UTC) or just the day.
+fn parse_fossil_time(value: &str) -> i64 {
+    let value = value.trim();
+    let parse_datetime = || {
+        let format =
+            format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").ok()?;
+        PrimitiveDateTime::parse(value.get(..19)?, &format).ok()
+    };
+    let parse_day = || {
+        let format = format_description::parse("[year]-[month]-[day]").ok()?;
+        Date::parse(value.get(..10)?, &format)
+            .ok()
+            .map(|date| date.midnight())
+    };
+    parse_datetime()
+        .or_else(parse_day)
+        .map(|datetime| datetime.assume_utc().unix_timestamp())
+        .unwrap_or(0)
+}
+
+/// Parses one `TIMELINE_FORMAT` line into `(hash, timestamp, user, comment)`.
+fn parse_timeline_line(line: &str) -> Option<(String, i64, String, String)> {
+    let mut fields = line.splitn(4, FIELD_SEP);
+    let hash = fields.next()?.trim();
+    let timestamp = parse_fossil_time(fields.next()?);
+    let user = fields.next()?;
+    let comment = fields.next()?;
+    Some((
+        hash.to_string(),
+        timestamp,
+        user.to_string(),
+        comment.trim().to_string(),
+    ))
+}
+
+/// Check-ins touching `relative_path`, newest first.
+fn load_timeline(repo_root: &Path, relative_path: &str) -> Result<Vec<VcsHistoryEntry>, VcsError> {
+    let output = run_fossil(
+        &[
+            "timeline",
+            "-t",
+            "ci",
+            "-n",
+            "0",
+            "-W",
+            "0",
+            "-F",
+            TIMELINE_FORMAT,
+            "-p",
+            relative_path,
+        ],
+        repo_root,
+    )?;
+    let entries: Vec<VcsHistoryEntry> = output
+        .lines()
+        .filter_map(parse_timeline_line)
+        .map(|(hash, timestamp, author, summary)| VcsHistoryEntry {
+            provider: "fossil".to_string(),
+            hash,
+            timestamp,
+            author,
+            summary,
+            path: relative_path.to_string(),
+            deleted: false,
+            integrations: Vec::new(),
+        })
+        .collect();
+    if entries.is_empty() && !output.trim().is_empty() {
+        log::warn!(
+            "fossil history parsed 0 entries path={relative_path} output_preview={}",
+            truncate_for_log(&output, 4000)
+        );
+    }
+    Ok(entries)
+}
+
+/// Parses `fossil changes`/verbose timeline lines such as `EDITED  src/main.c`.
+fn parse_change_line(line: &str) -> Option<(&str, String)> {
+    let line = line.trim();
+    let (status, path) = line.split_once(char::is_whitespace)?;
+    if status.is_empty() || !status.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+        return None;
+    }
+    Some((status, path.trim().to_string()))
+}
+
+impl VcsProvider for FossilProvider {
+    fn id(&self) -> &'static str {
+        "fossil"
+    }
+
+    fn label(&self) -> &'static str {
+        "Fossil"
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        // The checkout database is `_FOSSIL_` on Windows and `.fslckout` elsewhere.
+        find_ancestor_with(path, ".fslckout").is_some()
+            || find_ancestor_with(path, "_FOSSIL_").is_some()
+    }
+
+    fn history(&self, file_path: &Path) -> Result<VcsHistoryResult, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+        let files = run_fossil(&["ls", relative_path.as_str()], &repo_root)?;
+        if files.trim().is_empty() {
+            return Err(VcsError::Untracked {
+                message: "File is not tracked in Fossil.".to_string(),
+            });
+        }
+
+        let entries = load_timeline(&repo_root, &relative_path)?;
+        Ok(VcsHistoryResult {
+            provider: "fossil".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            relative_path,
+            entries,
+        })
+    }
+
+    fn blame(&self, request: &BlameRequest) -> Result<BlameResult, VcsError> {
+        let (repo_root, relative_path) = match &request.repo_root {
+            Some(root) => (PathBuf::from(root), request.path.replace('\\', "/")),
+            None => resolve_repo_path(Path::new(&request.path))?,
+        };
+        let mut args = vec!["blame"];
+        if let Some(revision) = &request.revision {
+            args.push("-r");
+            args.push(revision.as_str());
+        }
+        args.push(relative_path.as_str());
+        let output = run_fossil(&args, &repo_root)?;
+
+        // Blame only prints abbreviated hashes and the day; the timeline fills in the
+        // full hash, time and comment.
+        let timeline = load_timeline(&repo_root, &relative_path).unwrap_or_default();
+        let entries: Vec<BlameEntry> = output
+            .lines()
+            .filter_map(|line| {
+                // `<hash> <date> <user>: <text>`
+                let (meta, _) = line.split_once(": ")?;
+                let mut fields = meta.split_whitespace();
+                let hash = fields.next()?;
+                let date = fields.next()?;
+                let user = fields.collect::<Vec<_>>().join(" ");
+                Some((hash, date, user))
+            })
+            .enumerate()
+            .map(|(index, (hash, date, author))| {
+                let prefix = &hash[..hash.len().min(SHORT_HASH_LEN)];
+                let known = timeline.iter().find(|entry| entry.hash.starts_with(prefix));
+                BlameEntry {
+                    line: index + 1,
+                    hash: known.map_or_else(|| hash.to_string(), |entry| entry.hash.clone()),
+                    author,
+                    timestamp: known
+                        .map_or_else(|| parse_fossil_time(date), |entry| entry.timestamp),
+                    summary: known.map(|entry| entry.summary.clone()).unwrap_or_default(),
+                }
+            })
+            .collect();
+        log::info!("fossil blame path={relative_path} lines={}", entries.len());
+
+        Ok(BlameResult {
+            provider: "fossil".to_string(),
+            entries,
+        })
+    }
+
+    fn show_bytes(&self, request: &ShowRequest) -> Result<Vec<u8>, VcsError> {
+        let repo_root = request
+            .repo_root
+            .as_deref()
+            .map(PathBuf::from)
+            .ok_or_else(|| "Repository root is required.".to_string())?;
+        if !repo_root.is_dir() {
+            return Err("Repository root does not exist.".into());
+        }
+        let path = request.path.replace('\\', "/");
+        run_fossil_bytes(
+            &["cat", "-r", request.revision.as_str(), path.as_str()],
+            &repo_root,
+        )
+    }
+
+    fn show_revision(&self, file_path: &Path, revision: &str) -> Result<VcsRevisionFile, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+        let hash = resolve_checkin(&repo_root, revision)?;
+        let bytes = run_fossil_bytes(
+            &["cat", "-r", hash.as_str(), relative_path.as_str()],
+            &repo_root,
+        )
+        .map_err(|_| format!("{relative_path} does not exist at {revision}."))?;
+        Ok(VcsRevisionFile {
+            revision: hash,
+            path: relative_path,
+            content: decode_text(&bytes),
+        })
+    }
+
+    fn changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        let repo_root = match (&request.repo_root, &request.working_path) {
+            (Some(root), _) => PathBuf::from(root),
+            (None, Some(path)) => resolve_checkout_root(working_dir(Path::new(path))?)?,
+            (None, None) => return Err("Repository root is required.".into()),
+        };
+        let hash = resolve_checkin(&repo_root, &request.revision)?;
+        let info = run_fossil(&["info", hash.as_str()], &repo_root)?;
+        // Merges are listed against their primary parent.
+        let parent = info_value(&info, "parent")
+            .and_then(|value| value.split_whitespace().next())
+            .map(str::to_string);
+
+        // The first timeline entry is the check-in itself, followed by its files.
+        let output = run_fossil(
+            &[
+                "timeline",
+                "ancestors",
+                hash.as_str(),
+                "-t",
+                "ci",
+                "-n",
+                "1",
+                "-v",
+                "-W",
+                "0",
+                "-F",
+                TIMELINE_FORMAT,
+            ],
+            &repo_root,
+        )?;
+        let mut lines = output.lines();
+        let (_, timestamp, author, _) =
+            lines
+                .by_ref()
+                .find_map(parse_timeline_line)
+                .ok_or_else(|| VcsError::InvalidRevision {
+                    message: format!("Unknown revision: {}", request.revision),
+                })?;
+        // The timeline comment is flattened to one line; `info` keeps the full text.
+        let description = info_value(&info, "comment")
+            .map(|comment| match comment.rfind(" (user: ") {
+                Some(index) => comment[..index].to_string(),
+                None => comment.to_string(),
+            })
+            .unwrap_or_default();
+
+        let files = lines
+            .filter_map(parse_change_line)
+            .map(|(status, path)| {
+                let kind = match status {
+                    "ADDED" => VcsChangeKind::Added,
+                    "DELETED" => VcsChangeKind::Deleted,
+                    _ => VcsChangeKind::Modified,
+                };
+                VcsChangesetFile {
+                    kind,
+                    path,
+                    original_path: None,
+                    base_revision: parent.clone().filter(|_| kind != VcsChangeKind::Added),
+                    revision: (kind != VcsChangeKind::Deleted).then(|| hash.clone()),
+                }
+            })
+            .collect();
+
+        Ok(VcsChangeset {
+            provider: "fossil".to_string(),
+            revision: hash,
+            author,
+            timestamp,
+            description,
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            files,
+        })
+    }
+
+    fn status(&self, path: &Path) -> Result<VcsStatusResult, VcsError> {
+        let repo_root = resolve_checkout_root(working_dir(path)?)?;
+        let changes = run_fossil(&["changes"], &repo_root)?;
+        let extras = run_fossil(&["extras"], &repo_root).unwrap_or_default();
+
+        let tracked = changes
+            .lines()
+            .filter_map(parse_change_line)
+            .filter_map(|(status, file)| {
+                let kind = match status {
+                    "EDITED"
+                    | "UPDATED_BY_MERGE"
+                    | "UPDATED_BY_INTEGRATE"
+                    | "EXECUTABLE"
+                    | "UNEXEC"
+                    | "SYMLINK"
+                    | "UNLINK" => VcsChangeKind::Modified,
+                    "ADDED" | "ADDED_BY_MERGE" | "ADDED_BY_INTEGRATE" => VcsChangeKind::Added,
+                    "DELETED" | "MISSING" => VcsChangeKind::Deleted,
+                    // `fossil changes` only names the new path of a rename.
+                    "RENAMED" => VcsChangeKind::Renamed,
+                    "CONFLICT" => VcsChangeKind::Conflicted,
+                    _ => return None,
+                };
+                Some((kind, file))
+            });
+        let untracked = extras
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|file| (VcsChangeKind::Untracked, file.to_string()));
+
+        let entries: Vec<VcsStatusEntry> = tracked
+            .chain(untracked)
+            .map(|(kind, file)| {
+                let base_revision = match kind {
+                    VcsChangeKind::Added | VcsChangeKind::Untracked | VcsChangeKind::Renamed => {
+                        None
+                    }
+                    _ => Some("current".to_string()),
+                };
+                VcsStatusEntry {
+                    kind,
+                    working_path: repo_root.join(&file).to_string_lossy().to_string(),
+                    path: file,
+                    original_path: None,
+                    base_revision,
+                }
+            })
+            .collect();
+        log::info!(
+            "fossil status repo={} entries={}",
+            repo_root.display(),
+            entries.len()
+        );
+
+        Ok(VcsStatusResult {
+            provider: "fossil".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            entries,
+        })
+    }
+
+    fn cache_revision(&self, path: &Path) -> Option<String> {
+        let cwd = working_dir(path).ok()?;
+        let info = run_fossil(&["info"], cwd).ok()?;
+        info_value(&info, "checkout")
+            .and_then(|value| value.split_whitespace().next())
+            .map(str::to_string)
+    }
+}
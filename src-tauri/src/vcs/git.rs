@@ -0,0 +1,588 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use tauri_plugin_log::log;
+
+use super::{
+    decode_text, find_ancestor_with, history_paths, runner, to_git_path, working_dir,
+    working_file_parent, BlameEntry, BlameRequest, BlameResult, ChangesetRequest, ShowRequest,
+    VcsChangeKind, VcsChangeset, VcsChangesetFile, VcsError, VcsHistoryEntry, VcsHistoryResult,
+    VcsProvider, VcsRevisionFile, VcsStatusEntry, VcsStatusResult,
+};
+
+pub(crate) struct GitProvider;
+
+/// History entries collected before they are reported as progress.
+const HISTORY_PROGRESS_BATCH: usize = 50;
+
+pub(crate) fn run_git<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<String, VcsError> {
+    run_git_bytes(args, cwd).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like [`run_git`], but returns stdout untouched (file contents may be binary).
+pub(crate) fn run_git_bytes<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<Vec<u8>, VcsError> {
+    let output = runner::output(Command::new("git").current_dir(cwd).args(args), None)
+        .map_err(|error| VcsError::launch("git", error))?;
+    check_git_status(&output, args, cwd)?;
+    Ok(output.stdout)
+}
+
+/// Like [`run_git`], but hands each stdout line to `on_line` while git is still running.
+fn run_git_lines<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+    on_line: impl FnMut(&str),
+) -> Result<(), VcsError> {
+    let output = runner::stream_lines(Command::new("git").current_dir(cwd).args(args), on_line)
+        .map_err(|error| VcsError::launch("git", error))?;
+    check_git_status(&output, args, cwd)
+}
+
+fn check_git_status<S: AsRef<OsStr> + std::fmt::Debug>(
+    output: &Output,
+    args: &[S],
+    cwd: &Path,
+) -> Result<(), VcsError> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    let error = VcsError::failed("git", output, stderr);
+    log::warn!(
+        "git failed cwd={} args={args:?} error={error}",
+        cwd.display()
+    );
+    Err(error)
+}
+
+/// Resolves the root of the repository containing the directory `cwd`.
+fn resolve_repo_root(cwd: &Path) -> Result<PathBuf, VcsError> {
+    // `--show-toplevel` only fails outside a work tree, whatever language git reports it in.
+    let repo_root_output = run_git(&["rev-parse", "--show-toplevel"], cwd)
+        .map_err(|error| error.or_else_failed(|message| VcsError::NotARepository { message }))?;
+    let repo_root = PathBuf::from(repo_root_output.lines().next().unwrap_or_default().trim());
+    if repo_root.as_os_str().is_empty() {
+        return Err(VcsError::NotARepository {
+            message: "Unable to resolve repository root.".to_string(),
+        });
+    }
+    Ok(repo_root)
+}
+
+/// Resolves the repository root and the repo-relative path of a working-copy file.
+pub(crate) fn resolve_repo_path(file_path: &Path) -> Result<(PathBuf, String), VcsError> {
+    let parent = working_file_parent(file_path)?;
+    let repo_root = resolve_repo_root(parent)?;
+
+    let relative_path =
+        file_path
+            .strip_prefix(&repo_root)
+            .map_err(|_| VcsError::NotARepository {
+                message: "File is not inside the repository.".to_string(),
+            })?;
+    Ok((repo_root, to_git_path(relative_path)))
+}
+
+/// Resolves a commit-ish (hash, branch, tag, `HEAD~3`, ...) to a full commit hash.
+fn resolve_commit(repo_root: &Path, revision: &str) -> Result<String, VcsError> {
+    if revision.is_empty() || revision.starts_with('-') {
+        return Err(VcsError::InvalidRevision {
+            message: "Invalid revision.".to_string(),
+        });
+    }
+    let spec = format!("{revision}^{{commit}}");
+    run_git(
+        &["rev-parse", "--verify", "--quiet", spec.as_str()],
+        repo_root,
+    )
+    .map(|output| output.trim().to_string())
+    .map_err(|error| {
+        error.or_else_failed(|_| VcsError::InvalidRevision {
+            message: format!("Unknown revision: {revision}"),
+        })
+    })
+}
+
+fn parse_commit_line(line: &str) -> Option<(String, i64, String, String)> {
+    let mut parts = line.splitn(4, '\t');
+    let hash = parts.next()?;
+    let timestamp = parts.next()?.parse::<i64>().ok()?;
+    let author = parts.next()?.to_string();
+    let summary = parts.next().unwrap_or("").to_string();
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((hash.to_string(), timestamp, author, summary))
+}
+
+/// Maps the `XY` field of a porcelain v2 ordinary entry to a change kind.
+fn change_kind(xy: &str) -> VcsChangeKind {
+    if xy.contains('D') {
+        VcsChangeKind::Deleted
+    } else if xy.contains('A') {
+        VcsChangeKind::Added
+    } else {
+        VcsChangeKind::Modified
+    }
+}
+
+/// Parses `git status --porcelain=v2 -z` output.
+fn parse_porcelain_v2(output: &str, repo_root: &Path) -> Vec<VcsStatusEntry> {
+    let mut entries = Vec::new();
+    let mut records = output.split('\0');
+    while let Some(record) = records.next() {
+        // Field counts before the path: 8 for ordinary, 9 for renamed/copied (followed
+        // by the original path as a separate record), 10 for unmerged entries.
+        let (kind, path, original_path) = match record.split(' ').next() {
+            Some("1") => match record.splitn(9, ' ').collect::<Vec<_>>()[..] {
+                [_, xy, _, _, _, _, _, _, path] => (change_kind(xy), path, None),
+                _ => continue,
+            },
+            Some("2") => match record.splitn(10, ' ').collect::<Vec<_>>()[..] {
+                [_, _, _, _, _, _, _, _, score, path] => {
+                    let original = records.next().map(str::to_string);
+                    let kind = if score.starts_with('C') {
+                        VcsChangeKind::Added
+                    } else {
+                        VcsChangeKind::Renamed
+                    };
+                    (kind, path, original)
+                }
+                _ => continue,
+            },
+            Some("u") => match record.splitn(11, ' ').last() {
+                Some(path) => (VcsChangeKind::Conflicted, path, None),
+                None => continue,
+            },
+            Some("?") => match record.get(2..) {
+                Some(path) => (VcsChangeKind::Untracked, path, None),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        let base_revision = match kind {
+            VcsChangeKind::Added | VcsChangeKind::Untracked => None,
+            _ => Some("HEAD".to_string()),
+        };
+        entries.push(VcsStatusEntry {
+            kind,
+            path: path.to_string(),
+            working_path: repo_root.join(path).to_string_lossy().to_string(),
+            original_path,
+            base_revision,
+        });
+    }
+    entries
+}
+
+/// Parses `--name-status -z` output into `(status, path, original_path)` triples.
+fn parse_name_status_z(output: &str) -> Vec<(char, String, Option<String>)> {
+    let mut files = Vec::new();
+    let mut tokens = output.split('\0');
+    while let Some(token) = tokens.next() {
+        let Some(status) = token.trim().chars().next() else {
+            continue;
+        };
+        let Some(first) = tokens.next() else {
+            break;
+        };
+        if matches!(status, 'R' | 'C') {
+            let Some(second) = tokens.next() else {
+                break;
+            };
+            files.push((status, second.to_string(), Some(first.to_string())));
+        } else {
+            files.push((status, first.to_string(), None));
+        }
+    }
+    files
+}
+
+impl VcsProvider for GitProvider {
+    fn id(&self) -> &'static str {
+        "git"
+    }
+
+    fn label(&self) -> &'static str {
+        "Git"
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        // `.git` is a directory in normal clones and a file in worktrees/submodules.
+        find_ancestor_with(path, ".git").is_some()
+    }
+
+    fn history(&self, file_path: &Path) -> Result<VcsHistoryResult, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+
+        run_git(
+            &["ls-files", "--error-unmatch", "--", &relative_path],
+            &repo_root,
+        )
+        .map_err(|error| {
+            error.or_else_failed(|_| VcsError::Untracked {
+                message: "File is not tracked in git.".to_string(),
+            })
+        })?;
+
+        struct PendingCommit {
+            hash: String,
+            timestamp: i64,
+            author: String,
+            summary: String,
+            path: String,
+            deleted: bool,
+            touched: bool,
+        }
+
+        let mut entries = Vec::new();
+        let mut current_path = relative_path.clone();
+        let mut pending: Option<PendingCommit> = None;
+        let mut reported = 0;
+
+        let mut flush_pending = |pending: &mut Option<PendingCommit>| {
+            if let Some(entry) = pending.take() {
+                if entry.touched {
+                    entries.push(VcsHistoryEntry {
+                        provider: "git".to_string(),
+                        hash: entry.hash,
+                        timestamp: entry.timestamp,
+                        author: entry.author,
+                        summary: entry.summary,
+                        path: entry.path,
+                        deleted: entry.deleted,
+                        integrations: Vec::new(),
+                    });
+                    // Long histories show up in batches instead of after the whole walk.
+                    if entries.len() - reported >= HISTORY_PROGRESS_BATCH {
+                        runner::report_history(&entries[reported..]);
+                        reported = entries.len();
+                    }
+                }
+            }
+        };
+
+        let log_args = [
+            "--no-pager",
+            "log",
+            "--follow",
+            "--name-status",
+            "--format=%H\t%ct\t%an\t%s",
+            "--",
+            &relative_path,
+        ];
+        run_git_lines(&log_args, &repo_root, |line| {
+            if line.trim().is_empty() {
+                return;
+            }
+
+            if let Some((hash, timestamp, author, summary)) = parse_commit_line(line) {
+                flush_pending(&mut pending);
+                pending = Some(PendingCommit {
+                    hash,
+                    timestamp,
+                    author,
+                    summary,
+                    path: current_path.clone(),
+                    deleted: false,
+                    touched: false,
+                });
+                return;
+            }
+
+            let mut parts = line.split('\t');
+            let status = parts.next().unwrap_or("");
+            if status.is_empty() {
+                return;
+            }
+
+            let Some(entry) = pending.as_mut() else {
+                return;
+            };
+
+            if status.starts_with('R') || status.starts_with('C') {
+                let old_path = parts.next().unwrap_or("");
+                let new_path = parts.next().unwrap_or("");
+                if !old_path.is_empty() && !new_path.is_empty() {
+                    if new_path == current_path || old_path == current_path {
+                        entry.touched = true;
+                    }
+                    if status.starts_with('R') && new_path == current_path {
+                        current_path = old_path.to_string();
+                    }
+                }
+            } else {
+                let path = parts.next().unwrap_or("");
+                if path == current_path {
+                    entry.touched = true;
+                    if status.starts_with('D') {
+                        entry.deleted = true;
+                    }
+                }
+            }
+        })?;
+
+        flush_pending(&mut pending);
+
+        Ok(VcsHistoryResult {
+            provider: "git".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            relative_path,
+            entries,
+        })
+    }
+
+    fn blame(&self, request: &BlameRequest) -> Result<BlameResult, VcsError> {
+        log::info!(
+            "Git blame: starting for {} commit={:?}",
+            request.path,
+            request.revision
+        );
+
+        let (repo_root, relative_path) = if let Some(root) = &request.repo_root {
+            // Use provided repo root and path is already relative
+            (PathBuf::from(root), request.path.clone())
+        } else {
+            let (repo_root, relative_path) = resolve_repo_path(Path::new(&request.path))?;
+            log::info!("Git blame: repo root = {}", repo_root.display());
+            (repo_root, relative_path)
+        };
+
+        log::info!("Git blame: running git blame for {}", relative_path);
+
+        // git blame --porcelain gives machine-readable output
+        // If commit is specified, blame that specific commit
+        let mut args = vec!["blame".to_string(), "--porcelain".to_string()];
+        if let Some(revision) = &request.revision {
+            args.push(revision.clone());
+        }
+        args.push("--".into());
+        args.push(relative_path);
+
+        let blame_output = run_git(&args, &repo_root)?;
+
+        log::info!("Git blame: output lines = {}", blame_output.lines().count());
+
+        let mut entries: Vec<BlameEntry> = Vec::new();
+        let mut current_hash = String::new();
+        let mut current_author = String::new();
+        let mut current_timestamp: i64 = 0;
+        let mut current_summary = String::new();
+
+        // Cache commit info to avoid reparsing
+        let mut commit_cache: HashMap<String, (String, i64, String)> = HashMap::new();
+
+        for line in blame_output.lines() {
+            if line.starts_with('\t') {
+                // This is the actual content line, meaning we have a complete entry
+                entries.push(BlameEntry {
+                    line: entries.len() + 1,
+                    hash: current_hash.clone(),
+                    author: current_author.clone(),
+                    timestamp: current_timestamp,
+                    summary: current_summary.clone(),
+                });
+            } else if line.len() >= 40 && line.chars().take(40).all(|c| c.is_ascii_hexdigit()) {
+                // This is a commit hash line
+                let hash = &line[..40];
+                current_hash = hash.to_string();
+
+                // Check if we have cached info for this commit
+                if let Some((author, ts, summary)) = commit_cache.get(hash) {
+                    current_author = author.clone();
+                    current_timestamp = *ts;
+                    current_summary = summary.clone();
+                }
+            } else if let Some(author) = line.strip_prefix("author ") {
+                current_author = author.to_string();
+                commit_cache
+                    .entry(current_hash.clone())
+                    .or_insert_with(|| (current_author.clone(), 0, String::new()))
+                    .0 = current_author.clone();
+            } else if let Some(time) = line.strip_prefix("author-time ") {
+                if let Ok(ts) = time.parse::<i64>() {
+                    current_timestamp = ts;
+                    commit_cache
+                        .entry(current_hash.clone())
+                        .or_insert_with(|| (String::new(), ts, String::new()))
+                        .1 = ts;
+                }
+            } else if let Some(summary) = line.strip_prefix("summary ") {
+                current_summary = summary.to_string();
+                commit_cache
+                    .entry(current_hash.clone())
+                    .or_insert_with(|| (String::new(), 0, current_summary.clone()))
+                    .2 = current_summary.clone();
+            }
+        }
+
+        log::info!("Git blame: returning {} entries", entries.len());
+
+        Ok(BlameResult {
+            provider: "git".to_string(),
+            entries,
+        })
+    }
+
+    fn show_bytes(&self, request: &ShowRequest) -> Result<Vec<u8>, VcsError> {
+        let repo_root = request
+            .repo_root
+            .as_deref()
+            .map(PathBuf::from)
+            .ok_or_else(|| "Repository root is required.".to_string())?;
+        if !repo_root.is_dir() {
+            return Err("Repository root does not exist.".into());
+        }
+        let path = request.path.replace('\\', "/");
+        let spec = format!("{}:{path}", request.revision);
+        run_git_bytes(&["--no-pager", "show", &spec], &repo_root)
+    }
+
+    fn show_revision(&self, file_path: &Path, revision: &str) -> Result<VcsRevisionFile, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+        let hash = resolve_commit(&repo_root, revision)?;
+        let exists_at = |path: &str| {
+            let spec = format!("{hash}:{path}");
+            run_git(&["cat-file", "-e", spec.as_str()], &repo_root).is_ok()
+        };
+
+        // Only walk `log --follow` when the current path doesn't exist at that commit.
+        let path = if exists_at(&relative_path) {
+            relative_path
+        } else {
+            history_paths(self, file_path, &relative_path)
+                .into_iter()
+                .find(|path| exists_at(path))
+                .ok_or_else(|| format!("{relative_path} does not exist at {revision}."))?
+        };
+        let spec = format!("{hash}:{path}");
+        let content = decode_text(&run_git_bytes(
+            &["--no-pager", "show", spec.as_str()],
+            &repo_root,
+        )?);
+        Ok(VcsRevisionFile {
+            revision: hash,
+            path,
+            content,
+        })
+    }
+
+    fn changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        let repo_root = match (&request.repo_root, &request.working_path) {
+            (Some(root), _) => PathBuf::from(root),
+            (None, Some(path)) => resolve_repo_root(working_dir(Path::new(path))?)?,
+            (None, None) => return Err("Repository root is required.".into()),
+        };
+        let revision = request.revision.as_str();
+        if revision.is_empty() || revision.starts_with('-') {
+            return Err(VcsError::InvalidRevision {
+                message: "Invalid revision.".to_string(),
+            });
+        }
+
+        let header = run_git(
+            &[
+                "--no-pager",
+                "show",
+                "-s",
+                "--format=%H%x00%P%x00%ct%x00%an%x00%B",
+                revision,
+            ],
+            &repo_root,
+        )?;
+        let mut fields = header.splitn(5, '\0');
+        let hash = fields.next().unwrap_or_default().trim().to_string();
+        let first_parent = fields
+            .next()
+            .and_then(|parents| parents.split_whitespace().next())
+            .map(str::to_string);
+        let timestamp = fields
+            .next()
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .unwrap_or(0);
+        let author = fields.next().unwrap_or_default().to_string();
+        let description = fields.next().unwrap_or_default().trim_end().to_string();
+
+        // Merges are listed against their first parent, like `git log --first-parent`.
+        let output = run_git(
+            &[
+                "--no-pager",
+                "show",
+                "--format=",
+                "--name-status",
+                "-M",
+                "-z",
+                "-m",
+                "--first-parent",
+                hash.as_str(),
+            ],
+            &repo_root,
+        )?;
+
+        let files = parse_name_status_z(&output)
+            .into_iter()
+            .map(|(status, path, original_path)| {
+                let kind = match status {
+                    'A' | 'C' => VcsChangeKind::Added,
+                    'D' => VcsChangeKind::Deleted,
+                    'R' => VcsChangeKind::Renamed,
+                    'U' => VcsChangeKind::Conflicted,
+                    _ => VcsChangeKind::Modified,
+                };
+                let has_base = kind != VcsChangeKind::Added || original_path.is_some();
+                VcsChangesetFile {
+                    kind,
+                    path,
+                    original_path,
+                    base_revision: first_parent.clone().filter(|_| has_base),
+                    revision: (kind != VcsChangeKind::Deleted).then(|| hash.clone()),
+                }
+            })
+            .collect();
+
+        Ok(VcsChangeset {
+            provider: "git".to_string(),
+            revision: hash,
+            author,
+            timestamp,
+            description,
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            files,
+        })
+    }
+
+    fn status(&self, path: &Path) -> Result<VcsStatusResult, VcsError> {
+        let repo_root = resolve_repo_root(working_dir(path)?)?;
+        let output = run_git(
+            &["status", "--porcelain=v2", "-z", "--untracked-files=all"],
+            &repo_root,
+        )?;
+        let entries = parse_porcelain_v2(&output, &repo_root);
+        log::info!(
+            "git status repo={} entries={}",
+            repo_root.display(),
+            entries.len()
+        );
+
+        Ok(VcsStatusResult {
+            provider: "git".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            entries,
+        })
+    }
+
+    fn cache_revision(&self, path: &Path) -> Option<String> {
+        let cwd = working_dir(path).ok()?;
+        let head = run_git(&["rev-parse", "HEAD"], cwd).ok()?;
+        Some(head.trim().to_string()).filter(|head| !head.is_empty())
+    }
+}
@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tauri_plugin_log::log;
+
+use super::{
+    decode_text, find_ancestor_with, history_paths, runner, to_git_path, truncate_for_log,
+    working_dir, working_file_parent, BlameEntry, BlameRequest, BlameResult, ChangesetRequest,
+    ShowRequest, VcsChangeKind, VcsChangeset, VcsChangesetFile, VcsError, VcsHistoryEntry,
+    VcsHistoryResult, VcsProvider, VcsRevisionFile, VcsStatusEntry, VcsStatusResult,
+};
+
+pub(crate) struct HgProvider;
+
+/// Field and record separators used in templates; hg decodes the `\x..` escapes itself.
+const FIELD_SEP: char = '\x1f';
+const RECORD_SEP: char = '\x1e';
+const ITEM_SEP: char = '\x1d';
+const PAIR_SEP: char = '\x1c';
+
+/// `hg log --follow` template: node, date, author, summary, copies (`name<PAIR>source`
+/// items) and removed files.
+const LOG_TEMPLATE: &str = r"{node}\x1f{date|hgdate}\x1f{author|person}\x1f{desc|firstline}\x1f{file_copies % '{name}\x1c{source}\x1d'}\x1f{join(file_dels, '\x1d')}\x1e";
+/// `hg annotate` template: one `node<FIELD>date<FIELD>user` line per source line.
+const ANNOTATE_TEMPLATE: &str = r"{lines % '{node}\x1f{date|hgdate}\x1f{user|person}\n'}";
+/// Changeset header: node, first parent, date, author and full description.
+const CHANGESET_TEMPLATE: &str =
+    r"{node}\x1f{p1node}\x1f{date|hgdate}\x1f{author|person}\x1f{desc}";
+/// Revisions looked up per `hg log` call when collecting blame summaries.
+const SUMMARY_BATCH: usize = 200;
+const NULL_NODE: &str = "0000000000000000000000000000000000000000";
+
+pub(crate) fn run_hg<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<String, VcsError> {
+    run_hg_bytes(args, cwd).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like [`run_hg`], but returns stdout untouched (file contents may be binary).
+pub(crate) fn run_hg_bytes<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<Vec<u8>, VcsError> {
+    let output = runner::output(
+        Command::new("hg")
+            .current_dir(cwd)
+            // Plain mode ignores user aliases, localization and color so output stays parseable.
+            .env("HGPLAIN", "1")
+            .args(args),
+        None,
+    )
+    .map_err(|error| VcsError::launch("hg", error))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let error = VcsError::failed("hg", &output, stderr);
+        log::warn!(
+            "hg failed cwd={} args={args:?} error={error}",
+            cwd.display()
+        );
+        return Err(error);
+    }
+
+    Ok(output.stdout)
+}
+
+/// Resolves the root of the repository containing the directory `cwd`.
+fn resolve_repo_root(cwd: &Path) -> Result<PathBuf, VcsError> {
+    let output = run_hg(&["root"], cwd)
+        .map_err(|error| error.or_else_failed(|message| VcsError::NotARepository { message }))?;
+    let root = output.lines().next().map(str::trim).unwrap_or_default();
+    if root.is_empty() {
+        return Err(VcsError::NotARepository {
+            message: "Unable to resolve repository root.".to_string(),
+        });
+    }
+    Ok(PathBuf::from(root))
+}
+
+/// Resolves the repository root and the repo-relative path of a working-copy file.
+fn resolve_repo_path(file_path: &Path) -> Result<(PathBuf, String), VcsError> {
+    let parent = working_file_parent(file_path)?;
+    let repo_root = resolve_repo_root(parent)?;
+    let relative_path =
+        file_path
+            .strip_prefix(&repo_root)
+            .map_err(|_| VcsError::NotARepository {
+                message: "File is not inside the repository.".to_string(),
+            })?;
+    Ok((repo_root, to_git_path(relative_path)))
+}
+
+/// A repo-relative path as an hg file pattern, so names are never read as globs.
+fn path_pattern(path: &str) -> String {
+    format!("path:{path}")
+}
+
+/// Resolves a revision (number, hash, tag, bookmark, branch, revset) to its full node.
+fn resolve_node(repo_root: &Path, revision: &str) -> Result<String, VcsError> {
+    let revision = revision.trim();
+    if revision.is_empty() {
+        return Err(VcsError::InvalidRevision {
+            message: "Invalid revision.".to_string(),
+        });
+    }
+    let output = run_hg(
+        &["log", "-r", revision, "-l", "1", "-T", "{node}"],
+        repo_root,
+    )
+    .map_err(|error| {
+        error.or_else_failed(|_| VcsError::InvalidRevision {
+            message: format!("Unknown revision: {revision}"),
+        })
+    })?;
+    let node = output.trim();
+    if node.is_empty() {
+        return Err(VcsError::InvalidRevision {
+            message: format!("Unknown revision: {revision}"),
+        });
+    }
+    Ok(node.to_string())
+}
+
+/// Seconds from an `hgdate` value (`"1700000000 -3600"`).
+fn parse_hgdate(value: &str) -> i64 {
+    value
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.split('.').next())
+        .and_then(|seconds| seconds.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Parses `hg log --follow` output, tracking the file's path back through renames.
+fn parse_log(output: &str, relative_path: &str) -> Vec<VcsHistoryEntry> {
+    let mut current_path = relative_path.to_string();
+    let mut entries = Vec::new();
+    for record in output.split(RECORD_SEP) {
+        let record = record.trim_start_matches('\n');
+        if record.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = record.split(FIELD_SEP).collect();
+        let [node, date, author, summary, copies, removed] = fields[..] else {
+            continue;
+        };
+        let path = current_path.clone();
+        let deleted = removed.split(ITEM_SEP).any(|file| file == path);
+        // Older changesets know the file under the name it was copied from.
+        if let Some(source) = copies
+            .split(ITEM_SEP)
+            .filter_map(|item| item.split_once(PAIR_SEP))
+            .find_map(|(name, source)| (name == path).then(|| source.to_string()))
+        {
+            current_path = source;
+        }
+        entries.push(VcsHistoryEntry {
+            provider: "hg".to_string(),
+            hash: node.to_string(),
+            timestamp: parse_hgdate(date),
+            author: author.to_string(),
+            summary: summary.to_string(),
+            path,
+            deleted,
+            integrations: Vec::new(),
+        });
+    }
+    entries
+}
+
+/// First description lines of `nodes`, looked up in batches.
+fn load_summaries(repo_root: &Path, nodes: &[String]) -> HashMap<String, String> {
+    let mut summaries = HashMap::new();
+    for batch in nodes.chunks(SUMMARY_BATCH) {
+        let mut args = vec!["log".to_string(), "-T".to_string()];
+        args.push(r"{node}\x1f{desc|firstline}\n".to_string());
+        for node in batch {
+            args.push("-r".to_string());
+            args.push(node.clone());
+        }
+        match run_hg(&args, repo_root) {
+            Ok(output) => {
+                for line in output.lines() {
+                    if let Some((node, summary)) = line.split_once(FIELD_SEP) {
+                        summaries.insert(node.to_string(), summary.to_string());
+                    }
+                }
+            }
+            Err(error) => log::warn!("hg blame summaries failed error={error}"),
+        }
+    }
+    summaries
+}
+
+/// Parses `hg status -C` output; copy sources follow their file indented by two spaces.
+fn parse_status(output: &str) -> Vec<(char, String, Option<String>)> {
+    let mut files: Vec<(char, String, Option<String>)> = Vec::new();
+    for line in output.lines() {
+        if let Some(source) = line.strip_prefix("  ") {
+            if let Some(last) = files.last_mut() {
+                last.2 = Some(source.to_string());
+            }
+            continue;
+        }
+        let mut chars = line.chars();
+        let (Some(code), Some(' ')) = (chars.next(), chars.next()) else {
+            continue;
+        };
+        files.push((code, chars.as_str().to_string(), None));
+    }
+    files
+}
+
+impl VcsProvider for HgProvider {
+    fn id(&self) -> &'static str {
+        "hg"
+    }
+
+    fn label(&self) -> &'static str {
+        "Mercurial"
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        find_ancestor_with(path, ".hg").is_some()
+    }
+
+    fn history(&self, file_path: &Path) -> Result<VcsHistoryResult, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+        let pattern = path_pattern(&relative_path);
+
+        // `hg files` exits 1 when nothing matches; aborts use 255.
+        let files = match run_hg(&["files", "--", pattern.as_str()], &repo_root) {
+            Err(VcsError::CommandFailed {
+                status: Some(1), ..
+            }) => String::new(),
+            result => result?,
+        };
+        if files.trim().is_empty() {
+            return Err(VcsError::Untracked {
+                message: "File is not tracked in Mercurial.".to_string(),
+            });
+        }
+
+        let log_output = run_hg(
+            &[
+                "log",
+                "--follow",
+                "-T",
+                LOG_TEMPLATE,
+                "--",
+                pattern.as_str(),
+            ],
+            &repo_root,
+        )?;
+        let entries = parse_log(&log_output, &relative_path);
+        if entries.is_empty() && !log_output.trim().is_empty() {
+            log::warn!(
+                "hg history parsed 0 entries path={relative_path} output_preview={}",
+                truncate_for_log(&log_output, 4000)
+            );
+        }
+
+        Ok(VcsHistoryResult {
+            provider: "hg".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            relative_path,
+            entries,
+        })
+    }
+
+    fn blame(&self, request: &BlameRequest) -> Result<BlameResult, VcsError> {
+        let (repo_root, relative_path) = match &request.repo_root {
+            Some(root) => (PathBuf::from(root), request.path.replace('\\', "/")),
+            None => resolve_repo_path(Path::new(&request.path))?,
+        };
+        let pattern = path_pattern(&relative_path);
+        let mut args = vec!["annotate", "--user", "--date", "--changeset"];
+        if let Some(revision) = &request.revision {
+            args.push("-r");
+            args.push(revision.as_str());
+        }
+        args.extend(["-T", ANNOTATE_TEMPLATE, "--", pattern.as_str()]);
+        let output = run_hg(&args, &repo_root)?;
+
+        let mut entries: Vec<BlameEntry> = output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(FIELD_SEP);
+                let (node, date, author) = (fields.next()?, fields.next()?, fields.next()?);
+                Some((node.to_string(), parse_hgdate(date), author.to_string()))
+            })
+            .enumerate()
+            .map(|(index, (hash, timestamp, author))| BlameEntry {
+                line: index + 1,
+                hash,
+                author,
+                timestamp,
+                summary: String::new(),
+            })
+            .collect();
+
+        let mut nodes: Vec<String> = entries.iter().map(|entry| entry.hash.clone()).collect();
+        nodes.sort();
+        nodes.dedup();
+        let summaries = load_summaries(&repo_root, &nodes);
+        for entry in &mut entries {
+            if let Some(summary) = summaries.get(&entry.hash) {
+                entry.summary = summary.clone();
+            }
+        }
+        log::info!(
+            "hg blame path={relative_path} lines={} revisions={}",
+            entries.len(),
+            nodes.len()
+        );
+
+        Ok(BlameResult {
+            provider: "hg".to_string(),
+            entries,
+        })
+    }
+
+    fn show_bytes(&self, request: &ShowRequest) -> Result<Vec<u8>, VcsError> {
+        let repo_root = request
+            .repo_root
+            .as_deref()
+            .map(PathBuf::from)
+            .ok_or_else(|| "Repository root is required.".to_string())?;
+        if !repo_root.is_dir() {
+            return Err("Repository root does not exist.".into());
+        }
+        let pattern = path_pattern(&request.path.replace('\\', "/"));
+        run_hg_bytes(
+            &[
+                "cat",
+                "-r",
+                request.revision.as_str(),
+                "--",
+                pattern.as_str(),
+            ],
+            &repo_root,
+        )
+    }
+
+    fn show_revision(&self, file_path: &Path, revision: &str) -> Result<VcsRevisionFile, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+        let node = resolve_node(&repo_root, revision)?;
+        let exists_at = |path: &str| {
+            let pattern = path_pattern(path);
+            run_hg(
+                &["files", "-r", node.as_str(), "--", pattern.as_str()],
+                &repo_root,
+            )
+            .is_ok_and(|output| !output.trim().is_empty())
+        };
+
+        let path = if exists_at(&relative_path) {
+            relative_path
+        } else {
+            history_paths(self, file_path, &relative_path)
+                .into_iter()
+                .find(|path| exists_at(path))
+                .ok_or_else(|| format!("{relative_path} does not exist at {revision}."))?
+        };
+        let pattern = path_pattern(&path);
+        let content = decode_text(&run_hg_bytes(
+            &["cat", "-r", node.as_str(), "--", pattern.as_str()],
+            &repo_root,
+        )?);
+        Ok(VcsRevisionFile {
+            revision: node,
+            path,
+            content,
+        })
+    }
+
+    fn changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        let repo_root = match (&request.repo_root, &request.working_path) {
+            (Some(root), _) => PathBuf::from(root),
+            (None, Some(path)) => resolve_repo_root(working_dir(Path::new(path))?)?,
+            (None, None) => return Err("Repository root is required.".into()),
+        };
+        let node = resolve_node(&repo_root, &request.revision)?;
+
+        let header = run_hg(
+            &["log", "-r", node.as_str(), "-T", CHANGESET_TEMPLATE],
+            &repo_root,
+        )?;
+        let mut fields = header.splitn(5, FIELD_SEP);
+        let _ = fields.next();
+        let parent = fields
+            .next()
+            .map(str::to_string)
+            .filter(|parent| !parent.is_empty() && parent != NULL_NODE);
+        let timestamp = fields.next().map_or(0, parse_hgdate);
+        let author = fields.next().unwrap_or_default().to_string();
+        let description = fields.next().unwrap_or_default().trim_end().to_string();
+
+        let output = run_hg(
+            &["status", "--change", node.as_str(), "--copies"],
+            &repo_root,
+        )?;
+        let status = parse_status(&output);
+        let removed: Vec<&str> = status
+            .iter()
+            .filter(|(code, _, _)| *code == 'R')
+            .map(|(_, path, _)| path.as_str())
+            .collect();
+        let renamed_from: Vec<&str> = status
+            .iter()
+            .filter_map(|(_, _, source)| source.as_deref())
+            .filter(|source| removed.contains(source))
+            .collect();
+
+        let files = status
+            .iter()
+            .filter(|(code, path, _)| !(*code == 'R' && renamed_from.contains(&path.as_str())))
+            .map(|(code, path, source)| {
+                let kind = match code {
+                    'A' if source.as_deref().is_some_and(|s| removed.contains(&s)) => {
+                        VcsChangeKind::Renamed
+                    }
+                    'A' => VcsChangeKind::Added,
+                    'R' => VcsChangeKind::Deleted,
+                    _ => VcsChangeKind::Modified,
+                };
+                let has_base = kind != VcsChangeKind::Added || source.is_some();
+                VcsChangesetFile {
+                    kind,
+                    path: path.clone(),
+                    original_path: source.clone(),
+                    base_revision: parent.clone().filter(|_| has_base),
+                    revision: (kind != VcsChangeKind::Deleted).then(|| node.clone()),
+                }
+            })
+            .collect();
+
+        Ok(VcsChangeset {
+            provider: "hg".to_string(),
+            revision: node,
+            author,
+            timestamp,
+            description,
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            files,
+        })
+    }
+
+    fn status(&self, path: &Path) -> Result<VcsStatusResult, VcsError> {
+        let repo_root = resolve_repo_root(working_dir(path)?)?;
+        let output = run_hg(&["status", "--copies"], &repo_root)?;
+        // Unresolved merge conflicts only show up in `hg resolve`.
+        let conflicted: Vec<String> = run_hg(&["resolve", "--list"], &repo_root)
+            .map(|output| {
+                output
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("U "))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let entries: Vec<VcsStatusEntry> = parse_status(&output)
+            .into_iter()
+            .filter_map(|(code, file, source)| {
+                let kind = if conflicted.contains(&file) {
+                    VcsChangeKind::Conflicted
+                } else {
+                    match code {
+                        'M' => VcsChangeKind::Modified,
+                        'A' if source.is_some() => VcsChangeKind::Renamed,
+                        'A' => VcsChangeKind::Added,
+                        'R' | '!' => VcsChangeKind::Deleted,
+                        '?' => VcsChangeKind::Untracked,
+                        _ => return None,
+                    }
+                };
+                let base_revision = match kind {
+                    VcsChangeKind::Added | VcsChangeKind::Untracked => None,
+                    _ => Some(".".to_string()),
+                };
+                Some(VcsStatusEntry {
+                    kind,
+                    working_path: repo_root.join(&file).to_string_lossy().to_string(),
+                    path: file,
+                    original_path: source,
+                    base_revision,
+                })
+            })
+            .collect();
+        log::info!(
+            "hg status repo={} entries={}",
+            repo_root.display(),
+            entries.len()
+        );
+
+        Ok(VcsStatusResult {
+            provider: "hg".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            entries,
+        })
+    }
+
+    fn cache_revision(&self, path: &Path) -> Option<String> {
+        let cwd = working_dir(path).ok()?;
+        let node = run_hg(&["log", "-r", ".", "-T", "{node}"], cwd).ok()?;
+        Some(node.trim().to_string()).filter(|node| !node.is_empty())
+    }
+}
@@ -0,0 +1,463 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tauri_plugin_log::log;
+
+use super::{
+    decode_text, find_ancestor_with, runner, to_git_path, truncate_for_log, working_dir,
+    working_file_parent, BlameEntry, BlameRequest, BlameResult, ChangesetRequest, ShowRequest,
+    VcsChangeKind, VcsChangeset, VcsChangesetFile, VcsError, VcsHistoryEntry, VcsHistoryResult,
+    VcsProvider, VcsRevisionFile, VcsStatusEntry, VcsStatusResult,
+};
+
+pub(crate) struct JjProvider;
+
+const FIELD_SEP: char = '\x1f';
+const RECORD_SEP: char = '\x1e';
+
+/// `jj log` template: change id, commit id, author time, author and summary.
+const LOG_TEMPLATE: &str = r#"change_id ++ "\x1f" ++ commit_id ++ "\x1f" ++ author.timestamp().format("%s") ++ "\x1f" ++ author.name() ++ "\x1f" ++ description.first_line() ++ "\x1e""#;
+/// `jj file annotate` template: one line per source line, attributed by change id.
+const ANNOTATE_TEMPLATE: &str = r#"commit.change_id() ++ "\x1f" ++ commit.author().timestamp().format("%s") ++ "\x1f" ++ commit.author().name() ++ "\x1f" ++ commit.description().first_line() ++ "\n""#;
+/// Changeset header: change id, commit id, parent commits, author time, author, description.
+const CHANGESET_TEMPLATE: &str = r#"change_id ++ "\x1f" ++ commit_id ++ "\x1f" ++ parents.map(|c| c.commit_id()).join(" ") ++ "\x1f" ++ author.timestamp().format("%s") ++ "\x1f" ++ author.name() ++ "\x1f" ++ description"#;
+
+pub(crate) fn run_jj<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<String, VcsError> {
+    run_jj_bytes(args, cwd).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like [`run_jj`], but returns stdout untouched (file contents may be binary).
+pub(crate) fn run_jj_bytes<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<Vec<u8>, VcsError> {
+    let output = runner::output(
+        Command::new("jj")
+            .current_dir(cwd)
+            .args(["--no-pager", "--color", "never"])
+            .args(args),
+        None,
+    )
+    .map_err(|error| VcsError::launch("jj", error))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let error = VcsError::failed("jj", &output, stderr);
+        log::warn!(
+            "jj failed cwd={} args={args:?} error={error}",
+            cwd.display()
+        );
+        return Err(error);
+    }
+
+    Ok(output.stdout)
+}
+
+fn resolve_workspace_root(cwd: &Path) -> Result<PathBuf, VcsError> {
+    let output = run_jj(&["workspace", "root"], cwd)
+        .map_err(|error| error.or_else_failed(|message| VcsError::NotARepository { message }))?;
+    let root = output.lines().next().map(str::trim).unwrap_or_default();
+    if root.is_empty() {
+        return Err(VcsError::NotARepository {
+            message: "Unable to resolve repository root.".to_string(),
+        });
+    }
+    Ok(PathBuf::from(root))
+}
+
+/// Resolves the workspace root and the workspace-relative path of a working-copy file.
+fn resolve_repo_path(file_path: &Path) -> Result<(PathBuf, String), VcsError> {
+    let parent = working_file_parent(file_path)?;
+    let repo_root = resolve_workspace_root(parent)?;
+    let relative_path =
+        file_path
+            .strip_prefix(&repo_root)
+            .map_err(|_| VcsError::NotARepository {
+                message: "File is not inside the repository.".to_string(),
+            })?;
+    Ok((repo_root, to_git_path(relative_path)))
+}
+
+/// A workspace-relative path as a fileset that matches exactly that file.
+fn file_pattern(path: &str) -> String {
+    let escaped = path.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("root-file:\"{escaped}\"")
+}
+
+/// Resolves a revset to exactly one revision as `(change id, commit id)`.
+fn resolve_revision(repo_root: &Path, revision: &str) -> Result<(String, String), VcsError> {
+    let revision = revision.trim();
+    if revision.is_empty() || revision.starts_with('-') {
+        return Err(VcsError::InvalidRevision {
+            message: "Invalid revision.".to_string(),
+        });
+    }
+    let output = run_jj(
+        &[
+            "log",
+            "--no-graph",
+            "-r",
+            revision,
+            "--limit",
+            "1",
+            "-T",
+            r#"change_id ++ "\x1f" ++ commit_id"#,
+        ],
+        repo_root,
+    )
+    .map_err(|error| {
+        error.or_else_failed(|_| VcsError::InvalidRevision {
+            message: format!("Unknown revision: {revision}"),
+        })
+    })?;
+    match output.trim().split_once(FIELD_SEP) {
+        Some((change_id, commit_id)) if !change_id.is_empty() => {
+            Ok((change_id.to_string(), commit_id.to_string()))
+        }
+        _ => Err(VcsError::InvalidRevision {
+            message: format!("Unknown revision: {revision}"),
+        }),
+    }
+}
+
+fn parse_log(output: &str, relative_path: &str) -> Vec<VcsHistoryEntry> {
+    output
+        .split(RECORD_SEP)
+        .filter_map(|record| {
+            let record = record.trim_start_matches('\n');
+            let fields: Vec<&str> = record.split(FIELD_SEP).collect();
+            let [change_id, _commit_id, timestamp, author, summary] = fields[..] else {
+                return None;
+            };
+            Some(VcsHistoryEntry {
+                provider: "jj".to_string(),
+                hash: change_id.to_string(),
+                timestamp: timestamp.trim().parse().unwrap_or(0),
+                author: author.to_string(),
+                summary: summary.to_string(),
+                path: relative_path.to_string(),
+                deleted: false,
+                integrations: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+/// Expands a `jj diff --summary` rename such as `src/{old.rs => new.rs}` into
+/// `(old, new)`.
+fn split_rename(value: &str) -> Option<(String, String)> {
+    let open = value.find('{')?;
+    let close = value.rfind('}')?;
+    let (prefix, suffix) = (&value[..open], &value[close + 1..]);
+    let (from, to) = value[open + 1..close].split_once(" => ")?;
+    let join = |middle: &str| {
+        let path = format!("{prefix}{middle}{suffix}");
+        // An empty side leaves a doubled separator, e.g. `{ => dir}/file`.
+        path.replace("//", "/").trim_start_matches('/').to_string()
+    };
+    Some((join(from), join(to)))
+}
+
+/// Parses `jj diff --summary` output into `(status, path, original path)`.
+fn parse_summary(output: &str) -> Vec<(char, String, Option<String>)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut chars = line.chars();
+            let (Some(status), Some(' ')) = (chars.next(), chars.next()) else {
+                return None;
+            };
+            let path = chars.as_str();
+            match status {
+                'R' | 'C' => {
+                    let (from, to) = split_rename(path)?;
+                    Some((status, to, Some(from)))
+                }
+                _ => Some((status, path.to_string(), None)),
+            }
+        })
+        .collect()
+}
+
+impl VcsProvider for JjProvider {
+    fn id(&self) -> &'static str {
+        "jj"
+    }
+
+    fn label(&self) -> &'static str {
+        "Jujutsu"
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        // Checked before git: a colocated workspace also has a `.git`, but its HEAD
+        // trails the working-copy commit.
+        find_ancestor_with(path, ".jj").is_some()
+    }
+
+    fn history(&self, file_path: &Path) -> Result<VcsHistoryResult, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+        let pattern = file_pattern(&relative_path);
+
+        let files = run_jj(
+            &["file", "list", "-r", "@", "--", pattern.as_str()],
+            &repo_root,
+        )?;
+        if files.trim().is_empty() {
+            return Err(VcsError::Untracked {
+                message: "File is not tracked in jj.".to_string(),
+            });
+        }
+
+        let log_output = run_jj(
+            &[
+                "log",
+                "--no-graph",
+                "-r",
+                "::@",
+                "-T",
+                LOG_TEMPLATE,
+                "--",
+                pattern.as_str(),
+            ],
+            &repo_root,
+        )?;
+        let entries = parse_log(&log_output, &relative_path);
+        if entries.is_empty() && !log_output.trim().is_empty() {
+            log::warn!(
+                "jj history parsed 0 entries path={relative_path} output_preview={}",
+                truncate_for_log(&log_output, 4000)
+            );
+        }
+
+        Ok(VcsHistoryResult {
+            provider: "jj".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            relative_path,
+            entries,
+        })
+    }
+
+    fn blame(&self, request: &BlameRequest) -> Result<BlameResult, VcsError> {
+        let (repo_root, relative_path) = match &request.repo_root {
+            Some(root) => (PathBuf::from(root), request.path.replace('\\', "/")),
+            None => resolve_repo_path(Path::new(&request.path))?,
+        };
+        let revision = request.revision.as_deref().unwrap_or("@");
+        let output = run_jj(
+            &[
+                "file",
+                "annotate",
+                "-r",
+                revision,
+                "-T",
+                ANNOTATE_TEMPLATE,
+                relative_path.as_str(),
+            ],
+            &repo_root,
+        )?;
+
+        let entries: Vec<BlameEntry> = output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, FIELD_SEP);
+                let change_id = fields.next()?;
+                let timestamp = fields.next()?.trim().parse().unwrap_or(0);
+                let author = fields.next()?;
+                Some((
+                    change_id,
+                    timestamp,
+                    author,
+                    fields.next().unwrap_or_default(),
+                ))
+            })
+            .enumerate()
+            .map(
+                |(index, (change_id, timestamp, author, summary))| BlameEntry {
+                    line: index + 1,
+                    hash: change_id.to_string(),
+                    author: author.to_string(),
+                    timestamp,
+                    summary: summary.to_string(),
+                },
+            )
+            .collect();
+        log::info!("jj blame path={relative_path} lines={}", entries.len());
+
+        Ok(BlameResult {
+            provider: "jj".to_string(),
+            entries,
+        })
+    }
+
+    fn show_bytes(&self, request: &ShowRequest) -> Result<Vec<u8>, VcsError> {
+        let repo_root = request
+            .repo_root
+            .as_deref()
+            .map(PathBuf::from)
+            .ok_or_else(|| "Repository root is required.".to_string())?;
+        if !repo_root.is_dir() {
+            return Err("Repository root does not exist.".into());
+        }
+        let pattern = file_pattern(&request.path.replace('\\', "/"));
+        run_jj_bytes(
+            &[
+                "file",
+                "show",
+                "-r",
+                request.revision.as_str(),
+                "--",
+                pattern.as_str(),
+            ],
+            &repo_root,
+        )
+    }
+
+    fn show_revision(&self, file_path: &Path, revision: &str) -> Result<VcsRevisionFile, VcsError> {
+        let (repo_root, relative_path) = resolve_repo_path(file_path)?;
+        let (change_id, commit_id) = resolve_revision(&repo_root, revision)?;
+        let pattern = file_pattern(&relative_path);
+        let bytes = run_jj_bytes(
+            &[
+                "file",
+                "show",
+                "-r",
+                commit_id.as_str(),
+                "--",
+                pattern.as_str(),
+            ],
+            &repo_root,
+        )
+        .map_err(|_| format!("{relative_path} does not exist at {revision}."))?;
+        Ok(VcsRevisionFile {
+            revision: change_id,
+            path: relative_path,
+            content: decode_text(&bytes),
+        })
+    }
+
+    fn changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        let repo_root = match (&request.repo_root, &request.working_path) {
+            (Some(root), _) => PathBuf::from(root),
+            (None, Some(path)) => resolve_workspace_root(working_dir(Path::new(path))?)?,
+            (None, None) => return Err("Repository root is required.".into()),
+        };
+        let (_, commit_id) = resolve_revision(&repo_root, &request.revision)?;
+
+        let header = run_jj(
+            &[
+                "log",
+                "--no-graph",
+                "-r",
+                commit_id.as_str(),
+                "-T",
+                CHANGESET_TEMPLATE,
+            ],
+            &repo_root,
+        )?;
+        let mut fields = header.splitn(6, FIELD_SEP);
+        let change_id = fields.next().unwrap_or_default().to_string();
+        let _ = fields.next();
+        // Merges are listed against their first parent, as for git.
+        let first_parent = fields
+            .next()
+            .and_then(|parents| parents.split_whitespace().next())
+            .map(str::to_string);
+        let timestamp = fields
+            .next()
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .unwrap_or(0);
+        let author = fields.next().unwrap_or_default().to_string();
+        let description = fields.next().unwrap_or_default().trim_end().to_string();
+
+        let output = run_jj(&["diff", "--summary", "-r", commit_id.as_str()], &repo_root)?;
+        let files = parse_summary(&output)
+            .into_iter()
+            .map(|(status, path, original_path)| {
+                let kind = match status {
+                    'A' | 'C' => VcsChangeKind::Added,
+                    'D' => VcsChangeKind::Deleted,
+                    'R' => VcsChangeKind::Renamed,
+                    _ => VcsChangeKind::Modified,
+                };
+                let has_base = kind != VcsChangeKind::Added || original_path.is_some();
+                VcsChangesetFile {
+                    kind,
+                    path,
+                    original_path,
+                    base_revision: first_parent.clone().filter(|_| has_base),
+                    revision: (kind != VcsChangeKind::Deleted).then(|| commit_id.clone()),
+                }
+            })
+            .collect();
+
+        Ok(VcsChangeset {
+            provider: "jj".to_string(),
+            revision: change_id,
+            author,
+            timestamp,
+            description,
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            files,
+        })
+    }
+
+    fn status(&self, path: &Path) -> Result<VcsStatusResult, VcsError> {
+        let repo_root = resolve_workspace_root(working_dir(path)?)?;
+        // jj tracks new files automatically, so pending changes are the working-copy
+        // commit's own diff.
+        let output = run_jj(&["diff", "--summary", "-r", "@"], &repo_root)?;
+        let conflicted: Vec<String> = run_jj(&["resolve", "--list"], &repo_root)
+            .map(|output| {
+                output
+                    .lines()
+                    .filter_map(|line| line.split_whitespace().next())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let entries: Vec<VcsStatusEntry> = parse_summary(&output)
+            .into_iter()
+            .map(|(status, file, original_path)| {
+                let kind = if conflicted.contains(&file) {
+                    VcsChangeKind::Conflicted
+                } else {
+                    match status {
+                        'A' | 'C' => VcsChangeKind::Added,
+                        'R' => VcsChangeKind::Renamed,
+                        'D' => VcsChangeKind::Deleted,
+                        _ => VcsChangeKind::Modified,
+                    }
+                };
+                VcsStatusEntry {
+                    kind,
+                    working_path: repo_root.join(&file).to_string_lossy().to_string(),
+                    path: file,
+                    original_path,
+                    base_revision: (kind != VcsChangeKind::Added).then(|| "@-".to_string()),
+                }
+            })
+            .collect();
+        log::info!(
+            "jj status repo={} entries={}",
+            repo_root.display(),
+            entries.len()
+        );
+
+        Ok(VcsStatusResult {
+            provider: "jj".to_string(),
+            repo_root: Some(repo_root.to_string_lossy().to_string()),
+            entries,
+        })
+    }
+
+    fn cache_revision(&self, path: &Path) -> Option<String> {
+        let cwd = working_dir(path).ok()?;
+        let commit = run_jj(&["log", "--no-graph", "-r", "@", "-T", "commit_id"], cwd).ok()?;
+        Some(commit.trim().to_string()).filter(|commit| !commit.is_empty())
+    }
+}
@@ -0,0 +1,685 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri_plugin_log::log;
+
+use crate::textfile;
+
+use cache::{hex_digest, VcsCache};
+pub(crate) use error::VcsError;
+
+mod cache;
+mod error;
+pub(crate) mod fossil;
+pub(crate) mod git;
+pub(crate) mod hg;
+pub(crate) mod jj;
+pub(crate) mod p4;
+pub(crate) mod runner;
+pub(crate) mod svn;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsHistoryEntry {
+    pub(crate) provider: String,
+    pub(crate) hash: String,
+    pub(crate) timestamp: i64,
+    pub(crate) author: String,
+    pub(crate) summary: String,
+    pub(crate) path: String,
+    pub(crate) deleted: bool,
+    /// Sources this revision was integrated from (p4 only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) integrations: Vec<VcsIntegration>,
+}
+
+/// One `... from` integration record of a p4 revision, e.g. the branch it was copied
+/// or merged from.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsIntegration {
+    /// How it was integrated, e.g. `branch from`, `copy from`, `merge from`.
+    pub(crate) how: String,
+    /// Depot path of the source file.
+    pub(crate) path: String,
+    /// Exclusive start of the integrated source range; `None` when it starts at the
+    /// first revision.
+    pub(crate) start_revision: Option<String>,
+    /// Newest integrated source revision, e.g. `#5`; shows the source with `path`.
+    pub(crate) revision: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsHistoryResult {
+    pub(crate) provider: String,
+    pub(crate) repo_root: Option<String>,
+    pub(crate) relative_path: String,
+    pub(crate) entries: Vec<VcsHistoryEntry>,
+}
+
+/// Partial history emitted while a `vcs_history` request is still running.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsHistoryBatch {
+    pub(crate) request_id: String,
+    pub(crate) entries: Vec<VcsHistoryEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BlameEntry {
+    pub(crate) line: usize,
+    pub(crate) hash: String,
+    pub(crate) author: String,
+    pub(crate) timestamp: i64,
+    pub(crate) summary: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BlameResult {
+    pub(crate) provider: String,
+    pub(crate) entries: Vec<BlameEntry>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum VcsChangeKind {
+    Modified,
+    Added,
+    Deleted,
+    Renamed,
+    Untracked,
+    Conflicted,
+}
+
+/// One pending change in a working copy.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsStatusEntry {
+    pub(crate) kind: VcsChangeKind,
+    /// Repo-relative path for git/hg/jj/fossil/svn, depot path for p4.
+    pub(crate) path: String,
+    /// Absolute local path of the working file (may no longer exist when deleted).
+    pub(crate) working_path: String,
+    /// Source path of a rename, in the same form as `path`.
+    pub(crate) original_path: Option<String>,
+    /// Revision to pass to `vcs_show_file` for the unmodified version, if there is one.
+    pub(crate) base_revision: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsStatusResult {
+    pub(crate) provider: String,
+    pub(crate) repo_root: Option<String>,
+    pub(crate) entries: Vec<VcsStatusEntry>,
+}
+
+/// One file touched by a commit, changelist or revision.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsChangesetFile {
+    pub(crate) kind: VcsChangeKind,
+    /// Repo-relative path for git/hg/jj/fossil, depot path for p4, repository path for svn.
+    pub(crate) path: String,
+    /// Rename or copy source, in the same form as `path`.
+    pub(crate) original_path: Option<String>,
+    /// Revision to show for the file before the change; `None` when it was added.
+    pub(crate) base_revision: Option<String>,
+    /// Revision to show for the file after the change; `None` when it was deleted.
+    pub(crate) revision: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsChangeset {
+    pub(crate) provider: String,
+    pub(crate) revision: String,
+    pub(crate) author: String,
+    pub(crate) timestamp: i64,
+    /// Full commit message / changelist description.
+    pub(crate) description: String,
+    /// Value to pass as `repoRoot` when showing the files of this changeset.
+    pub(crate) repo_root: Option<String>,
+    pub(crate) files: Vec<VcsChangesetFile>,
+}
+
+/// One side of a revision-vs-revision comparison.
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsRevisionFile {
+    /// Resolved revision (commit hash, hg node, jj change id, fossil check-in, `#N` file
+    /// revision, svn revision number).
+    pub(crate) revision: String,
+    /// Path of the file at that revision, which differs from the working path after a rename.
+    pub(crate) path: String,
+    pub(crate) content: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VcsRevisionCompare {
+    pub(crate) provider: String,
+    pub(crate) original: VcsRevisionFile,
+    pub(crate) modified: VcsRevisionFile,
+}
+
+/// Arguments for listing a changeset; `working_path` is a local file used to pick the
+/// workspace/working copy, as for [`ShowRequest`].
+pub(crate) struct ChangesetRequest {
+    pub(crate) revision: String,
+    pub(crate) repo_root: Option<String>,
+    pub(crate) working_path: Option<String>,
+}
+
+/// Arguments for blaming a file, either the working copy or a historical revision.
+pub(crate) struct BlameRequest {
+    /// Absolute working-copy path, or a repository-relative path when `repo_root` is set.
+    pub(crate) path: String,
+    pub(crate) revision: Option<String>,
+    pub(crate) repo_root: Option<String>,
+}
+
+/// Arguments for fetching a file's content at a revision.
+///
+/// `path` is whatever the provider reported in `VcsHistoryEntry.path` (repo-relative for
+/// git, depot path for p4), `working_path` is the local file the history was requested for.
+pub(crate) struct ShowRequest {
+    pub(crate) revision: String,
+    pub(crate) path: String,
+    pub(crate) repo_root: Option<String>,
+    pub(crate) working_path: Option<String>,
+}
+
+/// One version-control backend (git, p4, svn, hg, ...).
+///
+/// Providers are stateless and blocking; callers are expected to run them on a
+/// blocking thread. Errors are [`VcsError`]s classified by cause: a provider reports
+/// `NotARepository` or `Untracked` when the path simply is not its own, so the
+/// registry can move on to the next provider, and anything else as a real failure.
+pub(crate) trait VcsProvider: Send + Sync {
+    /// Identifier shared with the frontend, e.g. `"git"`.
+    fn id(&self) -> &'static str;
+
+    /// Human-readable name used in logs and combined error messages.
+    fn label(&self) -> &'static str;
+
+    /// Cheap check (no process launch) whether `path` may be managed by this provider.
+    fn detect(&self, path: &Path) -> bool;
+
+    fn history(&self, path: &Path) -> Result<VcsHistoryResult, VcsError>;
+
+    fn blame(&self, request: &BlameRequest) -> Result<BlameResult, VcsError>;
+
+    /// Raw file contents at a revision.
+    fn show_bytes(&self, request: &ShowRequest) -> Result<Vec<u8>, VcsError>;
+
+    /// File contents at a revision, decoded the same way as a local file.
+    fn show(&self, request: &ShowRequest) -> Result<String, VcsError> {
+        let bytes = self.show_bytes(request)?;
+        Ok(decode_text(&bytes))
+    }
+
+    /// Fetches the working file `file_path` as it was at `revision`, a user-entered spec
+    /// such as a branch, tag, `HEAD~3`, `@label`, `#3` or svn revision number.
+    fn show_revision(&self, file_path: &Path, revision: &str) -> Result<VcsRevisionFile, VcsError>;
+
+    /// Lists every file touched by one commit / changelist / revision.
+    fn changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError>;
+
+    /// Lists the files shelved in a pending changelist. Each file's `revision` is
+    /// accepted by [`VcsProvider::show_bytes`] and yields the shelved content.
+    fn shelved_changeset(&self, _request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        Err(format!("{} has no shelved changes.", self.label()).into())
+    }
+
+    /// Lists pending changes in the working copy containing `path` (a file or directory).
+    fn status(&self, path: &Path) -> Result<VcsStatusResult, VcsError>;
+
+    /// Cheap fingerprint of the revision the working copy at `path` (a file or the
+    /// repository root) is based on, such as the HEAD commit. Cached history and blame
+    /// are only reused while it is unchanged; `None` bypasses the cache.
+    fn cache_revision(&self, _path: &Path) -> Option<String> {
+        None
+    }
+}
+
+/// Ordered list of providers; earlier providers win when several match a file.
+pub(crate) struct VcsRegistry {
+    providers: Vec<Box<dyn VcsProvider>>,
+    cache: VcsCache,
+}
+
+impl Default for VcsRegistry {
+    fn default() -> Self {
+        // Providers that detect a marker directory come first. p4 has no marker and
+        // claims every path while the client is installed, so it is the catch-all.
+        Self::new(vec![
+            Box::new(jj::JjProvider),
+            Box::new(fossil::FossilProvider),
+            Box::new(git::GitProvider),
+            Box::new(svn::SvnProvider),
+            Box::new(hg::HgProvider),
+            Box::new(p4::P4Provider),
+        ])
+    }
+}
+
+impl VcsRegistry {
+    pub(crate) fn new(providers: Vec<Box<dyn VcsProvider>>) -> Self {
+        Self {
+            providers,
+            cache: VcsCache::default(),
+        }
+    }
+
+    /// Persists history and blame results under `dir` from now on.
+    pub(crate) fn set_cache_dir(&self, dir: PathBuf) {
+        self.cache.set_dir(dir);
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<&dyn VcsProvider> {
+        self.providers
+            .iter()
+            .find(|provider| provider.id() == id)
+            .map(|provider| provider.as_ref())
+    }
+
+    fn require(&self, id: &str) -> Result<&dyn VcsProvider, VcsError> {
+        self.get(id)
+            .ok_or_else(|| format!("Unknown VCS provider: {id}").into())
+    }
+
+    /// Runs `run` with the provider the caller asked for by id.
+    fn with_chosen<T>(
+        &self,
+        id: &str,
+        run: impl FnOnce(&dyn VcsProvider) -> Result<T, VcsError>,
+    ) -> Result<T, VcsError> {
+        let provider = self.require(id)?;
+        runner::with_chosen_provider(|| run(provider))
+    }
+
+    /// Runs `action` with each provider that detects `path`, in priority order, and
+    /// returns the first success.
+    ///
+    /// `Ok(None)` means every provider reported that the path simply isn't under its
+    /// control; otherwise the first other error is returned, so its kind reaches the UI.
+    fn first_success<T>(
+        &self,
+        path: &Path,
+        action: &str,
+        run: impl Fn(&dyn VcsProvider) -> Result<T, VcsError>,
+    ) -> Result<Option<T>, VcsError> {
+        let mut errors: Vec<(&dyn VcsProvider, VcsError)> = Vec::new();
+        for provider in &self.providers {
+            let provider = provider.as_ref();
+            if !provider.detect(path) {
+                log::info!("{} not detected path={}", provider.label(), path.display());
+                continue;
+            }
+            match run(provider) {
+                Ok(result) => return Ok(Some(result)),
+                Err(error) if error.is_interruption() => return Err(error),
+                Err(error) => {
+                    log::warn!(
+                        "{} {action} failed path={} error={error}",
+                        provider.label(),
+                        path.display()
+                    );
+                    errors.push((provider, error));
+                }
+            }
+        }
+
+        let summary = errors
+            .iter()
+            .map(|(provider, error)| format!("{}_error={error}", provider.id()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        match errors.into_iter().find(|(_, error)| !error.is_no_history()) {
+            Some((provider, error)) => {
+                log::warn!(
+                    "{} {action} unavailable path={} {summary}",
+                    provider.label(),
+                    path.display()
+                );
+                Err(error)
+            }
+            None => {
+                log::info!("No VCS {action} path={} {summary}", path.display());
+                Ok(None)
+            }
+        }
+    }
+
+    /// Probes providers in priority order and returns the first history found.
+    ///
+    /// Falls back to an empty history when every provider reports that the file
+    /// simply isn't under its control.
+    pub(crate) fn history(&self, path: &str) -> Result<VcsHistoryResult, VcsError> {
+        log::info!("vcs_history requested path={path}");
+        let file_path = PathBuf::from(path);
+        working_file_parent(&file_path)?;
+
+        let result = self.first_success(&file_path, "history", |provider| {
+            self.cached_history(provider, &file_path)
+        })?;
+        Ok(result.unwrap_or_else(|| empty_history(path)))
+    }
+
+    fn cached_history(
+        &self,
+        provider: &dyn VcsProvider,
+        file_path: &Path,
+    ) -> Result<VcsHistoryResult, VcsError> {
+        let Some(revision) = provider.cache_revision(file_path) else {
+            return provider.history(file_path);
+        };
+        let identity = format!("{}\0{}", provider.id(), file_path.display());
+        if let Some(result) = self.cache.load("history", &identity, &revision) {
+            log::info!(
+                "{} history cache hit path={} revision={revision}",
+                provider.label(),
+                file_path.display()
+            );
+            return Ok(result);
+        }
+        let result = provider.history(file_path)?;
+        self.cache.store("history", &identity, &revision, &result);
+        Ok(result)
+    }
+
+    fn cached_blame(
+        &self,
+        provider: &dyn VcsProvider,
+        request: &BlameRequest,
+    ) -> Result<BlameResult, VcsError> {
+        let base = request.repo_root.as_deref().unwrap_or(&request.path);
+        let Some(mut revision) = provider.cache_revision(Path::new(base)) else {
+            return provider.blame(request);
+        };
+        // Blaming the working file also covers uncommitted edits.
+        if request.repo_root.is_none() && request.revision.is_none() {
+            match std::fs::read(&request.path) {
+                Ok(bytes) => revision = format!("{revision}:{}", hex_digest(&bytes)),
+                Err(_) => return provider.blame(request),
+            }
+        }
+        let identity = format!(
+            "{}\0{}\0{}\0{}",
+            provider.id(),
+            request.repo_root.as_deref().unwrap_or_default(),
+            request.path,
+            request.revision.as_deref().unwrap_or_default()
+        );
+        if let Some(result) = self.cache.load("blame", &identity, &revision) {
+            log::info!(
+                "{} blame cache hit path={} revision={revision}",
+                provider.label(),
+                request.path
+            );
+            return Ok(result);
+        }
+        let result = provider.blame(request)?;
+        self.cache.store("blame", &identity, &revision, &result);
+        Ok(result)
+    }
+
+    /// Lists pending changes for the working copy containing `path`.
+    ///
+    /// Like [`VcsRegistry::history`], an unversioned location yields an empty result.
+    pub(crate) fn status(&self, path: &str) -> Result<VcsStatusResult, VcsError> {
+        log::info!("vcs_status requested path={path}");
+        let target = PathBuf::from(path);
+        working_dir(&target)?;
+
+        let result = self.first_success(&target, "status", |provider| provider.status(&target))?;
+        Ok(result.unwrap_or_else(|| VcsStatusResult {
+            provider: "none".to_string(),
+            repo_root: None,
+            entries: Vec::new(),
+        }))
+    }
+
+    /// Blames with the requested provider, or with the first provider that succeeds.
+    pub(crate) fn blame(
+        &self,
+        request: &BlameRequest,
+        provider: Option<&str>,
+    ) -> Result<BlameResult, VcsError> {
+        log::info!(
+            "vcs_blame requested path={} revision={:?} provider={:?}",
+            request.path,
+            request.revision,
+            provider
+        );
+
+        if let Some(id) = provider {
+            return self.with_chosen(id, |provider| self.cached_blame(provider, request));
+        }
+
+        let mut failure = None;
+        for provider in &self.providers {
+            // A repo-relative path can't be probed on disk, so only skip on detect
+            // when we were given a real working-copy path.
+            if request.repo_root.is_none() && !provider.detect(Path::new(&request.path)) {
+                continue;
+            }
+            match self.cached_blame(provider.as_ref(), request) {
+                Ok(result) => return Ok(result),
+                Err(error) if error.is_interruption() => return Err(error),
+                Err(error) => {
+                    log::warn!(
+                        "{} blame failed path={} error={error}",
+                        provider.label(),
+                        request.path
+                    );
+                    if failure.is_none() && !error.is_no_history() {
+                        failure = Some(error);
+                    }
+                }
+            }
+        }
+
+        Err(failure.unwrap_or_else(|| VcsError::Untracked {
+            message: "No VCS blame available for this file".to_string(),
+        }))
+    }
+
+    pub(crate) fn show(&self, provider: &str, request: &ShowRequest) -> Result<String, VcsError> {
+        self.with_chosen(provider, |provider| provider.show(request))
+    }
+
+    pub(crate) fn show_bytes(
+        &self,
+        provider: &str,
+        request: &ShowRequest,
+    ) -> Result<Vec<u8>, VcsError> {
+        self.with_chosen(provider, |provider| provider.show_bytes(request))
+    }
+
+    /// Fetches one file at two arbitrary revisions with the same provider.
+    pub(crate) fn compare_revisions(
+        &self,
+        path: &str,
+        original: &str,
+        modified: &str,
+        provider: Option<&str>,
+    ) -> Result<VcsRevisionCompare, VcsError> {
+        log::info!(
+            "vcs_compare_revisions requested path={path} original={original} modified={modified} provider={provider:?}"
+        );
+        let file_path = PathBuf::from(path);
+        working_file_parent(&file_path)?;
+
+        let compare = |provider: &dyn VcsProvider| {
+            Ok(VcsRevisionCompare {
+                provider: provider.id().to_string(),
+                original: provider.show_revision(&file_path, original)?,
+                modified: provider.show_revision(&file_path, modified)?,
+            })
+        };
+        if let Some(id) = provider {
+            return self.with_chosen(id, compare);
+        }
+        self.first_success(&file_path, "revision compare", compare)?
+            .ok_or_else(|| VcsError::NotARepository {
+                message: "File is not under version control.".to_string(),
+            })
+    }
+
+    pub(crate) fn changeset(
+        &self,
+        provider: &str,
+        request: &ChangesetRequest,
+    ) -> Result<VcsChangeset, VcsError> {
+        log::info!(
+            "vcs_changeset requested provider={provider} revision={}",
+            request.revision
+        );
+        self.with_chosen(provider, |provider| provider.changeset(request))
+    }
+
+    pub(crate) fn shelved_changeset(
+        &self,
+        provider: &str,
+        request: &ChangesetRequest,
+    ) -> Result<VcsChangeset, VcsError> {
+        log::info!(
+            "vcs_shelved_changeset requested provider={provider} revision={}",
+            request.revision
+        );
+        self.with_chosen(provider, |provider| provider.shelved_changeset(request))
+    }
+}
+
+/// Decodes file contents from a VCS like a local file; binary content stays lossy UTF-8.
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+    let file = textfile::decode(bytes);
+    if file.binary {
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        file.text
+    }
+}
+
+/// Validates that `path` is an existing file and returns its parent directory.
+pub(crate) fn working_file_parent(path: &Path) -> Result<&Path, VcsError> {
+    if !path.is_file() {
+        return Err("Path is not a file.".into());
+    }
+    path.parent().ok_or_else(|| "Invalid file path.".into())
+}
+
+/// Returns `path` itself for a directory, or the parent directory of an existing file.
+pub(crate) fn working_dir(path: &Path) -> Result<&Path, VcsError> {
+    if path.is_dir() {
+        return Ok(path);
+    }
+    working_file_parent(path)
+}
+
+/// Other paths `file_path` was known by according to its history, newest first.
+///
+/// Used to locate a file at an older revision after it was renamed or moved.
+pub(crate) fn history_paths(
+    provider: &dyn VcsProvider,
+    file_path: &Path,
+    current: &str,
+) -> Vec<String> {
+    let entries = match provider.history(file_path) {
+        Ok(history) => history.entries,
+        Err(error) => {
+            log::warn!(
+                "{} rename lookup failed path={} error={error}",
+                provider.label(),
+                file_path.display()
+            );
+            return Vec::new();
+        }
+    };
+    let mut paths: Vec<String> = Vec::new();
+    for entry in entries {
+        if entry.path != current && !paths.contains(&entry.path) {
+            paths.push(entry.path);
+        }
+    }
+    paths
+}
+
+/// Walks up from `path` looking for a directory that contains `marker`.
+pub(crate) fn find_ancestor_with(path: &Path, marker: &str) -> Option<PathBuf> {
+    let mut current = if path.is_dir() {
+        Some(path)
+    } else {
+        path.parent()
+    };
+    while let Some(dir) = current {
+        if dir.join(marker).exists() {
+            return Some(dir.to_path_buf());
+        }
+        current = dir.parent();
+    }
+    None
+}
+
+/// Whether an executable named `program` can be found on `PATH`.
+pub(crate) fn is_on_path(program: &str) -> bool {
+    let Some(paths) = std::env::var_os("PATH") else {
+        return false;
+    };
+    let extensions: &[&str] = if cfg!(windows) {
+        &["exe", "cmd", "bat"]
+    } else {
+        &[""]
+    };
+    std::env::split_paths(&paths).any(|dir| {
+        extensions.iter().any(|ext| {
+            let candidate = if ext.is_empty() {
+                dir.join(program)
+            } else {
+                dir.join(format!("{program}.{ext}"))
+            };
+            candidate.is_file()
+        })
+    })
+}
+
+pub(crate) fn to_git_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+pub(crate) fn truncate_for_log(value: &str, max_len: usize) -> String {
+    let normalized = value.replace('\r', "").replace('\n', "\\n");
+    if normalized.len() <= max_len {
+        return normalized;
+    }
+    let mut truncated = normalized;
+    let mut cut = max_len;
+    while !truncated.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    truncated.truncate(cut);
+    truncated.push_str("...(truncated)");
+    truncated
+}
+
+pub(crate) fn fallback_relative_path(path: &str) -> String {
+    let file_path = PathBuf::from(path);
+    file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn empty_history(path: &str) -> VcsHistoryResult {
+    VcsHistoryResult {
+        provider: "none".to_string(),
+        repo_root: None,
+        relative_path: fallback_relative_path(path),
+        entries: Vec::new(),
+    }
+}
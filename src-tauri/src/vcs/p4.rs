@@ -0,0 +1,1293 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::Duration;
+
+use tauri_plugin_log::log;
+
+use super::{
+    decode_text, history_paths, is_on_path, runner, truncate_for_log, working_dir,
+    working_file_parent, BlameEntry, BlameRequest, BlameResult, ChangesetRequest, ShowRequest,
+    VcsChangeKind, VcsChangeset, VcsChangesetFile, VcsError, VcsHistoryEntry, VcsHistoryResult,
+    VcsIntegration, VcsProvider, VcsRevisionFile, VcsStatusEntry, VcsStatusResult,
+};
+
+pub(crate) struct P4Provider;
+
+#[derive(Default, Clone, serde::Deserialize)]
+pub(crate) struct P4Settings {
+    pub(crate) port: String,
+    pub(crate) user: String,
+    pub(crate) client: String,
+    /// Where p4 keeps login tickets (P4TICKETS); empty keeps p4's default.
+    pub(crate) tickets: String,
+    /// Where p4 keeps trusted SSL fingerprints (P4TRUST); empty keeps p4's default.
+    pub(crate) trust: String,
+}
+
+impl P4Settings {
+    /// Whether no connection settings are given; ticket and trust files apply either way.
+    fn is_empty(&self) -> bool {
+        self.port.is_empty() && self.user.is_empty() && self.client.is_empty()
+    }
+}
+
+static GLOBAL_P4_SETTINGS: OnceLock<Mutex<P4Settings>> = OnceLock::new();
+
+fn get_global_p4_settings() -> P4Settings {
+    GLOBAL_P4_SETTINGS
+        .get_or_init(|| Mutex::new(P4Settings::default()))
+        .lock()
+        .map(|guard| guard.clone())
+        .unwrap_or_default()
+}
+
+pub(crate) fn set_global_p4_settings(settings: P4Settings) {
+    if let Some(mutex) = GLOBAL_P4_SETTINGS.get() {
+        if let Ok(mut guard) = mutex.lock() {
+            *guard = settings;
+        }
+    } else {
+        let _ = GLOBAL_P4_SETTINGS.set(Mutex::new(settings));
+    }
+}
+
+/// A named connection used for files under `root_pattern`, so several workspaces can
+/// be used side by side without switching the global settings.
+#[derive(Default, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct P4Profile {
+    pub(crate) name: String,
+    /// Client root the profile applies to, e.g. `D:/p4/*/main`; `*` and `?` match
+    /// within one path component. Profiles without a pattern are never picked by
+    /// location.
+    #[serde(default)]
+    pub(crate) root_pattern: String,
+    #[serde(default)]
+    pub(crate) port: String,
+    #[serde(default)]
+    pub(crate) user: String,
+    #[serde(default)]
+    pub(crate) client: String,
+}
+
+static P4_PROFILES: Mutex<Vec<P4Profile>> = Mutex::new(Vec::new());
+
+pub(crate) fn set_p4_profiles(profiles: Vec<P4Profile>) {
+    if let Ok(mut guard) = P4_PROFILES.lock() {
+        *guard = profiles;
+    }
+}
+
+/// The profile whose pattern covers `cwd`; the most specific pattern wins.
+fn find_p4_profile(cwd: &Path) -> Option<P4Profile> {
+    let dir = path_components(&cwd.to_string_lossy());
+    let profiles = P4_PROFILES.lock().ok()?;
+    profiles
+        .iter()
+        .filter_map(|profile| {
+            let pattern = path_components(profile.root_pattern.trim());
+            let covers = !pattern.is_empty()
+                && pattern.len() <= dir.len()
+                && pattern
+                    .iter()
+                    .zip(&dir)
+                    .all(|(pattern, component)| wildcard_match(pattern, component));
+            covers.then_some((pattern.len(), profile))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, profile)| profile.clone())
+}
+
+/// Splits a path on either separator; case-insensitive on Windows like the file system.
+fn path_components(path: &str) -> Vec<String> {
+    let path = if cfg!(windows) {
+        path.to_lowercase()
+    } else {
+        path.to_string()
+    };
+    path.split(['/', '\\'])
+        .filter(|component| !component.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // Classic greedy matcher: remember the last `*` and retry from one character later.
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+struct P4ConfigInfo {
+    name: String,
+    path: PathBuf,
+}
+
+fn find_p4config_info(cwd: &Path) -> Option<P4ConfigInfo> {
+    let candidates = ["p4config.txt", ".p4config", "p4config"];
+    let mut current = Some(cwd);
+    while let Some(dir) = current {
+        for name in candidates {
+            let path = dir.join(name);
+            if path.is_file() {
+                return Some(P4ConfigInfo {
+                    name: name.to_string(),
+                    path,
+                });
+            }
+        }
+        current = dir.parent();
+    }
+    None
+}
+
+fn apply_p4_env(command: &mut Command, cwd: &Path) {
+    // 票据和信任文件位置与连接方式无关，p4config 中的同名设置仍然优先
+    let global_settings = get_global_p4_settings();
+    if !global_settings.tickets.is_empty() {
+        command.env("P4TICKETS", &global_settings.tickets);
+    }
+    if !global_settings.trust.is_empty() {
+        command.env("P4TRUST", &global_settings.trust);
+    }
+
+    // 1. 首先尝试在目录层级中查找 p4config 文件（最精确，与文件路径相关）
+    if let Some(info) = find_p4config_info(cwd) {
+        command.env("P4CONFIG", &info.name);
+        log::info!(
+            "P4CONFIG resolved name={} path={}",
+            info.name,
+            info.path.display()
+        );
+        return;
+    }
+
+    // 2. 其次使用客户端根目录与文件位置匹配的命名配置
+    if let Some(profile) = find_p4_profile(cwd) {
+        for (name, value) in [
+            ("P4PORT", &profile.port),
+            ("P4USER", &profile.user),
+            ("P4CLIENT", &profile.client),
+        ] {
+            if !value.is_empty() {
+                command.env(name, value);
+            }
+        }
+        log::info!(
+            "P4 env from profile {} (root {}): port={}, user={}, client={}",
+            profile.name,
+            profile.root_pattern,
+            profile.port,
+            profile.user,
+            profile.client
+        );
+        return;
+    }
+
+    // 3. 然后使用 UI 中配置的备用设置（用户主动为当前场景配置的）
+    if !global_settings.is_empty() {
+        if !global_settings.port.is_empty() {
+            command.env("P4PORT", &global_settings.port);
+        }
+        if !global_settings.user.is_empty() {
+            command.env("P4USER", &global_settings.user);
+        }
+        if !global_settings.client.is_empty() {
+            command.env("P4CLIENT", &global_settings.client);
+        }
+        log::info!(
+            "P4 env from UI settings: port={}, user={}, client={}",
+            if global_settings.port.is_empty() {
+                "(not set)"
+            } else {
+                &global_settings.port
+            },
+            if global_settings.user.is_empty() {
+                "(not set)"
+            } else {
+                &global_settings.user
+            },
+            if global_settings.client.is_empty() {
+                "(not set)"
+            } else {
+                &global_settings.client
+            }
+        );
+        return;
+    }
+
+    // 4. 最后检查系统环境变量 P4CONFIG（可能是其他项目留下的全局配置）
+    let env_config = std::env::var("P4CONFIG")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty());
+
+    if let Some(config) = &env_config {
+        log::info!("P4CONFIG from system env: {config}");
+        return;
+    }
+
+    log::info!(
+        "No P4 config found from {} (no local p4config, no UI settings, no P4CONFIG env)",
+        cwd.display()
+    );
+}
+
+fn log_p4_info_for_path(path: &Path) {
+    let cwd = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut command = Command::new("p4");
+    command.current_dir(cwd).args(["info"]);
+    apply_p4_env(&mut command, cwd);
+    match runner::output(&mut command, None) {
+        Ok(output) => {
+            let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            let message = if !stdout.is_empty() { stdout } else { stderr };
+            if output.status.success() {
+                if !message.is_empty() {
+                    log::info!("p4 info output={}", truncate_for_log(&message, 4000));
+                } else {
+                    log::info!("p4 info returned no output");
+                }
+            } else {
+                log::warn!(
+                    "p4 info failed cwd={} status={} output={}",
+                    cwd.display(),
+                    output.status,
+                    truncate_for_log(&message, 4000)
+                );
+            }
+        }
+        Err(error) => {
+            log::warn!("p4 info failed to run: {error}");
+        }
+    }
+}
+
+pub(crate) fn run_p4<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<String, VcsError> {
+    run_p4_bytes(args, cwd).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like [`run_p4`], but returns stdout untouched (file contents may be binary).
+pub(crate) fn run_p4_bytes<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<Vec<u8>, VcsError> {
+    run_p4_with_input(args, cwd, None)
+}
+
+/// Runs p4 with `input` on stdin, e.g. the argument list for `p4 -x -`. When the
+/// server asks for a login and [`may_prompt_login`] allows it, the user is prompted
+/// once and the command retried; otherwise the auth failure is returned right away.
+fn run_p4_with_input<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+    input: Option<&str>,
+) -> Result<Vec<u8>, VcsError> {
+    let generation = LOGIN_GENERATION.load(Ordering::SeqCst);
+    match run_p4_once(args, cwd, input) {
+        Err(VcsError::AuthRequired { message, .. }) if may_prompt_login(cwd) => {
+            login(cwd, generation, message)?;
+            run_p4_once(args, cwd, input)
+        }
+        result => result,
+    }
+}
+
+fn run_p4_once<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+    input: Option<&str>,
+) -> Result<Vec<u8>, VcsError> {
+    let mut command = Command::new("p4");
+    command.current_dir(cwd).args(args);
+    apply_p4_env(&mut command, cwd);
+
+    let output = runner::output(&mut command, input.map(str::as_bytes))
+        .map_err(|error| VcsError::launch("p4", error))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let message = if stderr.is_empty() { stdout } else { stderr };
+        let error = classify_p4_error(&output, message);
+        log::warn!(
+            "p4 failed cwd={} args={args:?} error={error}",
+            cwd.display()
+        );
+        return Err(error);
+    }
+
+    Ok(output.stdout)
+}
+
+/// How long a login prompt stays open before the waiting command gives up.
+const LOGIN_PROMPT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Asks the user for the password of the server named in `message`.
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct P4LoginPrompt {
+    pub(crate) id: u64,
+    pub(crate) message: String,
+}
+
+type LoginPromptHook = Box<dyn Fn(P4LoginPrompt) + Send + Sync>;
+
+/// The prompt that is open right now. Requests that hit an expired ticket meanwhile
+/// join it instead of asking again.
+struct PendingLogin {
+    id: u64,
+    /// Taken by [`answer_login_prompt`]; the prompting request runs `p4 login`.
+    reply: Option<mpsc::Sender<Option<String>>>,
+    /// Told whether the login succeeded.
+    waiters: Vec<mpsc::Sender<bool>>,
+}
+
+static LOGIN_PROMPT_HOOK: OnceLock<LoginPromptHook> = OnceLock::new();
+/// Only held to inspect or update the prompt, never while the user is typing.
+static PENDING_LOGIN: Mutex<Option<PendingLogin>> = Mutex::new(None);
+/// Bumped after every successful login; a command that failed under an older
+/// generation just retries instead of prompting again.
+static LOGIN_GENERATION: AtomicU64 = AtomicU64::new(0);
+static NEXT_LOGIN_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
+/// Directories p4 has already answered for, so an expired ticket there is its own.
+static KNOWN_WORKSPACES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Installs how login prompts reach the user. Without it, auth failures are returned
+/// as they are.
+pub(crate) fn set_login_prompt_hook(hook: impl Fn(P4LoginPrompt) + Send + Sync + 'static) {
+    let _ = LOGIN_PROMPT_HOOK.set(Box::new(hook));
+}
+
+/// Delivers the user's answer to prompt `id`; `None` means the prompt was dismissed.
+/// Answers to prompts that are no longer waiting are dropped.
+pub(crate) fn answer_login_prompt(id: u64, password: Option<String>) {
+    let reply = PENDING_LOGIN
+        .lock()
+        .ok()
+        .and_then(|mut pending| match pending.as_mut() {
+            Some(login) if login.id == id => login.reply.take(),
+            _ => None,
+        });
+    match reply {
+        Some(sender) => {
+            let _ = sender.send(password);
+        }
+        None => log::info!("p4 login reply for prompt {id} arrived after it closed"),
+    }
+}
+
+/// Checks the ticket for the workspace containing `path` and prompts for a password
+/// when it has expired. This is how the user logs in after a probe failed fast.
+pub(crate) fn ensure_login(path: &Path) -> Result<(), VcsError> {
+    let cwd = working_dir(path)?;
+    runner::with_chosen_provider(|| run_p4(&["login", "-s"], cwd))?;
+    remember_workspace(cwd);
+    Ok(())
+}
+
+fn remember_workspace(dir: &Path) {
+    if let Ok(mut known) = KNOWN_WORKSPACES.lock() {
+        if !known.iter().any(|known| dir.starts_with(known)) {
+            known.retain(|known| !known.starts_with(dir));
+            known.push(dir.to_path_buf());
+        }
+    }
+}
+
+/// Whether an auth failure under `cwd` may open a login prompt: p4 was picked by the
+/// caller, or the path is already known to be a p4 workspace. Detection lets p4 probe
+/// every path, so anything else fails fast instead of asking an unrelated folder for a
+/// P4 password.
+fn may_prompt_login(cwd: &Path) -> bool {
+    runner::is_chosen_provider()
+        || KNOWN_WORKSPACES
+            .lock()
+            .is_ok_and(|known| known.iter().any(|known| cwd.starts_with(known)))
+        || find_p4config_info(cwd).is_some()
+        || find_p4_profile(cwd).is_some()
+}
+
+fn login(cwd: &Path, generation: u64, message: String) -> Result<(), VcsError> {
+    let auth_required = |message: String| VcsError::AuthRequired {
+        tool: "p4".to_string(),
+        message,
+    };
+    let Some(hook) = LOGIN_PROMPT_HOOK.get() else {
+        return Err(auth_required(message));
+    };
+
+    let mut pending = PENDING_LOGIN.lock().map_err(|error| error.to_string())?;
+    if LOGIN_GENERATION.load(Ordering::SeqCst) != generation {
+        log::info!("p4 login already renewed by another request; retrying");
+        return Ok(());
+    }
+    if let Some(login) = pending.as_mut() {
+        let (sender, receiver) = mpsc::channel();
+        login.waiters.push(sender);
+        let id = login.id;
+        drop(pending);
+        log::info!("p4 login prompt {id} already open; waiting for it");
+        return match runner::wait_for(&receiver, LOGIN_PROMPT_TIMEOUT) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(auth_required(message)),
+            Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => {
+                Err(auth_required(message))
+            }
+            Err(error) => Err(VcsError::launch("p4", error)),
+        };
+    }
+    let id = NEXT_LOGIN_PROMPT_ID.fetch_add(1, Ordering::SeqCst);
+    let (sender, receiver) = mpsc::channel();
+    *pending = Some(PendingLogin {
+        id,
+        reply: Some(sender),
+        waiters: Vec::new(),
+    });
+    drop(pending);
+
+    log::info!("p4 login required cwd={} prompt={id}", cwd.display());
+    hook(P4LoginPrompt {
+        id,
+        message: message.clone(),
+    });
+    let result = match runner::wait_for(&receiver, LOGIN_PROMPT_TIMEOUT) {
+        // p4 login reads the password from stdin when it is not a terminal.
+        Ok(Some(password)) => run_p4_once(&["login"], cwd, Some(&format!("{password}\n")))
+            .map(|_| ())
+            .map_err(|error| error.or_else_failed(auth_required)),
+        Ok(None) => Err(auth_required(message)),
+        Err(error) if error.kind() == std::io::ErrorKind::BrokenPipe => Err(auth_required(message)),
+        Err(error) => Err(VcsError::launch("p4", error)),
+    };
+
+    // Bump the generation before closing the prompt, so a request failing right now
+    // either joins this prompt or sees the new ticket.
+    let waiters = match PENDING_LOGIN.lock() {
+        Ok(mut pending) => {
+            if result.is_ok() {
+                LOGIN_GENERATION.fetch_add(1, Ordering::SeqCst);
+            }
+            match pending.take() {
+                Some(login) if login.id == id => login.waiters,
+                other => {
+                    *pending = other;
+                    Vec::new()
+                }
+            }
+        }
+        Err(_) => Vec::new(),
+    };
+    for waiter in waiters {
+        let _ = waiter.send(result.is_ok());
+    }
+    if result.is_ok() {
+        remember_workspace(cwd);
+        log::info!("p4 login succeeded cwd={}", cwd.display());
+    }
+    result
+}
+
+/// Classifies a p4 failure. p4 has no stable error codes on the command line, but its
+/// messages come from the server and are not translated by the client.
+fn classify_p4_error(output: &Output, message: String) -> VcsError {
+    let lower = message.to_lowercase();
+    let has = |needles: &[&str]| needles.iter().any(|needle| lower.contains(needle));
+    let tool = "p4".to_string();
+    if has(&[
+        "p4passwd",
+        "perforce password",
+        "session has expired",
+        "please login again",
+        "password invalid",
+    ]) {
+        VcsError::AuthRequired { tool, message }
+    } else if has(&["connect to server failed", "tcp connect to"]) {
+        VcsError::ServerUnreachable { tool, message }
+    } else if has(&[
+        "must create client",
+        "no such client",
+        "client unknown",
+        "not under client's root",
+        "not in client view",
+    ]) || (lower.contains("client")
+        && (lower.contains("unknown") || lower.contains("not found")))
+    {
+        VcsError::NotARepository { message }
+    } else if has(&["not in client", "not on client", "no such file"]) {
+        VcsError::Untracked { message }
+    } else {
+        VcsError::failed("p4", output, message)
+    }
+}
+
+/// Splits `p4 -ztag` output into records of `... key value` fields.
+///
+/// Multi-line values (changelist descriptions) continue on lines without the `... `
+/// prefix, possibly blank ones, so a blank line only ends a record when the next line
+/// starts a new field.
+fn parse_ztag_records(output: &str) -> Vec<HashMap<String, String>> {
+    fn finish(records: &mut Vec<HashMap<String, String>>, mut record: HashMap<String, String>) {
+        for value in record.values_mut() {
+            value.truncate(value.trim_end().len());
+        }
+        records.push(record);
+    }
+
+    let mut records = Vec::new();
+    let mut current: HashMap<String, String> = HashMap::new();
+    let mut last_key: Option<String> = None;
+    let mut lines = output.lines().map(str::trim_end).peekable();
+    while let Some(line) = lines.next() {
+        if let Some(rest) = line.strip_prefix("... ") {
+            let (key, value) = rest.split_once(' ').unwrap_or((rest, ""));
+            current.insert(key.to_string(), value.to_string());
+            last_key = Some(key.to_string());
+            continue;
+        }
+        let ends_record =
+            line.is_empty() && lines.peek().is_none_or(|next| next.starts_with("... "));
+        if ends_record {
+            if !current.is_empty() {
+                finish(&mut records, std::mem::take(&mut current));
+            }
+            last_key = None;
+        } else if let Some(value) = last_key.as_ref().and_then(|key| current.get_mut(key)) {
+            value.push('\n');
+            value.push_str(line);
+        }
+    }
+    if !current.is_empty() {
+        finish(&mut records, current);
+    }
+    records
+}
+
+/// Maps a `p4 opened`/`fstat` action to a change kind; `None` for the delete half of a move.
+fn change_kind(action: &str) -> Option<VcsChangeKind> {
+    match action {
+        "move/delete" => None,
+        "move/add" => Some(VcsChangeKind::Renamed),
+        "add" | "branch" | "import" => Some(VcsChangeKind::Added),
+        "delete" => Some(VcsChangeKind::Deleted),
+        _ => Some(VcsChangeKind::Modified),
+    }
+}
+
+/// Normalizes a user-entered revision: `@label`, `@123`, `@=123`, `#3`, `#head`, or a
+/// bare changelist number.
+fn revision_spec(revision: &str) -> Result<String, VcsError> {
+    let revision = revision.trim();
+    if !revision.is_empty() && revision.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(format!("@{revision}"));
+    }
+    let valid = revision.len() > 1
+        && (revision.starts_with('@') || revision.starts_with('#'))
+        && !revision[1..].contains(['@', '#'])
+        && !revision.contains(char::is_whitespace);
+    if !valid {
+        return Err(VcsError::InvalidRevision {
+            message: "Invalid revision.".to_string(),
+        });
+    }
+    Ok(revision.to_string())
+}
+
+/// Launches p4 for [`blame_with`]; a stand-in can count or fake invocations.
+pub(crate) trait P4Runner {
+    fn run(&self, args: &[&str], cwd: &Path, input: Option<&str>) -> Result<String, VcsError>;
+}
+
+/// Runs the real p4 client.
+struct CliRunner;
+
+impl P4Runner for CliRunner {
+    fn run(&self, args: &[&str], cwd: &Path, input: Option<&str>) -> Result<String, VcsError> {
+        run_p4_with_input(args, cwd, input)
+            .map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+    }
+}
+
+/// Author, time and first description line of a changelist.
+type ChangeInfo = (String, i64, String);
+
+/// Reads `change`/`user`/`time`/`desc` from `-ztag changes` or `-ztag describe` output.
+fn collect_change_info(output: &str, info: &mut HashMap<String, ChangeInfo>) {
+    for record in parse_ztag_records(output) {
+        let Some(change) = record.get("change") else {
+            continue;
+        };
+        let field = |key: &str| record.get(key).cloned().unwrap_or_default();
+        let summary = field("desc")
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        info.insert(
+            change.clone(),
+            (field("user"), field("time").parse().unwrap_or(0), summary),
+        );
+    }
+}
+
+/// Blames a working file with a fixed number of p4 launches, however many
+/// changelists it spans: one `annotate`, one `changes` over the file's history and at
+/// most one batched `describe` (arguments fed through `-x -`) for anything missing.
+pub(crate) fn blame_with(runner: &dyn P4Runner, path: &str) -> Result<BlameResult, VcsError> {
+    let parent = working_file_parent(Path::new(path))?;
+
+    // -I attributes lines that arrived through integrations to their original change.
+    let output = runner.run(&["annotate", "-q", "-c", "-I", path], parent, None)?;
+    let lines: Vec<&str> = output
+        .lines()
+        .filter_map(|line| {
+            let (change, _) = line.split_once(':')?;
+            let change = change.trim();
+            (!change.is_empty() && change.chars().all(|c| c.is_ascii_digit())).then_some(change)
+        })
+        .collect();
+
+    let mut info: HashMap<String, ChangeInfo> = HashMap::new();
+    match runner.run(&["-ztag", "changes", "-i", "-l", path], parent, None) {
+        Ok(changes) => collect_change_info(&changes, &mut info),
+        Err(error) => log::warn!("P4 blame: changes lookup failed error={error}"),
+    }
+
+    let mut missing: Vec<&str> = lines
+        .iter()
+        .copied()
+        .filter(|change| !info.contains_key(*change))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    if !missing.is_empty() {
+        missing.sort_unstable();
+        let input = missing.join("\n");
+        match runner.run(
+            &["-x", "-", "-ztag", "describe", "-s"],
+            parent,
+            Some(&input),
+        ) {
+            Ok(describe) => collect_change_info(&describe, &mut info),
+            Err(error) => log::warn!("P4 blame: describe failed error={error}"),
+        }
+    }
+
+    let entries: Vec<BlameEntry> = lines
+        .iter()
+        .enumerate()
+        .map(|(index, change)| {
+            let (author, timestamp, summary) = info.get(*change).cloned().unwrap_or_default();
+            BlameEntry {
+                line: index + 1,
+                hash: change.to_string(),
+                author,
+                timestamp,
+                summary,
+            }
+        })
+        .collect();
+    log::info!(
+        "P4 blame: returning {} entries from {} changelists",
+        entries.len(),
+        info.len()
+    );
+
+    Ok(BlameResult {
+        provider: "p4".to_string(),
+        entries,
+    })
+}
+
+/// Lists the files of a submitted changelist, or with `shelved` the files shelved in a
+/// pending one. Shelved files are shown through `@=CL` (see `show_bytes`) against the
+/// depot revision they were shelved from.
+fn describe_changeset(request: &ChangesetRequest, shelved: bool) -> Result<VcsChangeset, VcsError> {
+    let change = request.revision.as_str();
+    if change.is_empty() || !change.chars().all(|c| c.is_ascii_digit()) {
+        return Err(VcsError::InvalidRevision {
+            message: "Invalid changelist.".to_string(),
+        });
+    }
+    let working_path = PathBuf::from(request.working_path.as_deref().unwrap_or_default());
+    let cwd = working_dir(&working_path)?;
+
+    let args: &[&str] = if shelved {
+        &["-ztag", "describe", "-S", "-s", change]
+    } else {
+        &["-ztag", "describe", "-s", change]
+    };
+    let output = run_p4(args, cwd)?;
+    let record = parse_ztag_records(&output)
+        .into_iter()
+        .next()
+        .ok_or_else(|| VcsError::InvalidRevision {
+            message: format!("Changelist {change} not found."),
+        })?;
+    let field = |key: &str| record.get(key).cloned().unwrap_or_default();
+
+    let mut files = Vec::new();
+    for index in 0.. {
+        let Some(depot_file) = record.get(&format!("depotFile{index}")) else {
+            break;
+        };
+        let action = field(&format!("action{index}"));
+        let rev = field(&format!("rev{index}")).parse::<u32>().unwrap_or(0);
+        let kind = match action.as_str() {
+            "move/delete" => VcsChangeKind::Deleted,
+            other => change_kind(other).unwrap_or(VcsChangeKind::Modified),
+        };
+        // A submitted file's rev is the revision it created; a shelved file's rev is
+        // the one it was opened at, which is its base.
+        let base_rev = if shelved { rev } else { rev.saturating_sub(1) };
+        let base_revision = match kind {
+            VcsChangeKind::Added | VcsChangeKind::Renamed => None,
+            _ => (base_rev > 0).then(|| format!("#{base_rev}")),
+        };
+        let revision = if shelved {
+            change.to_string()
+        } else {
+            format!("#{rev}")
+        };
+        files.push(VcsChangesetFile {
+            kind,
+            path: depot_file.clone(),
+            original_path: None,
+            base_revision,
+            revision: (kind != VcsChangeKind::Deleted).then_some(revision),
+        });
+    }
+
+    Ok(VcsChangeset {
+        provider: "p4".to_string(),
+        revision: change.to_string(),
+        author: field("user"),
+        timestamp: field("time").parse::<i64>().unwrap_or(0),
+        description: field("desc").trim_end().to_string(),
+        repo_root: None,
+        files,
+    })
+}
+
+impl VcsProvider for P4Provider {
+    fn id(&self) -> &'static str {
+        "p4"
+    }
+
+    fn label(&self) -> &'static str {
+        "P4"
+    }
+
+    fn detect(&self, _path: &Path) -> bool {
+        // Workspaces can be configured through `p4 set`/the registry, so there is no
+        // reliable on-disk marker; only rule p4 out when the client isn't installed.
+        is_on_path("p4")
+    }
+
+    fn history(&self, file_path: &Path) -> Result<VcsHistoryResult, VcsError> {
+        let parent = working_file_parent(file_path)?;
+        let path = file_path.to_string_lossy().to_string();
+
+        // `-i` continues into the branch the file was created from, so history does not
+        // stop at the branch point.
+        let log_output = match run_p4(
+            &["-ztag", "filelog", "-i", "-t", "-l", path.as_str()],
+            parent,
+        ) {
+            Ok(output) => {
+                remember_workspace(parent);
+                output
+            }
+            Err(error) => {
+                log_p4_info_for_path(file_path);
+                return Err(error);
+            }
+        };
+
+        struct PendingP4Entry {
+            change: String,
+            timestamp: i64,
+            author: String,
+            summary: String,
+            path: String,
+            deleted: bool,
+            integrations: Vec<VcsIntegration>,
+        }
+
+        let mut entries = Vec::new();
+        // The first record is the file itself, later ones are its branch ancestors.
+        let mut first_depot_path: Option<String> = None;
+        let mut current_depot_path: Option<String> = None;
+        let mut pending: Option<PendingP4Entry> = None;
+
+        let mut flush_pending = |pending: &mut Option<PendingP4Entry>| {
+            if let Some(entry) = pending.take() {
+                entries.push(VcsHistoryEntry {
+                    provider: "p4".to_string(),
+                    hash: entry.change,
+                    timestamp: entry.timestamp,
+                    author: entry.author,
+                    summary: entry.summary,
+                    path: entry.path,
+                    deleted: entry.deleted,
+                    // `... into` records point at later targets, not at lineage.
+                    integrations: entry
+                        .integrations
+                        .into_iter()
+                        .filter(|integration| {
+                            integration.how.ends_with(" from") && !integration.path.is_empty()
+                        })
+                        .collect(),
+                });
+            }
+        };
+
+        for line in log_output.lines() {
+            let trimmed = line.trim_end();
+            let Some(rest) = trimmed.strip_prefix("... ") else {
+                continue;
+            };
+
+            let mut parts = rest.splitn(2, ' ');
+            let key = parts.next().unwrap_or("");
+            let base_key = key
+                .trim_end_matches(|c: char| c.is_ascii_digit() || c == ',')
+                .trim();
+            let value = parts.next().unwrap_or("").trim();
+
+            match base_key {
+                "depotFile" if !value.is_empty() => {
+                    first_depot_path.get_or_insert_with(|| value.to_string());
+                    current_depot_path = Some(value.to_string());
+                }
+                "change" => {
+                    flush_pending(&mut pending);
+                    let entry_path = current_depot_path.clone().unwrap_or_else(|| path.clone());
+                    pending = Some(PendingP4Entry {
+                        change: value.to_string(),
+                        timestamp: 0,
+                        author: String::new(),
+                        summary: String::new(),
+                        path: entry_path,
+                        deleted: false,
+                        integrations: Vec::new(),
+                    });
+                }
+                "time" => {
+                    if let Some(entry) = pending.as_mut() {
+                        entry.timestamp = value.parse::<i64>().unwrap_or(0);
+                    }
+                }
+                "user" => {
+                    if let Some(entry) = pending.as_mut() {
+                        entry.author = value.to_string();
+                    }
+                }
+                "desc" => {
+                    if let Some(entry) = pending.as_mut() {
+                        if entry.summary.is_empty() {
+                            entry.summary = value.to_string();
+                        }
+                    }
+                }
+                "action" => {
+                    if let Some(entry) = pending.as_mut() {
+                        if value.contains("delete") {
+                            entry.deleted = true;
+                        }
+                    }
+                }
+                // Integration records follow their revision as `how`, `file`, `srev`, `erev`.
+                "how" => {
+                    if let Some(entry) = pending.as_mut() {
+                        entry.integrations.push(VcsIntegration {
+                            how: value.to_string(),
+                            path: String::new(),
+                            start_revision: None,
+                            revision: String::new(),
+                        });
+                    }
+                }
+                "file" | "srev" | "erev" => {
+                    if let Some(integration) = pending
+                        .as_mut()
+                        .and_then(|entry| entry.integrations.last_mut())
+                    {
+                        match base_key {
+                            "file" => integration.path = value.to_string(),
+                            "srev" => {
+                                integration.start_revision =
+                                    (value != "#none").then(|| value.to_string())
+                            }
+                            _ => integration.revision = value.to_string(),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        flush_pending(&mut pending);
+        // Ancestors are listed after the file; interleave them by changelist.
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.hash.parse::<u64>().unwrap_or(0)));
+
+        let relative_path = first_depot_path.unwrap_or(path);
+        if entries.is_empty() {
+            if log_output.trim().is_empty() {
+                log::warn!("p4 filelog returned empty output path={relative_path}");
+            } else {
+                let output_preview = truncate_for_log(&log_output, 4000);
+                log::warn!(
+                    "p4 history parsed 0 entries path={relative_path} output_preview={output_preview}"
+                );
+            }
+        }
+
+        Ok(VcsHistoryResult {
+            provider: "p4".to_string(),
+            repo_root: None,
+            relative_path,
+            entries,
+        })
+    }
+
+    fn blame(&self, request: &BlameRequest) -> Result<BlameResult, VcsError> {
+        if request.revision.is_some() {
+            return Err("P4 blame is only available for the working copy.".into());
+        }
+        let result = blame_with(&CliRunner, &request.path)?;
+        if let Some(parent) = Path::new(&request.path)
+            .parent()
+            .filter(|parent| parent.is_absolute())
+        {
+            remember_workspace(parent);
+        }
+        Ok(result)
+    }
+
+    fn show_bytes(&self, request: &ShowRequest) -> Result<Vec<u8>, VcsError> {
+        let revision = &request.revision;
+        // `#have`/`#head`/`#N` select a file revision, anything else is a changelist;
+        // `@=CL` also prints the version shelved in a pending changelist.
+        let spec = if let Some(file_rev) = revision.strip_prefix('#') {
+            if !(file_rev == "have"
+                || file_rev == "head"
+                || (!file_rev.is_empty() && file_rev.chars().all(|c| c.is_ascii_digit())))
+            {
+                return Err(VcsError::InvalidRevision {
+                    message: "Invalid file revision.".to_string(),
+                });
+            }
+            format!("{}{revision}", request.path)
+        } else {
+            if revision.is_empty() || !revision.chars().all(|c| c.is_ascii_digit()) {
+                return Err(VcsError::InvalidRevision {
+                    message: "Invalid changelist.".to_string(),
+                });
+            }
+            format!("{}@={revision}", request.path)
+        };
+        let working_path = PathBuf::from(request.working_path.as_deref().unwrap_or_default());
+        let cwd = working_path
+            .parent()
+            .ok_or_else(|| "Invalid file path.".to_string())?;
+        run_p4_bytes(&["print", "-q", spec.as_str()], cwd)
+    }
+
+    fn show_revision(&self, file_path: &Path, revision: &str) -> Result<VcsRevisionFile, VcsError> {
+        let parent = working_file_parent(file_path)?;
+        let spec = revision_spec(revision)?;
+        let fstat = |path: &str| {
+            let target = format!("{path}{spec}");
+            run_p4(
+                &[
+                    "-ztag",
+                    "fstat",
+                    "-T",
+                    "depotFile,headRev,headAction",
+                    target.as_str(),
+                ],
+                parent,
+            )
+            .ok()
+            .and_then(|output| parse_ztag_records(&output).into_iter().next())
+            .filter(|record| record.contains_key("headRev"))
+        };
+
+        // Moved files only resolve under their older depot path at older revisions.
+        let local_path = file_path.to_string_lossy().to_string();
+        let record = match fstat(&local_path) {
+            Some(record) => record,
+            None => history_paths(self, file_path, &local_path)
+                .iter()
+                .find_map(|path| fstat(path))
+                .ok_or_else(|| format!("{} does not exist at {spec}.", file_path.display()))?,
+        };
+        let depot_file = record.get("depotFile").cloned().unwrap_or(local_path);
+        let head_rev = &record["headRev"];
+        if record
+            .get("headAction")
+            .is_some_and(|action| action.contains("delete"))
+        {
+            return Err(format!("{depot_file} is deleted at {spec}.").into());
+        }
+
+        let file_revision = format!("#{head_rev}");
+        let target = format!("{depot_file}{file_revision}");
+        let content = decode_text(&run_p4_bytes(&["print", "-q", target.as_str()], parent)?);
+        Ok(VcsRevisionFile {
+            revision: file_revision,
+            path: depot_file,
+            content,
+        })
+    }
+
+    fn changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        describe_changeset(request, false)
+    }
+
+    fn shelved_changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        describe_changeset(request, true)
+    }
+
+    fn status(&self, path: &Path) -> Result<VcsStatusResult, VcsError> {
+        let cwd = working_dir(path)?;
+
+        let info = run_p4(&["-ztag", "info"], cwd)?;
+        let info = parse_ztag_records(&info)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        let client = info
+            .get("clientName")
+            .filter(|name| !name.is_empty() && name.as_str() != "*unknown*")
+            .ok_or_else(|| VcsError::NotARepository {
+                message: "P4 client unknown for this workspace.".to_string(),
+            })?;
+
+        // Only opened files are reported; finding unopened local edits would need a full
+        // `p4 reconcile -n` scan of the workspace.
+        let scope = format!("//{client}/...");
+        let output = run_p4(&["-ztag", "fstat", "-Ro", scope.as_str()], cwd)?;
+
+        let entries: Vec<VcsStatusEntry> = parse_ztag_records(&output)
+            .into_iter()
+            .filter_map(|record| {
+                let kind = change_kind(record.get("action")?)?;
+                let base_revision = match kind {
+                    VcsChangeKind::Added => None,
+                    _ => Some("#have".to_string()),
+                };
+                Some(VcsStatusEntry {
+                    kind,
+                    path: record.get("depotFile")?.clone(),
+                    working_path: record.get("clientFile")?.clone(),
+                    original_path: record.get("movedFile").cloned(),
+                    base_revision,
+                })
+            })
+            .collect();
+        log::info!("p4 status client={client} entries={}", entries.len());
+        remember_workspace(info.get("clientRoot").map_or(cwd, Path::new));
+
+        Ok(VcsStatusResult {
+            provider: "p4".to_string(),
+            repo_root: info.get("clientRoot").cloned(),
+            entries,
+        })
+    }
+
+    fn cache_revision(&self, path: &Path) -> Option<String> {
+        // History and blame follow the depot, so the file's newest change and the
+        // synced revision both matter.
+        let parent = working_file_parent(path).ok()?;
+        let target = path.to_string_lossy().to_string();
+        let output = run_p4(
+            &[
+                "-ztag",
+                "fstat",
+                "-T",
+                "headChange,haveRev",
+                target.as_str(),
+            ],
+            parent,
+        )
+        .ok()?;
+        let record = parse_ztag_records(&output).into_iter().next()?;
+        let head_change = record.get("headChange")?;
+        let have_rev = record.get("haveRev").map(String::as_str).unwrap_or("none");
+        Some(format!("{head_change}#{have_rev}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::fmt::Write as _;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Answers annotate/changes/describe for a file whose every line comes from its own
+    /// changelist, and counts how often p4 would have been launched.
+    struct CountingRunner {
+        changelists: usize,
+        /// How many of the changelists `changes -i` reports; the rest need `describe`.
+        in_changes: usize,
+        launches: Cell<usize>,
+        described: RefCell<Vec<String>>,
+    }
+
+    impl CountingRunner {
+        fn new(changelists: usize, in_changes: usize) -> Self {
+            Self {
+                changelists,
+                in_changes,
+                launches: Cell::new(0),
+                described: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn change(index: usize) -> usize {
+            1000 + index
+        }
+    }
+
+    fn ztag_change(output: &mut String, change: &str) {
+        let _ = write!(
+            output,
+            "... change {change}\n... user user{change}\n... time 1700000000\n\
+             ... desc Summary of {change}\nsecond line\n\n"
+        );
+    }
+
+    impl P4Runner for CountingRunner {
+        fn run(&self, args: &[&str], _cwd: &Path, input: Option<&str>) -> Result<String, VcsError> {
+            self.launches.set(self.launches.get() + 1);
+            let mut output = String::new();
+            if args.contains(&"annotate") {
+                for index in 0..self.changelists {
+                    let _ = writeln!(output, "{}: line {index}", Self::change(index));
+                }
+            } else if args.contains(&"changes") {
+                for index in 0..self.in_changes {
+                    ztag_change(&mut output, &Self::change(index).to_string());
+                }
+            } else if args.contains(&"describe") {
+                for change in input.unwrap_or_default().lines() {
+                    self.described.borrow_mut().push(change.to_string());
+                    ztag_change(&mut output, change);
+                }
+            } else {
+                panic!("unexpected p4 call {args:?}");
+            }
+            Ok(output)
+        }
+    }
+
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "gcompare-p4-test-{}-{name}.txt",
+                std::process::id()
+            ));
+            std::fs::write(&path, "content").unwrap();
+            Self(path)
+        }
+
+        fn path(&self) -> String {
+            self.0.to_string_lossy().to_string()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn blame_launch_count_does_not_grow_with_changelists() {
+        let file = TempFile::new("launches");
+        for changelists in [1, 10, 300, 900] {
+            let runner = CountingRunner::new(changelists, changelists / 2);
+            let blame = blame_with(&runner, &file.path()).unwrap();
+            assert_eq!(blame.entries.len(), changelists);
+            // annotate + changes + one batched describe.
+            assert_eq!(runner.launches.get(), 3, "{changelists} changelists");
+            assert_eq!(
+                runner.described.borrow().len(),
+                changelists - changelists / 2
+            );
+        }
+    }
+
+    #[test]
+    fn blame_skips_describe_when_changes_covers_every_line() {
+        let file = TempFile::new("covered");
+        let runner = CountingRunner::new(500, 500);
+        let blame = blame_with(&runner, &file.path()).unwrap();
+        assert_eq!(runner.launches.get(), 2);
+        assert!(runner.described.borrow().is_empty());
+
+        let last = blame.entries.last().unwrap();
+        assert_eq!(last.line, 500);
+        assert_eq!(last.hash, "1499");
+        assert_eq!(last.author, "user1499");
+        assert_eq!(last.timestamp, 1_700_000_000);
+        assert_eq!(last.summary, "Summary of 1499");
+    }
+
+    #[test]
+    fn blame_fills_described_changelists() {
+        let file = TempFile::new("described");
+        let runner = CountingRunner::new(400, 0);
+        let blame = blame_with(&runner, &file.path()).unwrap();
+        assert_eq!(runner.launches.get(), 3);
+        assert!(blame
+            .entries
+            .iter()
+            .all(|entry| entry.summary == format!("Summary of {}", entry.hash)));
+    }
+
+    #[test]
+    fn login_prompt_needs_a_chosen_provider_or_a_known_workspace() {
+        let workspace =
+            std::env::temp_dir().join(format!("gcompare-p4-test-{}-workspace", std::process::id()));
+        let nested = workspace.join("src");
+        assert!(!may_prompt_login(&nested));
+        assert!(runner::with_chosen_provider(|| may_prompt_login(&nested)));
+        assert!(!may_prompt_login(&nested));
+
+        remember_workspace(&workspace);
+        assert!(may_prompt_login(&nested));
+    }
+}
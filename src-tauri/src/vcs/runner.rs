@@ -0,0 +1,273 @@
+use std::cell::{Cell, RefCell};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tauri_plugin_log::log;
+
+use super::VcsHistoryEntry;
+
+/// Applied when the frontend has not sent a timeout yet; 0 disables the limit.
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+/// How often a running command checks for cancellation and the deadline.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static TIMEOUT_SECS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_SECS);
+
+pub(crate) fn set_timeout_secs(secs: u64) {
+    log::info!("vcs command timeout set to {secs}s");
+    TIMEOUT_SECS.store(secs, Ordering::Relaxed);
+}
+
+fn timeout() -> Option<Duration> {
+    match TIMEOUT_SECS.load(Ordering::Relaxed) {
+        0 => None,
+        secs => Some(Duration::from_secs(secs)),
+    }
+}
+
+/// Shared flag that stops every command started for one request.
+#[derive(Clone, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Receives history entries as soon as a provider has parsed them.
+pub(crate) type HistoryProgress = Box<dyn Fn(&[VcsHistoryEntry]) + Send>;
+
+struct RequestScope {
+    cancel: CancelToken,
+    deadline: Option<Instant>,
+    progress: Option<HistoryProgress>,
+}
+
+thread_local! {
+    static SCOPE: RefCell<Option<RequestScope>> = const { RefCell::new(None) };
+    static CHOSEN_PROVIDER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `task` with a cancellation token, deadline and progress sink that every
+/// command started on this thread picks up, so providers need no extra parameters.
+pub(crate) fn with_request<T>(
+    cancel: CancelToken,
+    progress: Option<HistoryProgress>,
+    task: impl FnOnce() -> T,
+) -> T {
+    let scope = RequestScope {
+        cancel,
+        deadline: timeout().map(|limit| Instant::now() + limit),
+        progress,
+    };
+    let previous = SCOPE.with(|cell| cell.replace(Some(scope)));
+    let result = task();
+    SCOPE.with(|cell| cell.replace(previous));
+    result
+}
+
+/// Runs `task` for a provider the caller named rather than one found by probing.
+/// Only then may a provider stop to ask the user something, such as a password.
+pub(crate) fn with_chosen_provider<T>(task: impl FnOnce() -> T) -> T {
+    let previous = CHOSEN_PROVIDER.with(|cell| cell.replace(true));
+    let result = task();
+    CHOSEN_PROVIDER.with(|cell| cell.set(previous));
+    result
+}
+
+pub(crate) fn is_chosen_provider() -> bool {
+    CHOSEN_PROVIDER.with(Cell::get)
+}
+
+/// Hands partial history to the current request, if it asked for progress.
+pub(crate) fn report_history(entries: &[VcsHistoryEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    SCOPE.with(|cell| {
+        if let Some(progress) = cell
+            .borrow()
+            .as_ref()
+            .and_then(|scope| scope.progress.as_ref())
+        {
+            progress(entries);
+        }
+    });
+}
+
+fn current_limits() -> (CancelToken, Option<Instant>) {
+    SCOPE.with(|cell| match cell.borrow().as_ref() {
+        Some(scope) => (scope.cancel.clone(), scope.deadline),
+        // Outside a request each command gets the full timeout on its own.
+        None => (
+            CancelToken::default(),
+            timeout().map(|limit| Instant::now() + limit),
+        ),
+    })
+}
+
+/// Waits up to `limit` for a reply from outside the request, e.g. a password the
+/// user types. Gives up early on cancellation, and the time spent waiting is added
+/// to the request's deadline so the commands that follow still get their full timeout.
+pub(crate) fn wait_for<T>(receiver: &mpsc::Receiver<T>, limit: Duration) -> io::Result<T> {
+    let (cancel, _) = current_limits();
+    let started = Instant::now();
+    let result = loop {
+        if cancel.is_cancelled() {
+            break Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "the request was cancelled",
+            ));
+        }
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(value) => break Ok(value),
+            Err(mpsc::RecvTimeoutError::Timeout) if started.elapsed() < limit => {}
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                break Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("no reply within {}s", limit.as_secs()),
+                ))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                break Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "the reply was abandoned",
+                ))
+            }
+        }
+    };
+    let waited = started.elapsed();
+    SCOPE.with(|cell| {
+        if let Some(scope) = cell.borrow_mut().as_mut() {
+            scope.deadline = scope.deadline.map(|deadline| deadline + waited);
+        }
+    });
+    result
+}
+
+/// Replacement for [`Command::output`] that can be cancelled and times out.
+pub(crate) fn output(command: &mut Command, input: Option<&[u8]>) -> io::Result<Output> {
+    let mut stdout = Vec::new();
+    let (status, stderr) = run(command, input, |line| stdout.extend_from_slice(line))?;
+    Ok(Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+/// Like [`output`], but hands stdout to `on_line` one line at a time while the
+/// command is still running; the returned stdout is empty.
+pub(crate) fn stream_lines(
+    command: &mut Command,
+    mut on_line: impl FnMut(&str),
+) -> io::Result<Output> {
+    let (status, stderr) = run(command, None, |line| {
+        let line = String::from_utf8_lossy(line);
+        on_line(line.trim_end_matches(['\n', '\r']));
+    })?;
+    Ok(Output {
+        status,
+        stdout: Vec::new(),
+        stderr,
+    })
+}
+
+fn run(
+    command: &mut Command,
+    input: Option<&[u8]>,
+    mut on_line: impl FnMut(&[u8]),
+) -> io::Result<(ExitStatus, Vec<u8>)> {
+    let (cancel, deadline) = current_limits();
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        let input = input.to_vec();
+        // Written from a thread so a command that answers before reading all of
+        // its input cannot deadlock against us; dropping stdin closes it.
+        thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+    }
+
+    let mut stderr_pipe = child.stderr.take();
+    let stderr_reader = thread::spawn(move || {
+        let mut stderr = Vec::new();
+        if let Some(pipe) = stderr_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut stderr);
+        }
+        stderr
+    });
+
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    if let Some(stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            let mut reader = BufReader::new(stdout);
+            loop {
+                let mut line = Vec::new();
+                match reader.read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        if sender.send(line).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    let stop = |child: &mut std::process::Child| -> io::Result<()> {
+        let reason = if cancel.is_cancelled() {
+            io::Error::new(io::ErrorKind::Interrupted, "the request was cancelled")
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            let secs = TIMEOUT_SECS.load(Ordering::Relaxed);
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("the command timed out after {secs}s"),
+            )
+        } else {
+            return Ok(());
+        };
+        log::warn!("vcs command stopped: {reason}");
+        let _ = child.kill();
+        let _ = child.wait();
+        Err(reason)
+    };
+
+    loop {
+        stop(&mut child)?;
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(line) => on_line(&line),
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        stop(&mut child)?;
+        thread::sleep(POLL_INTERVAL);
+    };
+    let stderr = stderr_reader.join().unwrap_or_default();
+    Ok((status, stderr))
+}
@@ -0,0 +1,715 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use tauri_plugin_log::log;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use super::{
+    decode_text, fallback_relative_path, find_ancestor_with, runner, to_git_path, truncate_for_log,
+    working_dir, working_file_parent, BlameEntry, BlameRequest, BlameResult, ChangesetRequest,
+    ShowRequest, VcsChangeKind, VcsChangeset, VcsChangesetFile, VcsError, VcsHistoryEntry,
+    VcsHistoryResult, VcsProvider, VcsRevisionFile, VcsStatusEntry, VcsStatusResult,
+};
+
+pub(crate) struct SvnProvider;
+
+pub(crate) fn run_svn<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<String, VcsError> {
+    run_svn_bytes(args, cwd).map(|stdout| String::from_utf8_lossy(&stdout).to_string())
+}
+
+/// Like [`run_svn`], but returns stdout untouched (file contents may be binary).
+pub(crate) fn run_svn_bytes<S: AsRef<OsStr> + std::fmt::Debug>(
+    args: &[S],
+    cwd: &Path,
+) -> Result<Vec<u8>, VcsError> {
+    let output = runner::output(Command::new("svn").current_dir(cwd).args(args), None)
+        .map_err(|error| VcsError::launch("svn", error))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let message = if stderr.is_empty() { stdout } else { stderr };
+        let error = classify_svn_error(&output, message);
+        log::warn!(
+            "svn failed cwd={} args={args:?} error={error}",
+            cwd.display()
+        );
+        return Err(error);
+    }
+
+    Ok(output.stdout)
+}
+
+/// Classifies an svn failure by its `svn: E155007:` style codes, which unlike the
+/// messages read the same in every locale.
+fn classify_svn_error(output: &Output, message: String) -> VcsError {
+    let has_code = |codes: &[&str]| {
+        codes
+            .iter()
+            .any(|code| message.contains(&format!("{code}:")))
+    };
+    let tool = "svn".to_string();
+    if has_code(&["E155007"]) {
+        VcsError::NotARepository { message }
+    } else if has_code(&["E155010", "E200005", "E200009", "E160013", "E195012"]) {
+        // Not under version control, or not found in the repository.
+        VcsError::Untracked { message }
+    } else if has_code(&["E160006"]) {
+        VcsError::InvalidRevision { message }
+    } else if has_code(&["E170001", "E215004"]) {
+        VcsError::AuthRequired { tool, message }
+    } else if has_code(&["E170013", "E000111", "E670002"]) {
+        VcsError::ServerUnreachable { tool, message }
+    } else {
+        VcsError::failed("svn", output, message)
+    }
+}
+
+/// An element of `svn --xml` output, reported once its end tag has been read.
+struct XmlElement<'a> {
+    /// Names of the enclosing elements, outermost first.
+    parents: &'a [String],
+    name: &'a str,
+    /// Attribute values with entities already decoded.
+    attributes: &'a HashMap<String, String>,
+    /// Decoded text directly inside the element.
+    text: &'a str,
+}
+
+impl XmlElement<'_> {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn parent(&self) -> Option<&str> {
+        self.parents.last().map(String::as_str)
+    }
+}
+
+struct XmlFrame {
+    name: String,
+    attributes: HashMap<String, String>,
+    text: String,
+}
+
+fn open_frame(start: &BytesStart) -> Result<XmlFrame, String> {
+    let mut attributes = HashMap::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|error| format!("Invalid svn XML: {error}"))?;
+        let value = attribute
+            .unescape_value()
+            .map_err(|error| format!("Invalid svn XML: {error}"))?;
+        attributes.insert(
+            String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_string(),
+            value.to_string(),
+        );
+    }
+    Ok(XmlFrame {
+        name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+        attributes,
+        text: String::new(),
+    })
+}
+
+/// Streams `svn --xml` output, calling `visit` for every element in end-tag order,
+/// so children are seen before the element that contains them.
+fn walk_svn_xml(output: &str, mut visit: impl FnMut(XmlElement)) -> Result<(), String> {
+    let invalid = |error: &dyn std::fmt::Display| format!("Invalid svn XML: {error}");
+    let mut reader = Reader::from_str(output);
+    let mut names: Vec<String> = Vec::new();
+    let mut frames: Vec<XmlFrame> = Vec::new();
+    loop {
+        match reader.read_event().map_err(|error| invalid(&error))? {
+            Event::Start(start) => {
+                let frame = open_frame(&start)?;
+                names.push(frame.name.clone());
+                frames.push(frame);
+            }
+            Event::Empty(start) => {
+                let frame = open_frame(&start)?;
+                visit(XmlElement {
+                    parents: &names,
+                    name: &frame.name,
+                    attributes: &frame.attributes,
+                    text: "",
+                });
+            }
+            Event::End(_) => {
+                let Some(frame) = frames.pop() else {
+                    continue;
+                };
+                names.pop();
+                visit(XmlElement {
+                    parents: &names,
+                    name: &frame.name,
+                    attributes: &frame.attributes,
+                    text: &frame.text,
+                });
+            }
+            Event::Text(text) => {
+                if let Some(frame) = frames.last_mut() {
+                    frame
+                        .text
+                        .push_str(&text.xml_content().map_err(|error| invalid(&error))?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(frame) = frames.last_mut() {
+                    frame
+                        .text
+                        .push_str(&data.decode().map_err(|error| invalid(&error))?);
+                }
+            }
+            // Entities arrive as their own events: `&lt;`, `&amp;`, `&#10;`, ...
+            Event::GeneralRef(reference) => {
+                let Some(frame) = frames.last_mut() else {
+                    continue;
+                };
+                match reference
+                    .resolve_char_ref()
+                    .map_err(|error| invalid(&error))?
+                {
+                    Some(ch) => frame.text.push(ch),
+                    None => {
+                        let name = reference.decode().map_err(|error| invalid(&error))?;
+                        match resolve_predefined_entity(&name) {
+                            Some(value) => frame.text.push_str(value),
+                            None => {
+                                return Err(format!("Invalid svn XML: unknown entity &{name};"))
+                            }
+                        }
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn parse_svn_time(value: &str) -> i64 {
+    OffsetDateTime::parse(value, &Rfc3339)
+        .map(|dt| dt.unix_timestamp())
+        .unwrap_or(0)
+}
+
+/// Parses `svn log --xml` output. `path` is the file the log was taken for; a
+/// revision that deleted it is marked as such.
+fn parse_svn_log_entries(output: &str, path: &str) -> Result<Vec<VcsHistoryEntry>, String> {
+    let mut entries = Vec::new();
+    let mut author = String::new();
+    let mut timestamp = 0;
+    let mut message = String::new();
+    let mut deleted = false;
+
+    walk_svn_xml(output, |element| match (element.parent(), element.name) {
+        (Some("logentry"), "author") => author = element.text.to_string(),
+        (Some("logentry"), "date") => timestamp = parse_svn_time(element.text),
+        (Some("logentry"), "msg") => message = element.text.to_string(),
+        (Some("paths"), "path")
+            if element.attr("action") == Some("D")
+                && !path.is_empty()
+                && element.text.ends_with(path) =>
+        {
+            deleted = true;
+        }
+        (_, "logentry") => {
+            let revision = element.attr("revision").unwrap_or_default();
+            if !revision.is_empty() {
+                entries.push(VcsHistoryEntry {
+                    provider: "svn".to_string(),
+                    hash: revision.to_string(),
+                    timestamp,
+                    author: std::mem::take(&mut author),
+                    summary: std::mem::take(&mut message),
+                    path: path.to_string(),
+                    deleted,
+                    integrations: Vec::new(),
+                });
+            }
+            author.clear();
+            message.clear();
+            timestamp = 0;
+            deleted = false;
+        }
+        _ => {}
+    })?;
+
+    Ok(entries)
+}
+
+/// Parses `svn blame --xml` output into one entry per line. Lines without a commit
+/// (local modifications) keep an empty revision.
+fn parse_svn_blame(output: &str) -> Result<Vec<BlameEntry>, String> {
+    let mut entries = Vec::new();
+    let mut revision = String::new();
+    let mut author = String::new();
+    let mut timestamp = 0;
+
+    walk_svn_xml(output, |element| match (element.parent(), element.name) {
+        (Some("commit"), "author") => author = element.text.to_string(),
+        (Some("commit"), "date") => timestamp = parse_svn_time(element.text),
+        (Some("entry"), "commit") => {
+            revision = element.attr("revision").unwrap_or_default().to_string();
+        }
+        (Some("target"), "entry") => {
+            let line = element
+                .attr("line-number")
+                .and_then(|line| line.parse().ok())
+                .unwrap_or(entries.len() + 1);
+            entries.push(BlameEntry {
+                line,
+                hash: std::mem::take(&mut revision),
+                author: std::mem::take(&mut author),
+                timestamp: std::mem::take(&mut timestamp),
+                summary: String::new(),
+            });
+        }
+        _ => {}
+    })?;
+
+    Ok(entries)
+}
+
+/// Revision keywords accepted by `svn cat -r` besides plain numbers.
+const REVISION_KEYWORDS: [&str; 4] = ["BASE", "HEAD", "COMMITTED", "PREV"];
+
+/// Accepts `123`, `r123`, `-r 123` and the revision keywords.
+fn normalize_revision(revision: &str) -> Result<String, VcsError> {
+    let revision = revision.trim();
+    let revision = revision.strip_prefix("-r").unwrap_or(revision).trim_start();
+    let number = revision.strip_prefix('r').unwrap_or(revision);
+    if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(number.to_string());
+    }
+    let keyword = revision.to_uppercase();
+    if REVISION_KEYWORDS.contains(&keyword.as_str()) {
+        return Ok(keyword);
+    }
+    Err(VcsError::InvalidRevision {
+        message: "Invalid revision.".to_string(),
+    })
+}
+
+fn resolve_wc_root(cwd: &Path, target: &str) -> Option<PathBuf> {
+    run_svn(&["info", "--show-item", "wc-root", target], cwd)
+        .ok()
+        .and_then(|output| {
+            output
+                .lines()
+                .next()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+        })
+}
+
+/// Parses `svn status --xml`.
+fn parse_svn_status(output: &str, wc_root: &Path) -> Result<Vec<VcsStatusEntry>, String> {
+    let mut entries = Vec::new();
+    let mut item: Option<String> = None;
+
+    walk_svn_xml(output, |element| match (element.parent(), element.name) {
+        (Some("entry"), "wc-status") => item = element.attr("item").map(str::to_string),
+        (Some("target"), "entry") => {
+            let (Some(path), Some(item)) = (element.attr("path"), item.take()) else {
+                return;
+            };
+            let kind = match item.as_str() {
+                "modified" | "replaced" => VcsChangeKind::Modified,
+                "added" => VcsChangeKind::Added,
+                "deleted" | "missing" => VcsChangeKind::Deleted,
+                "unversioned" => VcsChangeKind::Untracked,
+                "conflicted" => VcsChangeKind::Conflicted,
+                _ => return,
+            };
+            let working_path = PathBuf::from(path);
+            let relative_path = working_path
+                .strip_prefix(wc_root)
+                .map(to_git_path)
+                .unwrap_or_else(|_| to_git_path(&working_path));
+            let base_revision = match kind {
+                VcsChangeKind::Added | VcsChangeKind::Untracked => None,
+                _ => Some("BASE".to_string()),
+            };
+            entries.push(VcsStatusEntry {
+                kind,
+                path: relative_path,
+                working_path: path.to_string(),
+                original_path: None,
+                base_revision,
+            });
+        }
+        _ => {}
+    })?;
+
+    Ok(entries)
+}
+
+/// Parses the `<paths>` of a `svn log -v --xml` entry; directories are skipped.
+fn parse_changed_paths(output: &str, revision: u64) -> Result<Vec<VcsChangesetFile>, String> {
+    let mut files = Vec::new();
+    walk_svn_xml(output, |element| {
+        if element.parent() != Some("paths")
+            || element.name != "path"
+            || element.attr("kind") == Some("dir")
+        {
+            return;
+        }
+        let kind = match element.attr("action") {
+            Some("A") => VcsChangeKind::Added,
+            Some("D") => VcsChangeKind::Deleted,
+            Some(_) => VcsChangeKind::Modified,
+            None => return,
+        };
+        let original_path = element.attr("copyfrom-path").map(str::to_string);
+        let base_revision = match kind {
+            VcsChangeKind::Added if original_path.is_some() => {
+                element.attr("copyfrom-rev").map(str::to_string)
+            }
+            VcsChangeKind::Added => None,
+            _ => Some(revision.saturating_sub(1).to_string()),
+        };
+        files.push(VcsChangesetFile {
+            kind,
+            path: element.text.to_string(),
+            original_path,
+            base_revision,
+            revision: (kind != VcsChangeKind::Deleted).then(|| revision.to_string()),
+        });
+    })?;
+    Ok(files)
+}
+
+/// Maps each revision in `svn log --xml` output to the first line of its message.
+fn parse_summaries(output: &str) -> Result<HashMap<String, String>, String> {
+    Ok(parse_svn_log_entries(output, "")?
+        .into_iter()
+        .map(|entry| {
+            let summary = entry.summary.lines().next().unwrap_or_default().to_string();
+            (entry.hash, summary)
+        })
+        .collect())
+}
+
+fn apply_summaries(entries: &mut [BlameEntry], summaries: &HashMap<String, String>) {
+    for entry in entries {
+        if let Some(summary) = summaries.get(&entry.hash) {
+            entry.summary = summary.clone();
+        }
+    }
+}
+
+/// First lines of the log messages of `revisions`, from one `svn log` over their range.
+fn load_summaries(target: &str, cwd: &Path, revisions: &[u64]) -> HashMap<String, String> {
+    let (Some(oldest), Some(newest)) = (revisions.iter().min(), revisions.iter().max()) else {
+        return HashMap::new();
+    };
+    let range = format!("{newest}:{oldest}");
+    let summaries = run_svn(&["log", "--xml", "-r", range.as_str(), target], cwd)
+        .and_then(|output| parse_summaries(&output).map_err(VcsError::from));
+    match summaries {
+        Ok(summaries) => summaries,
+        Err(error) => {
+            log::warn!("svn blame summaries failed error={error}");
+            HashMap::new()
+        }
+    }
+}
+
+impl VcsProvider for SvnProvider {
+    fn id(&self) -> &'static str {
+        "svn"
+    }
+
+    fn label(&self) -> &'static str {
+        "SVN"
+    }
+
+    fn detect(&self, path: &Path) -> bool {
+        find_ancestor_with(path, ".svn").is_some()
+    }
+
+    fn history(&self, file_path: &Path) -> Result<VcsHistoryResult, VcsError> {
+        let parent = working_file_parent(file_path)?;
+        let path = file_path.to_string_lossy().to_string();
+
+        let wc_root = resolve_wc_root(parent, &path);
+
+        let relative_path = wc_root
+            .as_ref()
+            .and_then(|root| file_path.strip_prefix(root).ok())
+            .map(to_git_path)
+            .unwrap_or_else(|| fallback_relative_path(&path));
+
+        let log_output = run_svn(&["log", "--xml", "--verbose", path.as_str()], parent)?;
+
+        let entries = parse_svn_log_entries(&log_output, &relative_path)?;
+        if entries.is_empty() {
+            if log_output.trim().is_empty() {
+                log::warn!("svn log returned empty output path={relative_path}");
+            } else {
+                let output_preview = truncate_for_log(&log_output, 4000);
+                log::warn!(
+                    "svn history parsed 0 entries path={relative_path} output_preview={output_preview}"
+                );
+            }
+        }
+
+        Ok(VcsHistoryResult {
+            provider: "svn".to_string(),
+            repo_root: wc_root.map(|root| root.to_string_lossy().to_string()),
+            relative_path,
+            entries,
+        })
+    }
+
+    fn blame(&self, request: &BlameRequest) -> Result<BlameResult, VcsError> {
+        if request.revision.is_some() {
+            return Err("SVN blame is only available for the working copy.".into());
+        }
+        let path = request.path.as_str();
+        let parent = working_file_parent(Path::new(path))?;
+
+        let output = run_svn(&["blame", "--xml", path], parent)?;
+        let mut entries = parse_svn_blame(&output)?;
+
+        let mut revisions: Vec<u64> = entries
+            .iter()
+            .filter_map(|entry| entry.hash.parse().ok())
+            .collect();
+        revisions.sort_unstable();
+        revisions.dedup();
+        apply_summaries(&mut entries, &load_summaries(path, parent, &revisions));
+        log::info!(
+            "svn blame path={path} lines={} revisions={}",
+            entries.len(),
+            revisions.len()
+        );
+
+        Ok(BlameResult {
+            provider: "svn".to_string(),
+            entries,
+        })
+    }
+
+    fn show_bytes(&self, request: &ShowRequest) -> Result<Vec<u8>, VcsError> {
+        let revision = &request.revision;
+        let is_number = !revision.is_empty() && revision.chars().all(|c| c.is_ascii_digit());
+        if !is_number && !REVISION_KEYWORDS.contains(&revision.as_str()) {
+            return Err(VcsError::InvalidRevision {
+                message: "Invalid revision.".to_string(),
+            });
+        }
+        let working_path = PathBuf::from(request.working_path.as_deref().unwrap_or_default());
+        let cwd = working_path
+            .parent()
+            .ok_or_else(|| "Invalid file path.".to_string())?;
+        // Changeset files carry repository paths (`/trunk/a.c`) and the repository root
+        // URL; pin those with a peg revision so moved or deleted files still resolve.
+        let target = match request.repo_root.as_deref() {
+            Some(root) if root.contains("://") && request.path.starts_with('/') => {
+                format!("{}{}@{revision}", root.trim_end_matches('/'), request.path)
+            }
+            _ => working_path.to_string_lossy().to_string(),
+        };
+        run_svn_bytes(&["cat", "-r", revision.as_str(), target.as_str()], cwd)
+    }
+
+    fn show_revision(&self, file_path: &Path, revision: &str) -> Result<VcsRevisionFile, VcsError> {
+        let parent = working_file_parent(file_path)?;
+        let revision = normalize_revision(revision)?;
+        let target = file_path.to_string_lossy().to_string();
+
+        // With a working-copy target svn traces the file back through copies and moves
+        // itself, so `info` reports where it lived at that revision.
+        let info = run_svn(
+            &["info", "--xml", "-r", revision.as_str(), target.as_str()],
+            parent,
+        )?;
+        let mut resolved: Option<String> = None;
+        let mut relative_url: Option<String> = None;
+        walk_svn_xml(&info, |element| match element.name {
+            "entry" => resolved = element.attr("revision").map(str::to_string),
+            "relative-url" => relative_url = Some(element.text.to_string()),
+            _ => {}
+        })?;
+        let resolved = resolved.unwrap_or_else(|| revision.clone());
+        let path = relative_url
+            .map(|url| url.trim_start_matches('^').to_string())
+            .unwrap_or_else(|| fallback_relative_path(&target));
+
+        let content = decode_text(&run_svn_bytes(
+            &["cat", "-r", resolved.as_str(), target.as_str()],
+            parent,
+        )?);
+        Ok(VcsRevisionFile {
+            revision: resolved,
+            path,
+            content,
+        })
+    }
+
+    fn changeset(&self, request: &ChangesetRequest) -> Result<VcsChangeset, VcsError> {
+        let revision = request
+            .revision
+            .parse::<u64>()
+            .map_err(|_| VcsError::InvalidRevision {
+                message: "Invalid revision.".to_string(),
+            })?;
+        let working_path = PathBuf::from(request.working_path.as_deref().unwrap_or_default());
+        let cwd = working_dir(&working_path)?;
+        let target = cwd.to_string_lossy().to_string();
+
+        let root_output = run_svn(
+            &["info", "--show-item", "repos-root-url", target.as_str()],
+            cwd,
+        )?;
+        let root_url = root_output.trim().to_string();
+        if root_url.is_empty() {
+            return Err("Unable to resolve repository root.".into());
+        }
+
+        let revision_arg = revision.to_string();
+        let output = run_svn(
+            &[
+                "log",
+                "--xml",
+                "--verbose",
+                "-r",
+                revision_arg.as_str(),
+                root_url.as_str(),
+            ],
+            cwd,
+        )?;
+        let entry = parse_svn_log_entries(&output, "")?.into_iter().next();
+        let files = parse_changed_paths(&output, revision)?;
+
+        Ok(VcsChangeset {
+            provider: "svn".to_string(),
+            revision: revision_arg,
+            author: entry
+                .as_ref()
+                .map(|entry| entry.author.clone())
+                .unwrap_or_default(),
+            timestamp: entry.as_ref().map_or(0, |entry| entry.timestamp),
+            description: entry.map(|entry| entry.summary).unwrap_or_default(),
+            repo_root: Some(root_url),
+            files,
+        })
+    }
+
+    fn status(&self, path: &Path) -> Result<VcsStatusResult, VcsError> {
+        let cwd = working_dir(path)?;
+        let target = cwd.to_string_lossy().to_string();
+        let wc_root = resolve_wc_root(cwd, &target).ok_or_else(|| VcsError::NotARepository {
+            message: "Unable to resolve working copy root.".to_string(),
+        })?;
+        let root = wc_root.to_string_lossy().to_string();
+
+        let output = run_svn(&["status", "--xml", root.as_str()], cwd)?;
+        let entries = parse_svn_status(&output, &wc_root)?;
+        log::info!("svn status wc={root} entries={}", entries.len());
+
+        Ok(VcsStatusResult {
+            provider: "svn".to_string(),
+            repo_root: Some(root),
+            entries,
+        })
+    }
+
+    fn cache_revision(&self, path: &Path) -> Option<String> {
+        // The working copy's base revision; `svn log`/`svn blame` stop there.
+        let cwd = working_dir(path).ok()?;
+        let target = path.to_string_lossy().to_string();
+        let revision = run_svn(&["info", "--show-item", "revision", target.as_str()], cwd).ok()?;
+        Some(revision.trim().to_string()).filter(|revision| !revision.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG_XML: &str = include_str!("testdata/svn_log_verbose.xml");
+    const BLAME_XML: &str = include_str!("testdata/svn_blame.xml");
+
+    #[test]
+    fn log_decodes_entities_in_author_and_message() {
+        let entries = parse_svn_log_entries(LOG_XML, "src/old name.c").unwrap();
+        let revisions: Vec<&str> = entries.iter().map(|entry| entry.hash.as_str()).collect();
+        assert_eq!(revisions, ["42", "41", "40"]);
+
+        let edit = &entries[1];
+        assert_eq!(edit.author, "bob <bob@example.com>");
+        assert_eq!(
+            edit.summary,
+            "Check a < b && c\n\nDetails on the second line."
+        );
+        assert_eq!(edit.timestamp, 1_709_542_800);
+        assert_eq!(entries[0].timestamp, 1_709_634_030);
+    }
+
+    #[test]
+    fn log_marks_the_revision_that_deleted_the_file() {
+        let entries = parse_svn_log_entries(LOG_XML, "src/old name.c").unwrap();
+        let deleted: Vec<bool> = entries.iter().map(|entry| entry.deleted).collect();
+        assert_eq!(deleted, [true, false, false]);
+
+        // Another file's history is not affected by the delete.
+        let entries = parse_svn_log_entries(LOG_XML, "src/other.c").unwrap();
+        assert!(entries.iter().all(|entry| !entry.deleted));
+    }
+
+    #[test]
+    fn blame_keeps_local_modifications_without_a_commit() {
+        let entries = parse_svn_blame(BLAME_XML).unwrap();
+        assert_eq!(entries.len(), 4);
+
+        assert_eq!(entries[1].line, 2);
+        assert_eq!(entries[1].hash, "41");
+        assert_eq!(entries[1].author, "bob <bob@example.com>");
+        assert_eq!(entries[1].timestamp, 1_709_542_800);
+
+        let local = &entries[2];
+        assert_eq!(local.line, 3);
+        assert!(local.hash.is_empty());
+        assert!(local.author.is_empty());
+        assert_eq!(local.timestamp, 0);
+
+        // The commit of the next line is not merged into the uncommitted one.
+        assert_eq!(entries[3].hash, "40");
+        assert_eq!(entries[3].author, "carol");
+    }
+
+    #[test]
+    fn blame_summaries_use_the_first_line_of_the_log_message() {
+        let mut entries = parse_svn_blame(BLAME_XML).unwrap();
+        let summaries = parse_summaries(LOG_XML).unwrap();
+        apply_summaries(&mut entries, &summaries);
+
+        let summaries: Vec<&str> = entries.iter().map(|entry| entry.summary.as_str()).collect();
+        assert_eq!(
+            summaries,
+            ["Add old name.c", "Check a < b && c", "", "Add old name.c"]
+        );
+    }
+
+    #[test]
+    fn unknown_entities_are_rejected() {
+        let output = "<log><logentry revision=\"1\"><msg>a&nbsp;b</msg></logentry></log>";
+        let Err(error) = parse_svn_log_entries(output, "") else {
+            panic!("an unknown entity must fail the parse");
+        };
+        assert!(error.contains("unknown entity &nbsp;"), "{error}");
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU16, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use tauri_plugin_log::log;
+
+use crate::sessions;
+
+/// Per-user directory (under the runtime or local data dir) holding the endpoint file.
+const ENDPOINT_DIR: &str = "GCompare";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Line sent to a waiting launcher once its comparison is closed.
+const RELEASED: &str = "released";
+
+#[derive(Serialize, Deserialize)]
+struct Endpoint {
+    port: u16,
+    token: String,
+}
+
+/// What a `--wait` launcher sends to the primary instance.
+#[derive(Serialize, Deserialize)]
+struct WaitRequest {
+    token: String,
+    paths: Vec<String>,
+}
+
+/// Connections of `--wait` launchers, keyed by the wait id stored on their session.
+#[derive(Default)]
+pub(crate) struct PendingWaits {
+    next_id: AtomicU64,
+    streams: Mutex<HashMap<u64, TcpStream>>,
+    /// Port published in the endpoint file, 0 until the listener is up.
+    port: AtomicU16,
+}
+
+impl PendingWaits {
+    /// Lets the launcher waiting on `wait_id` exit. Unknown ids are ignored.
+    pub(crate) fn release(&self, wait_id: u64) {
+        let stream = self
+            .streams
+            .lock()
+            .expect("pending waits lock")
+            .remove(&wait_id);
+        if let Some(mut stream) = stream {
+            let _ = writeln!(stream, "{RELEASED}");
+            log::info!("wait released id={wait_id}");
+        }
+    }
+
+    /// Releases every launcher and withdraws the endpoint when the app exits.
+    pub(crate) fn shutdown(&self) {
+        let port = self.port.load(Ordering::Relaxed);
+        let published = std::fs::read(endpoint_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Endpoint>(&bytes).ok());
+        // A newer instance may have taken over the file; leave its endpoint alone.
+        if port != 0 && published.is_some_and(|endpoint| endpoint.port == port) {
+            let _ = std::fs::remove_file(endpoint_path());
+        }
+        self.release_all();
+    }
+
+    fn release_all(&self) {
+        let ids: Vec<u64> = self
+            .streams
+            .lock()
+            .expect("pending waits lock")
+            .keys()
+            .copied()
+            .collect();
+        for id in ids {
+            self.release(id);
+        }
+    }
+}
+
+/// Where the primary instance publishes its wait endpoint. The file holds the auth
+/// token, so it lives in a directory only the current user can reach and is named
+/// after the user as well.
+fn endpoint_path() -> PathBuf {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    let user: String = user
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    dirs::runtime_dir()
+        .or_else(dirs::data_local_dir)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(ENDPOINT_DIR)
+        .join(format!("gcompare-wait-{user}.json"))
+}
+
+/// Writes the endpoint readable by the owner only. A fresh temp file is created
+/// (never opened through an existing file or symlink) and renamed into place.
+fn publish_endpoint(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)?;
+
+    let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+    let _ = std::fs::remove_file(&temp);
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&temp)?;
+    file.write_all(bytes)?;
+    drop(file);
+    std::fs::rename(&temp, path)
+}
+
+/// 256 bits from the OS random source, hex encoded.
+fn random_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+pub(crate) fn is_wait_launch(args: &[OsString]) -> bool {
+    args.iter().any(|arg| arg == "--wait")
+}
+
+/// Hands `paths` to a running instance and blocks until the user closes them there.
+///
+/// Returns `None` when no instance is listening, so this process should start the
+/// window itself (and then lives until that window closes anyway).
+pub(crate) fn forward_and_wait(paths: Vec<String>) -> Option<i32> {
+    let endpoint: Endpoint = std::fs::read(endpoint_path())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())?;
+    let address = (Ipv4Addr::LOCALHOST, endpoint.port).into();
+    let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT).ok()?;
+    let request = WaitRequest {
+        token: endpoint.token,
+        paths,
+    };
+    let line = serde_json::to_string(&request).ok()?;
+    writeln!(stream, "{line}").ok()?;
+
+    // The primary answers once the comparison is closed; a dropped connection (the app
+    // quit) ends the wait as well.
+    let mut reply = String::new();
+    let _ = BufReader::new(stream).read_line(&mut reply);
+    Some(0)
+}
+
+fn accept(app: &tauri::AppHandle, token: &str, stream: TcpStream) -> Result<(), String> {
+    // Only the request line is read here; a silent peer must not stall the listener.
+    stream
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|error| error.to_string())?;
+    let mut line = String::new();
+    BufReader::new(stream.try_clone().map_err(|error| error.to_string())?)
+        .read_line(&mut line)
+        .map_err(|error| error.to_string())?;
+    let request: WaitRequest =
+        serde_json::from_str(&line).map_err(|error| format!("Invalid wait request: {error}"))?;
+    if request.token != token {
+        return Err("Wait request with a wrong token".to_string());
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+    let waits = app.state::<PendingWaits>();
+    let wait_id = waits.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    waits
+        .streams
+        .lock()
+        .expect("pending waits lock")
+        .insert(wait_id, stream);
+    log::info!("wait opened id={wait_id}");
+    sessions::open_external(app, request.paths, Some(wait_id));
+    Ok(())
+}
+
+/// Listens on a loopback port for `--wait` launchers and publishes it for this user.
+pub(crate) fn start_listener(app: tauri::AppHandle) {
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+        Ok(listener) => listener,
+        Err(error) => {
+            log::warn!("wait listener unavailable: {error}");
+            return;
+        }
+    };
+    let Ok(address) = listener.local_addr() else {
+        return;
+    };
+    let token = match random_token() {
+        Ok(token) => token,
+        Err(error) => {
+            log::warn!("wait listener unavailable, no random token: {error}");
+            return;
+        }
+    };
+    let endpoint = Endpoint {
+        port: address.port(),
+        token: token.clone(),
+    };
+    let written = serde_json::to_vec(&endpoint)
+        .map_err(|error| error.to_string())
+        .and_then(|bytes| {
+            publish_endpoint(&endpoint_path(), &bytes).map_err(|error| error.to_string())
+        });
+    if let Err(error) = written {
+        log::warn!("failed to publish wait endpoint: {error}");
+        return;
+    }
+    app.state::<PendingWaits>()
+        .port
+        .store(address.port(), Ordering::Relaxed);
+    log::info!("wait listener on port {}", address.port());
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let result = stream
+                .map_err(|error| error.to_string())
+                .and_then(|stream| accept(&app, &token, stream));
+            if let Err(error) = result {
+                log::warn!("wait connection rejected: {error}");
+            }
+        }
+    });
+}
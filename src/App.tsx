@@ -1,20 +1,40 @@
-import { useCallback, useEffect, useRef, useState } from "react";
+import { useCallback, useEffect, useMemo, useRef, useState } from "react";
 import { DiffEditor, Editor, loader, type MonacoDiffEditor } from "@monaco-editor/react";
 import type { editor as MonacoEditorType } from "monaco-editor";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
-import { mkdir, readTextFile, writeTextFile } from "@tauri-apps/plugin-fs";
+import { open } from "@tauri-apps/plugin-dialog";
+import { mkdir, readTextFile, stat, writeTextFile } from "@tauri-apps/plugin-fs";
 import { BaseDirectory } from "@tauri-apps/api/path";
 import { check, type DownloadEvent } from "@tauri-apps/plugin-updater";
+import HexCompareView from "./HexCompareView";
+import ImageCompareView from "./ImageCompareView";
+import P4ProfilesEditor from "./P4ProfilesEditor";
+import { useCompareSessions } from "./hooks/useCompareSessions";
+import { useDirectoryCompare } from "./hooks/useDirectoryCompare";
 import { useFileHandlers } from "./hooks/useFileHandlers";
+import { useMergeSession } from "./hooks/useMergeSession";
 import { useMonacoRemeasure } from "./hooks/useMonacoRemeasure";
+import { useP4Login } from "./hooks/useP4Login";
 import { useRecents } from "./hooks/useRecents";
 import { useStatusMessage } from "./hooks/useStatusMessage";
 import { useSettings } from "./hooks/useSettings";
 import { useSystemTheme } from "./hooks/useSystemTheme";
+import { useRevisionCompare } from "./hooks/useRevisionCompare";
+import { useVcsChangeset } from "./hooks/useVcsChangeset";
+import { useVcsStatus } from "./hooks/useVcsStatus";
 import { getMonacoTheme } from "./utils/monacoTheme";
+import { describeInvokeError, isVcsError } from "./utils/vcsError";
 import type { BlameResult } from "./types/blame";
+import type { DirEntryDiff, DirEntryStatus } from "./types/dircmp";
+import type { GitToolInstall } from "./types/gittool";
+import type { ImageSource } from "./types/imgcmp";
+import type { MergeChoice } from "./types/merge";
+import type { ComparePaneState, CompareSession } from "./types/sessions";
+import type { TextFile, TextFileMeta } from "./types/textfile";
+import { DEFAULT_VCS_TIMEOUT_SECS } from "./types/settings";
+import type { VcsChangeKind, VcsStatusEntry } from "./types/vcs";
 import "./App.css";
 
 const appStart = typeof performance !== "undefined" ? performance.now() : Date.now();
@@ -40,18 +60,33 @@ const initialModifiedText = [
   "- Fast navigation",
   "- Clean layout",
   "- Cross-platform",
-  "- Git/P4/SVN CLI support",
+  "- Git/P4/SVN/Mercurial/jj/Fossil CLI support",
 ].join("\n");
 const gitVirtualPathPrefix = "git:";
 const p4VirtualPathPrefix = "p4:";
 const svnVirtualPathPrefix = "svn:";
+const hgVirtualPathPrefix = "hg:";
+const jjVirtualPathPrefix = "jj:";
+const fossilVirtualPathPrefix = "fossil:";
 const vcsVirtualPathPrefixes = [
   gitVirtualPathPrefix,
   p4VirtualPathPrefix,
   svnVirtualPathPrefix,
+  hgVirtualPathPrefix,
+  jjVirtualPathPrefix,
+  fossilVirtualPathPrefix,
 ];
-type VcsProvider = "git" | "p4" | "svn";
+type VcsProvider = "git" | "p4" | "svn" | "hg" | "jj" | "fossil";
+/** Providers whose history ids are hashes (or jj change ids) rather than numbers. */
+const hashRevisionProviders: VcsProvider[] = ["git", "hg", "jj", "fossil"];
 type HistoryProvider = VcsProvider | "none";
+/** A `... from` record of a P4 revision: the branch version it was integrated from. */
+type HistoryIntegration = {
+  how: string;
+  path: string;
+  startRevision: string | null;
+  revision: string;
+};
 type HistoryEntry = {
   provider: VcsProvider;
   hash: string;
@@ -60,6 +95,7 @@ type HistoryEntry = {
   summary: string;
   path: string;
   deleted: boolean;
+  integrations?: HistoryIntegration[];
 };
 type HistoryResult = {
   provider: HistoryProvider;
@@ -67,23 +103,62 @@ type HistoryResult = {
   relativePath: string;
   entries: HistoryEntry[];
 };
+type HistoryBatch = {
+  requestId: string;
+  entries: HistoryEntry[];
+};
+/** Timeouts offered for VCS commands, in seconds; 0 disables the limit. */
+const vcsTimeoutOptions = [30, 60, 120, 300, 0];
+const createRequestId = () => `${Date.now()}-${Math.random().toString(36).slice(2)}`;
 type EditorSide = "original" | "modified";
 const isVirtualPath = (path: string | null) =>
   Boolean(path && vcsVirtualPathPrefixes.some((prefix) => path.startsWith(prefix)));
-const getHistoryId = (entry: HistoryEntry) =>
-  entry.provider === "git" ? entry.hash.slice(0, 7) : entry.hash;
+/** Short form of a revision id: abbreviated hashes, p4/svn numbers as they are. */
+const formatRevisionId = (provider: VcsProvider, revision: string) =>
+  provider === "git"
+    ? revision.slice(0, 7)
+    : provider === "fossil"
+      ? revision.slice(0, 10)
+      : provider === "hg" || provider === "jj"
+        ? revision.slice(0, 12)
+        : revision;
+const getHistoryId = (entry: HistoryEntry) => formatRevisionId(entry.provider, entry.hash);
 const getHistoryPrefix = (provider: VcsProvider) =>
   provider === "git"
     ? gitVirtualPathPrefix
     : provider === "p4"
       ? p4VirtualPathPrefix
-      : svnVirtualPathPrefix;
+      : provider === "hg"
+        ? hgVirtualPathPrefix
+        : provider === "jj"
+          ? jjVirtualPathPrefix
+          : provider === "fossil"
+            ? fossilVirtualPathPrefix
+            : svnVirtualPathPrefix;
+const imageExtensions = ["png", "jpg", "jpeg", "bmp"];
+const isImagePath = (path: string | null) =>
+  Boolean(path && imageExtensions.includes(path.split(".").pop()?.toLowerCase() ?? ""));
+const formatTextFileMeta = (meta: TextFileMeta) =>
+  `${meta.encoding}${meta.bom ? " BOM" : ""} · ${meta.mixedLineEndings ? "Mixed" : meta.lineEnding.toUpperCase()}`;
+const dirStatusLabels: Record<DirEntryStatus, string> = {
+  added: "+",
+  removed: "−",
+  modified: "M",
+  identical: "=",
+};
+const changeKindLabels: Record<VcsChangeKind, string> = {
+  modified: "M",
+  added: "A",
+  deleted: "D",
+  renamed: "R",
+  untracked: "?",
+  conflicted: "U",
+};
 const formatCommitTime = (timestamp: number) =>
   new Date(timestamp * 1000).toLocaleString();
-const shouldShowHistoryStatus = (message: string) => {
-  const lower = message.toLowerCase();
-  return !lower.includes("history unavailable");
-};
+/** A cancelled walk was replaced by a newer one, so it needs no status message. */
+const shouldShowHistoryStatus = (error: unknown) =>
+  !(isVcsError(error) && error.kind === "cancelled");
 type LineChange = {
   originalStartLineNumber: number;
   originalEndLineNumber: number;
@@ -136,9 +211,26 @@ const getPathParts = (path: string): PathParts => {
   return { name, parent, full: path };
 };
 
+const formatTabTitle = (paths: string[]) => {
+  const names = paths.map((path) => getPathParts(path).name);
+  if (names.length === 0) {
+    return "Untitled";
+  }
+  return names.length === 2 && names[0] !== names[1] ? `${names[0]} ↔ ${names[1]}` : names[0];
+};
+
 function App() {
-  const { settings, updateTheme, updateViewMode, updateP4Settings } = useSettings();
+  const {
+    settings,
+    updateTheme,
+    updateViewMode,
+    updateP4Settings,
+    updateP4Profiles,
+    updateVcsTimeout,
+  } = useSettings();
   const systemTheme = useSystemTheme();
+  const { loginPrompt, answerLogin, logIn } = useP4Login();
+  const [p4PasswordInput, setP4PasswordInput] = useState("");
   const [updateBusy, setUpdateBusy] = useState(false);
   const diffEditorRef = useRef<MonacoDiffEditor | null>(null);
   const blameEditorRef = useRef<MonacoEditorType.IStandaloneCodeEditor | null>(null);
@@ -155,10 +247,14 @@ function App() {
     modifiedText,
     originalPath,
     modifiedPath,
+    originalMeta,
+    modifiedMeta,
+    originalBinary,
+    modifiedBinary,
     applyPaths,
-    enqueueOpenPaths,
     handleOpenFile,
     openFilePath,
+    restorePanes,
     setSideContent,
   } = useFileHandlers({
     initialOriginalText,
@@ -166,6 +262,55 @@ function App() {
     largeFileThreshold,
     showStatus,
   });
+  const {
+    dirCompareActive,
+    dirCompareBusy,
+    dirCompareRoots,
+    dirCompareEntries,
+    dirCompareSummary,
+    dirCompareError,
+    compareDirectories,
+    closeDirectoryCompare,
+  } = useDirectoryCompare({ showStatus });
+  const {
+    mergeSession,
+    mergeConflictIndexes,
+    mergeChoices,
+    mergeUnresolvedCount,
+    mergeSaving,
+    chooseConflict,
+    finishMerge,
+    abortMerge,
+  } = useMergeSession({ showStatus });
+  const {
+    changesOpen,
+    changesLoading,
+    changesResult,
+    changesError,
+    refreshChanges,
+    closeChanges,
+  } = useVcsStatus({ showStatus });
+  const [changesSelectedPath, setChangesSelectedPath] = useState<string | null>(null);
+  const {
+    changeset,
+    changesetShelved,
+    changesetLoading,
+    changesetFileIndex,
+    setChangesetFileIndex,
+    loadChangeset,
+    fetchChangesetFile,
+    closeChangeset,
+  } = useVcsChangeset({ showStatus });
+  const {
+    originalRevision,
+    setOriginalRevision,
+    modifiedRevision,
+    setModifiedRevision,
+    revisionCompareLoading,
+    compareRevisions,
+  } = useRevisionCompare({ showStatus });
+  const [dirHideIdentical, setDirHideIdentical] = useState(true);
+  const [dirSelectedPath, setDirSelectedPath] = useState<string | null>(null);
   const {
     recentFiles,
     recentProjects,
@@ -177,6 +322,7 @@ function App() {
   const [historyBusy, setHistoryBusy] = useState(false);
   const [historyEntries, setHistoryEntries] = useState<HistoryEntry[]>([]);
   const [historyError, setHistoryError] = useState<string | null>(null);
+  const [historyNeedsP4Login, setHistoryNeedsP4Login] = useState(false);
   const [_historyProvider, setHistoryProvider] = useState<HistoryProvider | null>(null);
   const [historyRepoRoot, setHistoryRepoRoot] = useState<string | null>(null);
   const [_historyRelativePath, setHistoryRelativePath] = useState<string | null>(null);
@@ -186,14 +332,25 @@ function App() {
   const [historySelectedHash, setHistorySelectedHash] = useState<string | null>(null);
   const [historyLoadingHash, setHistoryLoadingHash] = useState<string | null>(null);
   const lastHistoryPathRef = useRef<string | null>(null);
+  // Id of the running vcs_history request; progress and results of older ones are dropped.
+  const historyRequestRef = useRef<{ id: string; path: string } | null>(null);
   const [recentsOpen, setRecentsOpen] = useState(false);
   const [recentsPinned, setRecentsPinned] = useState(false);
   const [diffChanges, setDiffChanges] = useState<LineChange[]>([]);
   const [diffIndex, setDiffIndex] = useState(0);
   const [p4SettingsOpen, setP4SettingsOpen] = useState(false);
+  const [revisionCompareOpen, setRevisionCompareOpen] = useState(false);
+  const [shelfReviewOpen, setShelfReviewOpen] = useState(false);
+  const [shelvedChangeInput, setShelvedChangeInput] = useState("");
+  // Image shown for a virtual (history) side, matched by its side label.
+  const [revisionImage, setRevisionImage] = useState<{ label: string; source: ImageSource } | null>(
+    null,
+  );
   const [p4PortInput, setP4PortInput] = useState("");
   const [p4UserInput, setP4UserInput] = useState("");
   const [p4ClientInput, setP4ClientInput] = useState("");
+  const [p4TicketsInput, setP4TicketsInput] = useState("");
+  const [p4TrustInput, setP4TrustInput] = useState("");
 
   // Blame 模式状态
   const [blameMode, setBlameMode] = useState(false);
@@ -202,14 +359,19 @@ function App() {
   const [blameContent, setBlameContent] = useState<string>("");
   const [blameFilePath, setBlameFilePath] = useState<string | null>(null);
   const blameDecorationsRef = useRef<string[]>([]);
+  const blameRequestRef = useRef<string | null>(null);
   const blameMapRef = useRef<Map<number, { author: string; hash: string; timestamp: number; summary: string }>>(new Map());
 
+  const p4Profiles = useMemo(() => settings.p4Profiles ?? [], [settings.p4Profiles]);
+
   // 同步 P4 设置输入框的值
   useEffect(() => {
     if (settings.p4) {
       setP4PortInput(settings.p4.port || "");
       setP4UserInput(settings.p4.user || "");
       setP4ClientInput(settings.p4.client || "");
+      setP4TicketsInput(settings.p4.tickets || "");
+      setP4TrustInput(settings.p4.trust || "");
     }
   }, [settings.p4]);
 
@@ -223,6 +385,33 @@ function App() {
       : modifiedIsFile
         ? modifiedPath
         : null;
+  const selectedHistoryEntry =
+    historyEntries.find((entry) => entry.hash === historySelectedHash) ?? null;
+  const changesTargetPath =
+    historyTargetPath ?? (modifiedIsFile ? modifiedPath : originalIsFile ? originalPath : null);
+  const originalImage = useMemo<ImageSource | null>(() => {
+    if (revisionImage && revisionImage.label === originalPath) {
+      return revisionImage.source;
+    }
+    return originalIsFile && originalPath && isImagePath(originalPath)
+      ? { kind: "file", path: originalPath }
+      : null;
+  }, [originalIsFile, originalPath, revisionImage]);
+  const modifiedImage = useMemo<ImageSource | null>(() => {
+    if (revisionImage && revisionImage.label === modifiedPath) {
+      return revisionImage.source;
+    }
+    return modifiedIsFile && modifiedPath && isImagePath(modifiedPath)
+      ? { kind: "file", path: modifiedPath }
+      : null;
+  }, [modifiedIsFile, modifiedPath, revisionImage]);
+  // Every open side must be an image; an empty side just waits for one.
+  const imageMode =
+    !blameMode
+    && Boolean(originalImage || modifiedImage)
+    && (!originalPath || Boolean(originalImage))
+    && (!modifiedPath || Boolean(modifiedImage));
+  const binaryMode = (originalBinary || modifiedBinary) && !blameMode && !imageMode;
   const historyVisible = historyPinned || historyOpen;
   const recentsVisible = recentsPinned || recentsOpen;
   const hasRecents = recentFiles.length > 0 || recentProjects.length > 0;
@@ -365,6 +554,128 @@ function App() {
     [getPreferredSide, handleOpenFile],
   );
 
+  const openDirectoryPair = useCallback(
+    async (paths: string[]) => {
+      const candidates = paths.filter(Boolean);
+      if (candidates.length !== 2) {
+        return false;
+      }
+      try {
+        const infos = await Promise.all(candidates.map((path) => stat(path)));
+        if (!infos.every((info) => info.isDirectory)) {
+          return false;
+        }
+      } catch {
+        return false;
+      }
+      setDirSelectedPath(null);
+      void compareDirectories(candidates[0], candidates[1]);
+      return true;
+    },
+    [compareDirectories],
+  );
+
+  const capturePanes = useCallback((): ComparePaneState => {
+    // Edits live in the Monaco models, so read them back before leaving a tab.
+    const editor = diffEditorRef.current;
+    return {
+      originalText: editor?.getOriginalEditor().getValue() ?? originalText,
+      modifiedText: editor?.getModifiedEditor().getValue() ?? modifiedText,
+      originalPath,
+      modifiedPath,
+      originalMeta,
+      modifiedMeta,
+      originalBinary,
+      modifiedBinary,
+    };
+  }, [
+    modifiedBinary,
+    modifiedMeta,
+    modifiedPath,
+    modifiedText,
+    originalBinary,
+    originalMeta,
+    originalPath,
+    originalText,
+  ]);
+
+  const loadSessionPaths = useCallback(
+    async (paths: string[]) => {
+      const handled = await openDirectoryPair(paths);
+      if (!handled) {
+        await applyPaths(paths, "open");
+      }
+    },
+    [applyPaths, openDirectoryPair],
+  );
+
+  const {
+    tabs,
+    activeTabId,
+    activateTab,
+    closeTab,
+    initSessions,
+    newTab,
+    openSession,
+    syncActivePaths,
+  } = useCompareSessions({
+    capturePanes,
+    restorePanes,
+    loadPaths: loadSessionPaths,
+  });
+
+  useEffect(() => {
+    const paths = [originalPath, modifiedPath].filter((path): path is string => Boolean(path));
+    // Folder sessions never fill the panes; keep their paths.
+    if (paths.length > 0) {
+      syncActivePaths(paths);
+    }
+  }, [modifiedPath, originalPath, syncActivePaths]);
+
+  const handleCompareFolders = useCallback(async () => {
+    const left = await open({ directory: true, multiple: false, title: "Select left folder" });
+    if (!left || Array.isArray(left)) {
+      return;
+    }
+    const right = await open({
+      directory: true,
+      multiple: false,
+      title: "Select right folder",
+      defaultPath: left,
+    });
+    if (!right || Array.isArray(right)) {
+      return;
+    }
+    setDirSelectedPath(null);
+    await compareDirectories(left, right);
+  }, [compareDirectories]);
+
+  const handleOpenDirEntry = useCallback(
+    async (entry: DirEntryDiff) => {
+      setDirSelectedPath(entry.relativePath);
+      if (entry.leftPath) {
+        await openFilePath(entry.leftPath, "original");
+      } else {
+        setSideContent("original", "", null);
+      }
+      if (entry.rightPath) {
+        await openFilePath(entry.rightPath, "modified");
+      } else {
+        setSideContent("modified", "", null);
+      }
+    },
+    [openFilePath, setSideContent],
+  );
+
+  useEffect(() => {
+    if (!mergeSession) {
+      return;
+    }
+    void openFilePath(mergeSession.leftPath, "original");
+    void openFilePath(mergeSession.rightPath, "modified");
+    // Only when a merge session is loaded; later file changes are up to the user.
+  }, [mergeSession]);
+
   const getDropSide = (rawX: number) => {
     const scale = window.devicePixelRatio || 1;
     const logicalX = rawX;
@@ -413,16 +724,17 @@ function App() {
     const contents = targetEditor.getValue();
 
     try {
-      await writeTextFile(path, contents);
+      const meta = focusedSide === "original" ? originalMeta : modifiedMeta;
+      await invoke("write_text_file", { path, text: contents, meta });
       showStatus(
         `Saved ${focusedSide === "original" ? "left" : "right"} file.`,
         2000,
       );
     } catch (error) {
       console.error(error);
-      showStatus("Failed to save file.", 2500);
+      showStatus(`Failed to save file: ${String(error)}`, 4000);
     }
-  }, [modifiedPath, originalPath, showStatus]);
+  }, [modifiedMeta, modifiedPath, originalMeta, originalPath, showStatus]);
 
   useEffect(() => {
     const onKeyDown = (event: KeyboardEvent) => {
@@ -462,6 +774,18 @@ function App() {
     void appendStartupLog(message);
   }, []);
 
+  const handleInstallGitTool = useCallback(async () => {
+    try {
+      const result = await invoke<GitToolInstall>("install_git_tool");
+      showStatus(
+        `Registered with git (${result.entries.length} config entries). Use git difftool --tool=gcompare.`,
+        6000,
+      );
+    } catch (error) {
+      showStatus(`Failed to register git tool: ${String(error)}`, 6000);
+    }
+  }, [showStatus]);
+
   const handleCheckUpdates = useCallback(async () => {
     if (updateBusy) {
       return;
@@ -523,13 +847,46 @@ function App() {
     }
   }, [showStatus, updateBusy]);
 
-  const formatInvokeError = useCallback((error: unknown) => {
-    if (error instanceof Error) {
-      return error.message;
+  const formatInvokeError = useCallback((error: unknown) => describeInvokeError(error), []);
+
+  // Stops a running history walk (e.g. `git log --follow`) once its file is no longer shown.
+  const cancelHistoryRequest = useCallback(() => {
+    const requestId = historyRequestRef.current?.id;
+    if (!requestId) {
+      return;
     }
-    return String(error);
+    historyRequestRef.current = null;
+    setHistoryBusy(false);
+    void invoke("vcs_cancel", { requestId }).catch((error) => console.warn(error));
   }, []);
 
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    let active = true;
+    listen<HistoryBatch>("gcompare://vcs-history-progress", (event) => {
+      if (event.payload.requestId !== historyRequestRef.current?.id) {
+        return;
+      }
+      setHistoryEntries((prev) => [...prev, ...event.payload.entries]);
+    })
+      .then((fn) => {
+        if (active) {
+          unlisten = fn;
+        } else {
+          fn();
+        }
+      })
+      .catch((error) => console.error(error));
+    return () => {
+      active = false;
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  useEffect(() => cancelHistoryRequest, [cancelHistoryRequest, historyTargetPath]);
+
   const fetchHistory = useCallback(async (force = false) => {
     if (!historyTargetPath) {
       setHistoryEntries([]);
@@ -537,6 +894,7 @@ function App() {
       setHistoryRepoRoot(null);
       setHistoryRelativePath(null);
       setHistoryError("Open a file to view history.");
+      setHistoryNeedsP4Login(false);
       lastHistoryPathRef.current = null;
       return;
     }
@@ -558,12 +916,26 @@ function App() {
       return;
     }
 
+    // Progress batches change historyEntries and re-run this; keep the walk already running.
+    if (!force && historyRequestRef.current?.path === historyTargetPath) {
+      return;
+    }
+
+    cancelHistoryRequest();
+    const requestId = createRequestId();
+    historyRequestRef.current = { id: requestId, path: historyTargetPath };
     setHistoryBusy(true);
     setHistoryError(null);
+    setHistoryNeedsP4Login(false);
+    setHistoryEntries([]);
     try {
       const result = await invoke<HistoryResult>("vcs_history", {
         path: historyTargetPath,
+        requestId,
       });
+      if (historyRequestRef.current?.id !== requestId) {
+        return;
+      }
       setHistoryEntries(result.entries);
       setHistoryRepoRoot(result.repoRoot);
       setHistoryRelativePath(result.relativePath);
@@ -571,26 +943,62 @@ function App() {
       setHistorySelectedHash(null);
       lastHistoryPathRef.current = historyTargetPath;
     } catch (error) {
+      if (historyRequestRef.current?.id !== requestId) {
+        return;
+      }
       const message = formatInvokeError(error);
       setHistoryEntries([]);
       setHistoryProvider(null);
       setHistoryRepoRoot(null);
       setHistoryRelativePath(null);
       setHistoryError(message);
-      if (shouldShowHistoryStatus(message)) {
+      // Probes never prompt, so offer the login explicitly.
+      setHistoryNeedsP4Login(
+        isVcsError(error) && error.kind === "authRequired" && error.tool === "p4",
+      );
+      if (shouldShowHistoryStatus(error)) {
         showStatus(`History error: ${message}`, 8000);
       }
       lastHistoryPathRef.current = historyTargetPath;
     } finally {
-      setHistoryBusy(false);
+      if (historyRequestRef.current?.id === requestId) {
+        historyRequestRef.current = null;
+        setHistoryBusy(false);
+      }
+    }
+  }, [
+    cancelHistoryRequest,
+    formatInvokeError,
+    historyEntries.length,
+    historyError,
+    historyTargetPath,
+    showStatus,
+  ]);
+
+  const loginForHistory = useCallback(async () => {
+    if (!historyTargetPath) {
+      return;
+    }
+    try {
+      await logIn(historyTargetPath);
+      await fetchHistory(true);
+    } catch (error) {
+      showStatus(`P4 login failed: ${formatInvokeError(error)}`, 8000);
     }
-  }, [formatInvokeError, historyEntries.length, historyError, historyTargetPath, showStatus]);
+  }, [fetchHistory, formatInvokeError, historyTargetPath, logIn, showStatus]);
 
   // Fetch blame data for the current file
   const fetchBlame = useCallback(async (
     filePath: string,
     options?: { commit?: string; repoRoot?: string; provider?: string }
   ) => {
+    if (blameRequestRef.current) {
+      void invoke("vcs_cancel", { requestId: blameRequestRef.current }).catch((error) =>
+        console.warn(error),
+      );
+    }
+    const requestId = createRequestId();
+    blameRequestRef.current = requestId;
     setBlameBusy(true);
     try {
       const result = await invoke<BlameResult>("vcs_blame", {
@@ -598,19 +1006,29 @@ function App() {
         commit: options?.commit,
         repoRoot: options?.repoRoot,
         provider: options?.provider,
+        requestId,
       });
+      if (blameRequestRef.current !== requestId) {
+        return;
+      }
       console.log("Blame result:", result);
       setBlameData(result);
       if (result.entries.length > 0) {
         showStatus(`Blame loaded: ${result.entries.length} lines from ${result.provider}`, 3000);
       }
     } catch (error) {
+      if (blameRequestRef.current !== requestId) {
+        return;
+      }
       console.warn("Blame failed:", error);
       setBlameData(null);
       blameMapRef.current = new Map();
       showStatus(`Blame unavailable: ${formatInvokeError(error)}`, 5000);
     } finally {
-      setBlameBusy(false);
+      if (blameRequestRef.current === requestId) {
+        blameRequestRef.current = null;
+        setBlameBusy(false);
+      }
     }
   }, [formatInvokeError, showStatus]);
 
@@ -762,8 +1180,8 @@ function App() {
       
       // Load the file content for the standalone blame editor
       try {
-        const content = await readTextFile(targetPath);
-        setBlameContent(content);
+        const file = await invoke<TextFile>("read_text_file", { path: targetPath });
+        setBlameContent(file.text);
         setBlameFilePath(targetPath);
       } catch (e) {
         console.warn("Failed to load file for blame:", e);
@@ -811,33 +1229,42 @@ function App() {
         showStatus("History is not available yet.", 2500);
         return;
       }
-      if (entry.provider === "git" && !historyRepoRoot) {
-        showStatus("Git history is not available yet.", 2500);
+      if (hashRevisionProviders.includes(entry.provider) && !historyRepoRoot) {
+        showStatus("Repository history is not available yet.", 2500);
         return;
       }
 
       setHistoryLoadingHash(entry.hash);
       setHistorySelectedHash(entry.hash);
       try {
-        const content =
-          entry.provider === "git"
-            ? await invoke<string>("git_show_file", {
-                repoRoot: historyRepoRoot,
-                commit: entry.hash,
-                path: entry.path,
-              })
-            : entry.provider === "p4"
-              ? await invoke<string>("p4_show_file", {
-                  path: entry.path,
-                  change: entry.hash,
-                  workingPath: historyTargetPath,
-                })
-              : await invoke<string>("svn_show_file", {
-                  revision: entry.hash,
-                  workingPath: historyTargetPath,
-                });
         const displayId = getHistoryId(entry);
         const commitLabel = `${getHistoryPrefix(entry.provider)}${displayId}:${entry.path}`;
+        const isImage = isImagePath(entry.path);
+        // Images are decoded by `compare_images` straight from the VCS instead.
+        const content = isImage
+          ? ""
+          : await invoke<string>("vcs_show_file", {
+              provider: entry.provider,
+              revision: entry.hash,
+              path: entry.path,
+              repoRoot: historyRepoRoot,
+              workingPath: historyTargetPath,
+            });
+        setRevisionImage(
+          isImage
+            ? {
+                label: commitLabel,
+                source: {
+                  kind: "revision",
+                  provider: entry.provider,
+                  revision: entry.hash,
+                  path: entry.path,
+                  repoRoot: historyRepoRoot,
+                  workingPath: historyTargetPath,
+                },
+              }
+            : null,
+        );
         const workingText =
           historySourceSide === "original" ? originalText : modifiedText;
         const workingPath = historyTargetPath;
@@ -848,7 +1275,7 @@ function App() {
           otherSideIsFile && otherSidePath && otherSidePath !== workingPath;
 
         if (historySourceSide === "original") {
-          setSideContent("modified", workingText, workingPath);
+          setSideContent("modified", workingText, workingPath, originalMeta);
           setSideContent("original", content, commitLabel);
           setHistorySourceSide("modified");
         } else {
@@ -859,12 +1286,12 @@ function App() {
         if (blameMode) {
           setBlameContent(content);
           // Fetch blame for the historical version
-          if (entry.provider === "git") {
-            // For git, we can get blame for specific commits
+          if (hashRevisionProviders.includes(entry.provider)) {
+            // Hash-based providers can blame a specific commit
             await fetchBlame(entry.path, {
               commit: entry.hash,
               repoRoot: historyRepoRoot || undefined,
-              provider: "git",
+              provider: entry.provider,
             });
           } else {
             // For P4/SVN, clear blame data as we can't get historical blame easily
@@ -894,6 +1321,7 @@ function App() {
       modifiedPath,
       modifiedText,
       originalIsFile,
+      originalMeta,
       originalPath,
       originalText,
       setSideContent,
@@ -902,6 +1330,175 @@ function App() {
     ],
   );
 
+  const handleCompareIntegration = useCallback(
+    async (entry: HistoryEntry, integration: HistoryIntegration) => {
+      if (!historyTargetPath) {
+        showStatus("History is not available yet.", 2500);
+        return;
+      }
+      const prefix = getHistoryPrefix(entry.provider);
+      const show = (revision: string, path: string) =>
+        invoke<string>("vcs_show_file", {
+          provider: entry.provider,
+          revision,
+          path,
+          repoRoot: historyRepoRoot,
+          workingPath: historyTargetPath,
+        });
+      setHistoryLoadingHash(entry.hash);
+      try {
+        const [source, target] = await Promise.all([
+          show(integration.revision, integration.path),
+          entry.deleted ? Promise.resolve("") : show(entry.hash, entry.path),
+        ]);
+        setRevisionImage(null);
+        setSideContent("original", source, `${prefix}${integration.revision}:${integration.path}`);
+        setSideContent(
+          "modified",
+          target,
+          entry.deleted ? null : `${prefix}${getHistoryId(entry)}:${entry.path}`,
+        );
+        setHistorySelectedHash(entry.hash);
+        showStatus(`Comparing with ${integration.how} ${integration.path}${integration.revision}.`, 2600);
+      } catch (error) {
+        console.error(error);
+        showStatus(`Failed to load integration source: ${formatInvokeError(error)}`, 8000);
+      } finally {
+        setHistoryLoadingHash(null);
+      }
+    },
+    [formatInvokeError, historyRepoRoot, historyTargetPath, setSideContent, showStatus],
+  );
+
+  const handleToggleChanges = useCallback(() => {
+    if (changesOpen) {
+      closeChanges();
+      return;
+    }
+    if (!changesTargetPath) {
+      showStatus("Open a file inside a repository first.", 2500);
+      return;
+    }
+    setChangesSelectedPath(null);
+    void refreshChanges(changesTargetPath);
+  }, [changesOpen, changesTargetPath, closeChanges, refreshChanges, showStatus]);
+
+  const handleOpenChange = useCallback(
+    async (entry: VcsStatusEntry) => {
+      if (!changesResult || changesResult.provider === "none") {
+        return;
+      }
+      const provider = changesResult.provider;
+      setChangesSelectedPath(entry.path);
+      try {
+        if (entry.baseRevision) {
+          const basePath = entry.originalPath ?? entry.path;
+          const content = await invoke<string>("vcs_show_file", {
+            provider,
+            revision: entry.baseRevision,
+            path: basePath,
+            repoRoot: changesResult.repoRoot,
+            workingPath: entry.workingPath,
+          });
+          setSideContent(
+            "original",
+            content,
+            `${getHistoryPrefix(provider)}${entry.baseRevision}:${basePath}`,
+          );
+        } else {
+          setSideContent("original", "", null);
+        }
+        if (entry.kind === "deleted") {
+          setSideContent("modified", "", null);
+        } else {
+          await openFilePath(entry.workingPath, "modified");
+        }
+      } catch (error) {
+        console.error(error);
+        showStatus(`Failed to load base version: ${formatInvokeError(error)}`, 6000);
+      }
+    },
+    [changesResult, formatInvokeError, openFilePath, setSideContent, showStatus],
+  );
+
+  const handleOpenChangeset = useCallback(() => {
+    if (!selectedHistoryEntry) {
+      showStatus("Select a commit or changelist first.", 2500);
+      return;
+    }
+    void loadChangeset({
+      provider: selectedHistoryEntry.provider,
+      revision: selectedHistoryEntry.hash,
+      repoRoot: historyRepoRoot,
+      workingPath: historyTargetPath,
+    });
+  }, [historyRepoRoot, historyTargetPath, loadChangeset, selectedHistoryEntry, showStatus]);
+
+  const handleOpenShelf = useCallback(() => {
+    const change = shelvedChangeInput.trim();
+    if (!historyTargetPath) {
+      showStatus("Open a file in the P4 workspace first.", 2500);
+      return;
+    }
+    if (!/^\d+$/.test(change)) {
+      showStatus("Enter a changelist number.", 2500);
+      return;
+    }
+    void loadChangeset({
+      provider: "p4",
+      revision: change,
+      repoRoot: null,
+      workingPath: historyTargetPath,
+      shelved: true,
+    });
+  }, [historyTargetPath, loadChangeset, shelvedChangeInput, showStatus]);
+
+  const handleCompareRevisions = useCallback(async () => {
+    if (!historyTargetPath) {
+      showStatus("Open a versioned file first.", 2500);
+      return;
+    }
+    const result = await compareRevisions(historyTargetPath);
+    if (!result) {
+      return;
+    }
+    const prefix = getHistoryPrefix(result.provider);
+    const label = (revision: string, path: string) =>
+      `${prefix}${formatRevisionId(result.provider, revision)}:${path}`;
+    const { original, modified } = result;
+    setSideContent("original", original.content, label(original.revision, original.path));
+    setSideContent("modified", modified.content, label(modified.revision, modified.path));
+    setHistorySelectedHash(null);
+  }, [compareRevisions, historyTargetPath, setSideContent, showStatus]);
+
+  const handleOpenChangesetFile = useCallback(
+    async (index: number) => {
+      const file = changeset?.files[index];
+      if (!changeset || !file) {
+        return;
+      }
+      setChangesetFileIndex(index);
+      try {
+        const { original, modified } = await fetchChangesetFile(file);
+        const prefix = getHistoryPrefix(changeset.provider);
+        setSideContent(
+          "original",
+          original,
+          file.baseRevision ? `${prefix}${file.baseRevision}:${file.originalPath ?? file.path}` : null,
+        );
+        setSideContent(
+          "modified",
+          modified,
+          file.revision ? `${prefix}${file.revision}:${file.path}` : null,
+        );
+      } catch (error) {
+        console.error(error);
+        showStatus(`Failed to load changeset file: ${formatInvokeError(error)}`, 6000);
+      }
+    },
+    [changeset, fetchChangesetFile, formatInvokeError, setChangesetFileIndex, setSideContent, showStatus],
+  );
+
   const handleNavigateDiff = useCallback(
     (direction: "next" | "prev") => {
       const editor = diffEditorRef.current;
@@ -986,6 +1583,8 @@ function App() {
     let unlistenOpenRight: (() => void) | null = null;
     let unlistenSaveFocused: (() => void) | null = null;
     let unlistenTheme: (() => void) | null = null;
+    let unlistenCompareFolders: (() => void) | null = null;
+    let unlistenInstallGitTool: (() => void) | null = null;
 
     const setup = async () => {
       unlistenDrag = await getCurrentWindow().onDragDropEvent((event) => {
@@ -993,22 +1592,22 @@ function App() {
           return;
         }
         if (event.payload.type === "drop") {
-          const preferredSide = getDropSide(event.payload.position.x);
-          void applyPaths(event.payload.paths, "drop", preferredSide);
+          const { paths, position } = event.payload;
+          const preferredSide = getDropSide(position.x);
+          void openDirectoryPair(paths).then((handled) => {
+            if (!handled) {
+              void applyPaths(paths, "drop", preferredSide);
+            }
+          });
         }
       });
 
-      unlistenOpen = await listen<string[]>(
-        "gcompare://open-files",
-        (event) => {
-          if (!active) {
-            return;
-          }
-          if (Array.isArray(event.payload)) {
-            enqueueOpenPaths(event.payload);
-          }
-        },
-      );
+      unlistenOpen = await listen<CompareSession>("gcompare://open-files", (event) => {
+        if (!active) {
+          return;
+        }
+        openSession(event.payload);
+      });
 
       unlistenMenu = await listen("gcompare://check-updates", () => {
         if (!active) {
@@ -1038,6 +1637,20 @@ function App() {
         void handleSaveFocused();
       });
 
+      unlistenCompareFolders = await listen("gcompare://compare-folders", () => {
+        if (!active) {
+          return;
+        }
+        void handleCompareFolders();
+      });
+
+      unlistenInstallGitTool = await listen("gcompare://install-git-tool", () => {
+        if (!active) {
+          return;
+        }
+        void handleInstallGitTool();
+      });
+
       unlistenTheme = await listen<string>("gcompare://set-theme", (event) => {
         if (!active) {
           return;
@@ -1048,15 +1661,11 @@ function App() {
         }
       });
 
-      // Only consume initial paths once to avoid race conditions with StrictMode
-      // Note: we check initialPathsConsumedRef before the async operation to prevent double consumption
+      // Only consume initial sessions once to avoid race conditions with StrictMode
       if (!initialPathsConsumedRef.current) {
         initialPathsConsumedRef.current = true;
-        const initial = await invoke<string[]>("consume_open_paths");
-        // Don't check 'active' here - we want to process initial paths even if effect re-runs
-        if (Array.isArray(initial) && initial.length > 0) {
-          enqueueOpenPaths(initial);
-        }
+        // Don't check 'active' here - we want to process initial sessions even if effect re-runs
+        await initSessions();
       }
     };
 
@@ -1085,12 +1694,22 @@ function App() {
       if (unlistenTheme) {
         unlistenTheme();
       }
+      if (unlistenCompareFolders) {
+        unlistenCompareFolders();
+      }
+      if (unlistenInstallGitTool) {
+        unlistenInstallGitTool();
+      }
     };
   }, [
     applyPaths,
-    enqueueOpenPaths,
     handleCheckUpdates,
+    handleCompareFolders,
+    handleInstallGitTool,
     handleOpenFile,
+    initSessions,
+    openDirectoryPair,
+    openSession,
     handleSaveFocused,
     updateTheme,
   ]);
@@ -1116,6 +1735,22 @@ function App() {
               <span className="action-label-full">Open Right File</span>
               <span className="action-label-short">Right File</span>
             </button>
+            <button
+              className={`action-btn${dirCompareActive ? " is-active" : ""}`}
+              type="button"
+              onClick={() => void handleCompareFolders()}
+            >
+              <span className="action-label-full">Compare Folders</span>
+              <span className="action-label-short">Folders</span>
+            </button>
+            <button
+              className={`action-btn${changesOpen ? " is-active" : ""}`}
+              type="button"
+              onClick={handleToggleChanges}
+            >
+              <span className="action-label-full">Pending Changes</span>
+              <span className="action-label-short">Changes</span>
+            </button>
           </div>
           <div className="diff-nav diff-nav-bar">
             Diffs: {diffChanges.length === 0 ? "0" : `${diffIndex + 1}/${diffChanges.length}`}
@@ -1180,6 +1815,78 @@ function App() {
             )}
           </div>
         </header>
+        {mergeSession ? (
+          <div className="merge-bar" aria-label="Merge">
+            <span className="merge-title" title={mergeSession.outputPath}>
+              Merge → {getPathParts(mergeSession.outputPath).name}
+            </span>
+            <span className="merge-status">
+              {mergeConflictIndexes.length === 0
+                ? "No conflicts"
+                : `${mergeUnresolvedCount} of ${mergeConflictIndexes.length} conflicts unresolved`}
+            </span>
+            <div className="merge-conflicts">
+              {mergeConflictIndexes.map((regionIndex, conflictIndex) => (
+                <label key={regionIndex} className="merge-conflict">
+                  <span>#{conflictIndex + 1}</span>
+                  <select
+                    value={mergeChoices[regionIndex] ?? ""}
+                    onChange={(event) =>
+                      chooseConflict(regionIndex, (event.target.value || null) as MergeChoice | null)
+                    }
+                  >
+                    <option value="">Unresolved</option>
+                    <option value="left">Left</option>
+                    <option value="right">Right</option>
+                    <option value="both">Left + Right</option>
+                    <option value="base">Base</option>
+                  </select>
+                </label>
+              ))}
+            </div>
+            <button
+              className="action-btn"
+              type="button"
+              disabled={mergeUnresolvedCount > 0 || mergeSaving}
+              onClick={() => void finishMerge()}
+            >
+              Save Merge
+            </button>
+            <button className="action-btn" type="button" onClick={() => void abortMerge()}>
+              Abort
+            </button>
+          </div>
+        ) : null}
+        <div className="compare-tabs" role="tablist" aria-label="Comparisons">
+          {tabs.map((tab) => (
+            <div
+              key={tab.id}
+              className={`compare-tab${tab.id === activeTabId ? " is-active" : ""}`}
+              role="tab"
+              aria-selected={tab.id === activeTabId}
+              title={
+                (tab.paths.join("\n") || "Untitled") +
+                (tab.waitId !== null ? "\nThe launching tool waits until this tab is closed." : "")
+              }
+            >
+              <button className="compare-tab-label" type="button" onClick={() => activateTab(tab.id)}>
+                {formatTabTitle(tab.paths)}
+                {tab.waitId !== null ? " ⏳" : ""}
+              </button>
+              <button
+                className="compare-tab-close"
+                type="button"
+                onClick={() => void closeTab(tab.id)}
+                aria-label="Close tab"
+              >
+                ×
+              </button>
+            </div>
+          ))}
+          <button className="compare-tab-new" type="button" onClick={() => void newTab()} aria-label="New tab">
+            +
+          </button>
+        </div>
         <div className="workspace">
           <div
             className={`history-shell${historyVisible ? " is-open" : ""}${historyPinned ? " is-pinned" : ""}`}
@@ -1221,6 +1928,15 @@ function App() {
                       >
                         {historyBusy ? "Loading..." : "Refresh"}
                       </button>
+                      <button
+                        className="history-refresh"
+                        type="button"
+                        onClick={handleOpenChangeset}
+                        disabled={!selectedHistoryEntry || changesetLoading}
+                        title="List every file in the selected commit / changelist"
+                      >
+                        {changesetLoading ? "Loading..." : "Files"}
+                      </button>
                     </div>
                   </div>
                   <div className="history-controls">
@@ -1232,7 +1948,10 @@ function App() {
                     >
                       <span className={`p4-settings-arrow${p4SettingsOpen ? " is-open" : ""}`}>▶</span>
                       <span>P4Config</span>
-                      {(settings.p4?.port || settings.p4?.user || settings.p4?.client) && (
+                      {(settings.p4?.port ||
+                        settings.p4?.user ||
+                        settings.p4?.client ||
+                        p4Profiles.length > 0) && (
                         <span className="p4-settings-badge">●</span>
                       )}
                     </button>
@@ -1269,6 +1988,24 @@ function App() {
                           onChange={(e) => setP4ClientInput(e.target.value)}
                         />
                       </label>
+                      <label className="p4-setting-field">
+                        <span>P4TICKETS</span>
+                        <input
+                          type="text"
+                          placeholder="default ticket file"
+                          value={p4TicketsInput}
+                          onChange={(e) => setP4TicketsInput(e.target.value)}
+                        />
+                      </label>
+                      <label className="p4-setting-field">
+                        <span>P4TRUST</span>
+                        <input
+                          type="text"
+                          placeholder="default trust file"
+                          value={p4TrustInput}
+                          onChange={(e) => setP4TrustInput(e.target.value)}
+                        />
+                      </label>
                       <div className="p4-settings-actions">
                         <button
                           type="button"
@@ -1278,6 +2015,8 @@ function App() {
                               port: p4PortInput,
                               user: p4UserInput,
                               client: p4ClientInput,
+                              tickets: p4TicketsInput,
+                              trust: p4TrustInput,
                             }).then(() => {
                               showStatus("P4 settings saved");
                             });
@@ -1292,10 +2031,14 @@ function App() {
                             setP4PortInput("");
                             setP4UserInput("");
                             setP4ClientInput("");
+                            setP4TicketsInput("");
+                            setP4TrustInput("");
                             void updateP4Settings({
                               port: "",
                               user: "",
                               client: "",
+                              tickets: "",
+                              trust: "",
                             }).then(() => {
                               showStatus("P4 settings cleared");
                             });
@@ -1304,6 +2047,94 @@ function App() {
                           Clear
                         </button>
                       </div>
+                      <P4ProfilesEditor
+                        profiles={p4Profiles}
+                        onSave={updateP4Profiles}
+                        showStatus={showStatus}
+                      />
+                    </div>
+                  )}
+                  <div className="history-controls">
+                    <button
+                      type="button"
+                      className="p4-settings-toggle"
+                      onClick={() => setRevisionCompareOpen(!revisionCompareOpen)}
+                      title="Compare this file between any two revisions"
+                    >
+                      <span className={`p4-settings-arrow${revisionCompareOpen ? " is-open" : ""}`}>▶</span>
+                      <span>Compare Revisions</span>
+                    </button>
+                  </div>
+                  {revisionCompareOpen && (
+                    <div className="p4-settings-form">
+                      <p className="p4-settings-hint">
+                        Commit, branch, tag or HEAD~3 (git), @label / @change / #rev (P4), revision number (SVN).
+                      </p>
+                      <label className="p4-setting-field">
+                        <span>From</span>
+                        <input
+                          type="text"
+                          placeholder="e.g. HEAD~3"
+                          value={originalRevision}
+                          onChange={(e) => setOriginalRevision(e.target.value)}
+                        />
+                      </label>
+                      <label className="p4-setting-field">
+                        <span>To</span>
+                        <input
+                          type="text"
+                          placeholder="e.g. HEAD"
+                          value={modifiedRevision}
+                          onChange={(e) => setModifiedRevision(e.target.value)}
+                        />
+                      </label>
+                      <div className="p4-settings-actions">
+                        <button
+                          type="button"
+                          className="p4-settings-save"
+                          onClick={() => void handleCompareRevisions()}
+                          disabled={!historyTargetPath || revisionCompareLoading}
+                        >
+                          {revisionCompareLoading ? "Loading..." : "Compare"}
+                        </button>
+                      </div>
+                    </div>
+                  )}
+                  <div className="history-controls">
+                    <button
+                      type="button"
+                      className="p4-settings-toggle"
+                      onClick={() => setShelfReviewOpen(!shelfReviewOpen)}
+                      title="Review the files shelved in a pending P4 changelist"
+                    >
+                      <span className={`p4-settings-arrow${shelfReviewOpen ? " is-open" : ""}`}>▶</span>
+                      <span>P4 Shelf</span>
+                    </button>
+                  </div>
+                  {shelfReviewOpen && (
+                    <div className="p4-settings-form">
+                      <p className="p4-settings-hint">
+                        Each shelved file is compared with the depot revision it was shelved from.
+                      </p>
+                      <label className="p4-setting-field">
+                        <span>Changelist</span>
+                        <input
+                          type="text"
+                          placeholder="e.g. 12345"
+                          value={shelvedChangeInput}
+                          onChange={(e) => setShelvedChangeInput(e.target.value)}
+                        />
+                      </label>
+                      <div className="p4-settings-actions">
+                        <button
+                          type="button"
+                          className="p4-settings-save"
+                          onClick={handleOpenShelf}
+                          disabled={!historyTargetPath || changesetLoading}
+                        >
+                          {changesetLoading ? "Loading..." : "Review"}
+                        </button>
+                      </div>
                     </div>
                   )}
                   <div className="history-controls">
@@ -1326,26 +2157,54 @@ function App() {
                         </option>
                       </select>
                     </label>
+                    <label
+                      className="history-control-inline"
+                      title="Stop VCS commands that run longer than this"
+                    >
+                      <span>Timeout</span>
+                      <select
+                        value={settings.vcsTimeoutSecs ?? DEFAULT_VCS_TIMEOUT_SECS}
+                        onChange={(event) => void updateVcsTimeout(Number(event.target.value))}
+                      >
+                        {vcsTimeoutOptions.map((secs) => (
+                          <option key={secs} value={secs}>
+                            {secs === 0 ? "No limit" : secs < 60 ? `${secs}s` : `${secs / 60} min`}
+                          </option>
+                        ))}
+                      </select>
+                    </label>
                   </div>
                   <div className="history-list">
-                    {historyBusy ? (
+                    {historyBusy && historyEntries.length === 0 ? (
                       <div className="history-empty">Loading history...</div>
+                    ) : historyNeedsP4Login ? (
+                      <div className="history-empty">
+                        P4 login required.{" "}
+                        <button
+                          className="history-refresh"
+                          type="button"
+                          onClick={() => void loginForHistory()}
+                        >
+                          Log in
+                        </button>
+                      </div>
                     ) : historyEntries.length === 0 ? (
                       <div className="history-empty">No history entries yet.</div>
                     ) : (
                       historyEntries.map((entry) => {
                         const displayId = getHistoryId(entry);
                         const idLabel =
-                          entry.provider === "git"
+                          hashRevisionProviders.includes(entry.provider)
                             ? displayId
                             : entry.provider === "p4"
                               ? `CL ${displayId}`
                               : `r${displayId}`;
                         const isActive = historySelectedHash === entry.hash;
                         const isLoading = historyLoadingHash === entry.hash;
-                        return (
+                        const integrations = entry.integrations ?? [];
+                        const item = (
                           <button
-                            key={`${entry.provider}:${entry.hash}`}
+                            key={`${entry.provider}:${entry.hash}:${entry.path}`}
                             type="button"
                             className={`history-item${isActive ? " is-active" : ""}`}
                             onClick={() => void handleCompareCommit(entry)}
@@ -1365,6 +2224,29 @@ function App() {
                             ) : null}
                           </button>
                         );
+                        if (integrations.length === 0) {
+                          return item;
+                        }
+                        return (
+                          <div key={`${entry.provider}:${entry.hash}:${entry.path}`}>
+                            {item}
+                            {integrations.map((integration) => (
+                              <button
+                                key={`${integration.path}${integration.revision}`}
+                                type="button"
+                                className="history-item-lineage"
+                                title="Compare with the version it was integrated from"
+                                onClick={() => void handleCompareIntegration(entry, integration)}
+                                disabled={isLoading}
+                              >
+                                ↳ {integration.how} {integration.path}
+                                {integration.startRevision
+                                  ? `${integration.startRevision},${integration.revision}`
+                                  : integration.revision}
+                              </button>
+                            ))}
+                          </div>
+                        );
                       })
                     )}
                   </div>
@@ -1372,6 +2254,185 @@ function App() {
               ) : null}
             </aside>
           </div>
+          {changeset ? (
+            <aside className="changes-panel" aria-label="Changeset files">
+              <div className="dir-compare-header">
+                <div className="dir-compare-title">
+                  <span className="history-title">
+                    {changesetShelved ? "Shelf " : ""}
+                    {getHistoryPrefix(changeset.provider)}
+                    {formatRevisionId(changeset.provider, changeset.revision)}
+                  </span>
+                  <span className="dir-compare-summary">
+                    {changeset.author} · {formatCommitTime(changeset.timestamp)}
+                  </span>
+                </div>
+                <div className="history-panel-actions">
+                  <button
+                    className="history-refresh"
+                    type="button"
+                    disabled={!changesetFileIndex}
+                    onClick={() => void handleOpenChangesetFile((changesetFileIndex ?? 0) - 1)}
+                  >
+                    ↑
+                  </button>
+                  <button
+                    className="history-refresh"
+                    type="button"
+                    disabled={
+                      changeset.files.length === 0
+                      || (changesetFileIndex ?? -1) >= changeset.files.length - 1
+                    }
+                    onClick={() => void handleOpenChangesetFile((changesetFileIndex ?? -1) + 1)}
+                  >
+                    ↓
+                  </button>
+                  <button className="history-refresh" type="button" onClick={closeChangeset}>
+                    Close
+                  </button>
+                </div>
+              </div>
+              {changeset.description ? (
+                <div className="history-subtitle" title={changeset.description}>
+                  {changeset.description.split("\n")[0]}
+                </div>
+              ) : null}
+              <div className="dir-compare-list">
+                {changeset.files.length === 0 ? (
+                  <div className="history-empty">No files in this change.</div>
+                ) : (
+                  changeset.files.map((file, index) => (
+                    <button
+                      key={`${file.kind}:${file.path}`}
+                      type="button"
+                      className={`dir-compare-item is-${file.kind}${changesetFileIndex === index ? " is-active" : ""}`}
+                      onClick={() => void handleOpenChangesetFile(index)}
+                      title={file.originalPath ? `${file.originalPath} → ${file.path}` : file.path}
+                    >
+                      <span className="dir-compare-badge">{changeKindLabels[file.kind]}</span>
+                      <span className="dir-compare-path">{file.path}</span>
+                    </button>
+                  ))
+                )}
+              </div>
+            </aside>
+          ) : null}
+          {changesOpen ? (
+            <aside className="changes-panel" aria-label="Pending changes">
+              <div className="dir-compare-header">
+                <div className="dir-compare-title">
+                  <span className="history-title">Changes</span>
+                  <span className="dir-compare-summary">
+                    {changesLoading
+                      ? "Loading..."
+                      : changesResult && changesResult.provider !== "none"
+                        ? `${changesResult.provider.toUpperCase()} · ${changesResult.entries.length} files`
+                        : ""}
+                  </span>
+                </div>
+                <div className="history-panel-actions">
+                  <button
+                    className="history-refresh"
+                    type="button"
+                    disabled={changesLoading || !changesTargetPath}
+                    onClick={() => changesTargetPath && void refreshChanges(changesTargetPath)}
+                  >
+                    Refresh
+                  </button>
+                  <button className="history-refresh" type="button" onClick={closeChanges}>
+                    Close
+                  </button>
+                </div>
+              </div>
+              {changesResult?.repoRoot ? (
+                <div className="dir-compare-roots" title={changesResult.repoRoot}>
+                  <span>{getPathParts(changesResult.repoRoot).name}</span>
+                </div>
+              ) : null}
+              <div className="dir-compare-list">
+                {changesError ? (
+                  <div className="history-empty">{changesError}</div>
+                ) : changesResult && changesResult.entries.length === 0 && !changesLoading ? (
+                  <div className="history-empty">No pending changes.</div>
+                ) : (
+                  changesResult?.entries.map((entry) => (
+                    <button
+                      key={`${entry.kind}:${entry.path}`}
+                      type="button"
+                      className={`dir-compare-item is-${entry.kind}${changesSelectedPath === entry.path ? " is-active" : ""}`}
+                      onClick={() => void handleOpenChange(entry)}
+                      title={entry.originalPath ? `${entry.originalPath} → ${entry.path}` : entry.path}
+                    >
+                      <span className="dir-compare-badge">{changeKindLabels[entry.kind]}</span>
+                      <span className="dir-compare-path">{entry.path}</span>
+                    </button>
+                  ))
+                )}
+              </div>
+            </aside>
+          ) : null}
+          {dirCompareActive && dirCompareRoots ? (
+            <aside className="dir-compare-panel" aria-label="Folder comparison">
+              <div className="dir-compare-header">
+                <div className="dir-compare-title">
+                  <span className="history-title">Folders</span>
+                  <span className="dir-compare-summary">
+                    {dirCompareBusy
+                      ? `Comparing... ${dirCompareEntries.length}`
+                      : dirCompareSummary
+                        ? `${dirCompareSummary.modified} M · ${dirCompareSummary.added} + · ${dirCompareSummary.removed} −`
+                        : ""}
+                  </span>
+                </div>
+                <button
+                  className="history-refresh"
+                  type="button"
+                  onClick={() => {
+                    setDirSelectedPath(null);
+                    closeDirectoryCompare();
+                  }}
+                >
+                  Close
+                </button>
+              </div>
+              <div
+                className="dir-compare-roots"
+                title={`${dirCompareRoots.left}\n${dirCompareRoots.right}`}
+              >
+                <span>{getPathParts(dirCompareRoots.left).name}</span>
+                <span>↔</span>
+                <span>{getPathParts(dirCompareRoots.right).name}</span>
+              </div>
+              <label className="dir-compare-filter">
+                <input
+                  type="checkbox"
+                  checked={dirHideIdentical}
+                  onChange={(event) => setDirHideIdentical(event.target.checked)}
+                />
+                <span>Hide identical</span>
+              </label>
+              <div className="dir-compare-list">
+                {dirCompareError ? (
+                  <div className="history-empty">{dirCompareError}</div>
+                ) : (
+                  dirCompareEntries
+                    .filter((entry) => !dirHideIdentical || entry.status !== "identical")
+                    .map((entry) => (
+                      <button
+                        key={entry.relativePath}
+                        type="button"
+                        className={`dir-compare-item is-${entry.status}${dirSelectedPath === entry.relativePath ? " is-active" : ""}`}
+                        onClick={() => void handleOpenDirEntry(entry)}
+                        title={entry.error ?? entry.relativePath}
+                      >
+                        <span className="dir-compare-badge">{dirStatusLabels[entry.status]}</span>
+                        <span className="dir-compare-path">{entry.relativePath}</span>
+                      </button>
+                    ))
+                )}
+              </div>
+            </aside>
+          ) : null}
           <section className="diff-panel" aria-label={blameMode ? "Blame view" : "Diff editor"}>
             {blameMode ? (
               blameFilePath && (
@@ -1397,6 +2458,18 @@ function App() {
                   }}
                 />
               )
+            ) : imageMode ? (
+              <ImageCompareView
+                original={originalImage}
+                modified={modifiedImage}
+                showStatus={showStatus}
+              />
+            ) : binaryMode ? (
+              <HexCompareView
+                leftPath={originalIsFile ? originalPath : null}
+                rightPath={modifiedIsFile ? modifiedPath : null}
+                showStatus={showStatus}
+              />
             ) : (
               <DiffEditor
                 original={originalText}
@@ -1526,9 +2599,11 @@ function App() {
         <div className="status-bar" role="status" aria-live="polite">
           <span className="status-item">
             Left: {originalPath ? originalPath : "Untitled"}
+            {originalMeta ? ` (${formatTextFileMeta(originalMeta)})` : ""}
           </span>
           <span className="status-item">
             Right: {modifiedPath ? modifiedPath : "Untitled"}
+            {modifiedMeta ? ` (${formatTextFileMeta(modifiedMeta)})` : ""}
           </span>
           <span className="status-item status-message">
             {statusMessage ?? ""}
@@ -1538,6 +2613,45 @@ function App() {
           </span>
         </div>
       </div>
+      {loginPrompt && (
+        <div className="p4-login-overlay">
+          <form
+            className="p4-login-dialog p4-settings-form"
+            onSubmit={(e) => {
+              e.preventDefault();
+              answerLogin(p4PasswordInput);
+              setP4PasswordInput("");
+            }}
+          >
+            <strong>P4 login required</strong>
+            <p className="p4-settings-hint">{loginPrompt.message}</p>
+            <label className="p4-setting-field">
+              <span>Password</span>
+              <input
+                type="password"
+                autoFocus
+                value={p4PasswordInput}
+                onChange={(e) => setP4PasswordInput(e.target.value)}
+              />
+            </label>
+            <div className="p4-settings-actions">
+              <button type="submit" className="p4-settings-save" disabled={!p4PasswordInput}>
+                Log in
+              </button>
+              <button
+                type="button"
+                className="p4-settings-clear"
+                onClick={() => {
+                  answerLogin(null);
+                  setP4PasswordInput("");
+                }}
+              >
+                Cancel
+              </button>
+            </div>
+          </form>
+        </div>
+      )}
     </main>
   );
 }
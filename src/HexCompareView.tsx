@@ -0,0 +1,187 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { BinaryCompareSummary } from "./types/bincmp";
+
+const BYTES_PER_ROW = 16;
+const ROWS_PER_PAGE = 64;
+const PAGE_SIZE = BYTES_PER_ROW * ROWS_PER_PAGE;
+
+type HexCompareViewProps = {
+  leftPath: string | null;
+  rightPath: string | null;
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+const formatOffset = (offset: number) => offset.toString(16).padStart(8, "0");
+const formatByte = (byte: number | undefined) =>
+  byte === undefined ? "  " : byte.toString(16).padStart(2, "0");
+const formatAscii = (byte: number | undefined) =>
+  byte === undefined ? " " : byte >= 0x20 && byte < 0x7f ? String.fromCharCode(byte) : ".";
+
+const readChunk = (path: string | null, offset: number) =>
+  path
+    ? invoke<number[]>("read_binary_chunk", { path, offset, length: PAGE_SIZE })
+    : Promise.resolve<number[]>([]);
+
+export default function HexCompareView({ leftPath, rightPath, showStatus }: HexCompareViewProps) {
+  const [summary, setSummary] = useState<BinaryCompareSummary | null>(null);
+  const [pageOffset, setPageOffset] = useState(0);
+  const [leftBytes, setLeftBytes] = useState<number[]>([]);
+  const [rightBytes, setRightBytes] = useState<number[]>([]);
+
+  useEffect(() => {
+    let cancelled = false;
+    setSummary(null);
+    setPageOffset(0);
+    if (!leftPath || !rightPath) {
+      return;
+    }
+    invoke<BinaryCompareSummary>("compare_binary", { left: leftPath, right: rightPath })
+      .then((result) => {
+        if (cancelled) {
+          return;
+        }
+        setSummary(result);
+        if (result.firstDifference !== null) {
+          setPageOffset(result.firstDifference - (result.firstDifference % PAGE_SIZE));
+        }
+      })
+      .catch((error) => {
+        if (!cancelled) {
+          showStatus(`Binary compare failed: ${String(error)}`, 6000);
+        }
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [leftPath, rightPath, showStatus]);
+
+  useEffect(() => {
+    let cancelled = false;
+    Promise.all([readChunk(leftPath, pageOffset), readChunk(rightPath, pageOffset)])
+      .then(([left, right]) => {
+        if (!cancelled) {
+          setLeftBytes(left);
+          setRightBytes(right);
+        }
+      })
+      .catch((error) => {
+        if (!cancelled) {
+          showStatus(`Failed to read file: ${String(error)}`, 6000);
+        }
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [leftPath, pageOffset, rightPath, showStatus]);
+
+  const totalSize = Math.max(summary?.leftSize ?? 0, summary?.rightSize ?? 0);
+  const lastPage = Math.max(0, totalSize - 1 - ((totalSize - 1) % PAGE_SIZE));
+
+  const jumpToDifference = useCallback(
+    (direction: 1 | -1) => {
+      if (!summary) {
+        return;
+      }
+      const target =
+        direction === 1
+          ? summary.ranges.find((range) => range.offset >= pageOffset + PAGE_SIZE)
+          : [...summary.ranges].reverse().find((range) => range.offset < pageOffset);
+      if (!target) {
+        showStatus(direction === 1 ? "No more differences." : "No earlier differences.", 2000);
+        return;
+      }
+      setPageOffset(target.offset - (target.offset % PAGE_SIZE));
+    },
+    [pageOffset, showStatus, summary],
+  );
+
+  const renderSide = (bytes: number[], other: number[], rowStart: number) => {
+    const cells = [];
+    for (let column = 0; column < BYTES_PER_ROW; column += 1) {
+      const index = rowStart + column;
+      const byte = bytes[index];
+      const changed = byte !== other[index];
+      cells.push(
+        <span key={column} className={`hex-byte${changed && byte !== undefined ? " is-changed" : ""}`}>
+          {formatByte(byte)}
+        </span>,
+      );
+    }
+    let ascii = "";
+    for (let column = 0; column < BYTES_PER_ROW; column += 1) {
+      ascii += formatAscii(bytes[rowStart + column]);
+    }
+    return (
+      <>
+        <span className="hex-bytes">{cells}</span>
+        <span className="hex-ascii">{ascii}</span>
+      </>
+    );
+  };
+
+  const rowCount = Math.ceil(Math.max(leftBytes.length, rightBytes.length) / BYTES_PER_ROW);
+  const rows = [];
+  for (let row = 0; row < rowCount; row += 1) {
+    const rowStart = row * BYTES_PER_ROW;
+    rows.push(
+      <div key={row} className="hex-row">
+        <span className="hex-offset">{formatOffset(pageOffset + rowStart)}</span>
+        {renderSide(leftBytes, rightBytes, rowStart)}
+        <span className="hex-offset">{formatOffset(pageOffset + rowStart)}</span>
+        {renderSide(rightBytes, leftBytes, rowStart)}
+      </div>,
+    );
+  }
+
+  return (
+    <div className="hex-compare">
+      <div className="hex-compare-header">
+        <span className="hex-compare-summary">
+          {!leftPath || !rightPath
+            ? "Binary file. Open a file on the other side to compare bytes."
+            : !summary
+              ? "Comparing bytes..."
+              : summary.identical
+                ? `Files are identical (${summary.leftSize} bytes).`
+                : `First difference at 0x${formatOffset(summary.firstDifference ?? 0)} · ${summary.rangeCount} differing range(s) · ${summary.differingBytes} byte(s) differ · ${summary.leftSize} vs ${summary.rightSize} bytes`}
+        </span>
+        <div className="history-panel-actions">
+          <button
+            className="history-refresh"
+            type="button"
+            onClick={() => jumpToDifference(-1)}
+            disabled={!summary || summary.identical}
+          >
+            Prev Diff
+          </button>
+          <button
+            className="history-refresh"
+            type="button"
+            onClick={() => jumpToDifference(1)}
+            disabled={!summary || summary.identical}
+          >
+            Next Diff
+          </button>
+          <button
+            className="history-refresh"
+            type="button"
+            onClick={() => setPageOffset((prev) => Math.max(0, prev - PAGE_SIZE))}
+            disabled={pageOffset === 0}
+          >
+            ↑
+          </button>
+          <button
+            className="history-refresh"
+            type="button"
+            onClick={() => setPageOffset((prev) => Math.min(lastPage, prev + PAGE_SIZE))}
+            disabled={pageOffset >= lastPage}
+          >
+            ↓
+          </button>
+        </div>
+      </div>
+      <div className="hex-rows">{rows}</div>
+    </div>
+  );
+}
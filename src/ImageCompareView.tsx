@@ -0,0 +1,167 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+  ImageDiffResult,
+  ImageInfo,
+  ImageSource,
+  ImageViewMode,
+} from "./types/imgcmp";
+
+type ImageCompareViewProps = {
+  original: ImageSource | null;
+  modified: ImageSource | null;
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+const viewModes: Array<{ value: ImageViewMode; label: string }> = [
+  { value: "side-by-side", label: "Side by Side" },
+  { value: "onion-skin", label: "Onion Skin" },
+  { value: "highlight", label: "Highlight" },
+];
+
+const percent = (value: number, total: number) => `${total > 0 ? (value / total) * 100 : 0}%`;
+
+export default function ImageCompareView({ original, modified, showStatus }: ImageCompareViewProps) {
+  const [result, setResult] = useState<ImageDiffResult | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [viewMode, setViewMode] = useState<ImageViewMode>("side-by-side");
+  const [opacity, setOpacity] = useState(50);
+  const [tolerance, setTolerance] = useState(0);
+
+  useEffect(() => {
+    let cancelled = false;
+    setResult(null);
+    setError(null);
+    if (!original || !modified) {
+      return;
+    }
+    invoke<ImageDiffResult>("compare_images", { original, modified, options: { tolerance } })
+      .then((next) => {
+        if (!cancelled) {
+          setResult(next);
+        }
+      })
+      .catch((compareError) => {
+        if (!cancelled) {
+          const message = String(compareError);
+          setError(message);
+          showStatus(`Image compare failed: ${message}`, 6000);
+        }
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [modified, original, showStatus, tolerance]);
+
+  const renderCanvas = (layers: Array<{ image: ImageInfo; opacity?: number }>, mask: boolean) => {
+    if (!result) {
+      return null;
+    }
+    return (
+      <div
+        className="image-canvas"
+        style={{ aspectRatio: `${result.width} / ${result.height}`, maxWidth: `${result.width}px` }}
+      >
+        {layers.map(({ image, opacity: layerOpacity }, index) => (
+          <img
+            key={index}
+            className="image-layer"
+            src={image.dataUrl}
+            alt=""
+            style={{
+              width: percent(image.width, result.width),
+              height: percent(image.height, result.height),
+              opacity: layerOpacity ?? 1,
+            }}
+          />
+        ))}
+        {mask ? <img className="image-layer" src={result.maskUrl} alt="" /> : null}
+        {result.boxes.map((rect, index) => (
+          <span
+            key={index}
+            className="image-box"
+            style={{
+              left: percent(rect.x, result.width),
+              top: percent(rect.y, result.height),
+              width: percent(rect.width, result.width),
+              height: percent(rect.height, result.height),
+            }}
+          />
+        ))}
+      </div>
+    );
+  };
+
+  const summary = !original || !modified
+    ? "Open an image on the other side to compare."
+    : error
+      ? error
+      : !result
+        ? "Comparing images..."
+        : result.changedPixels === 0
+          ? `Images are identical (${result.width}×${result.height}).`
+          : `${result.changedPixels.toLocaleString()} of ${result.totalPixels.toLocaleString()} pixels differ (${((result.changedPixels / result.totalPixels) * 100).toFixed(2)}%) · ${result.boxCount} region(s) · ${result.original.width}×${result.original.height} vs ${result.modified.width}×${result.modified.height}`;
+
+  return (
+    <div className="image-compare">
+      <div className="hex-compare-header">
+        <span className="hex-compare-summary">{summary}</span>
+        <div className="history-panel-actions">
+          {viewMode === "onion-skin" ? (
+            <input
+              type="range"
+              min={0}
+              max={100}
+              value={opacity}
+              onChange={(event) => setOpacity(Number(event.target.value))}
+              title="Right image opacity"
+            />
+          ) : null}
+          <label className="history-control-inline" title="Largest per-channel difference ignored">
+            <span>Tolerance</span>
+            <input
+              className="image-tolerance"
+              type="number"
+              min={0}
+              max={255}
+              value={tolerance}
+              onChange={(event) =>
+                setTolerance(Math.min(255, Math.max(0, Number(event.target.value) || 0)))
+              }
+            />
+          </label>
+          {viewModes.map((mode) => (
+            <button
+              key={mode.value}
+              className={`history-refresh${viewMode === mode.value ? " is-active" : ""}`}
+              type="button"
+              onClick={() => setViewMode(mode.value)}
+              aria-pressed={viewMode === mode.value}
+            >
+              {mode.label}
+            </button>
+          ))}
+        </div>
+      </div>
+      {result ? (
+        <div className={`image-stage is-${viewMode}`}>
+          {viewMode === "side-by-side" ? (
+            <>
+              <div className="image-pane">{renderCanvas([{ image: result.original }], false)}</div>
+              <div className="image-pane">{renderCanvas([{ image: result.modified }], false)}</div>
+            </>
+          ) : viewMode === "onion-skin" ? (
+            <div className="image-pane">
+              {renderCanvas(
+                [{ image: result.original }, { image: result.modified, opacity: opacity / 100 }],
+                false,
+              )}
+            </div>
+          ) : (
+            <div className="image-pane">{renderCanvas([{ image: result.modified }], true)}</div>
+          )}
+        </div>
+      ) : null}
+    </div>
+  );
+}
@@ -0,0 +1,96 @@
+import { useEffect, useState } from "react";
+import type { P4Profile } from "./types/settings";
+
+type P4ProfilesEditorProps = {
+  profiles: P4Profile[];
+  onSave: (profiles: P4Profile[]) => Promise<void>;
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+const emptyProfile = (): P4Profile => ({
+  name: "",
+  rootPattern: "",
+  port: "",
+  user: "",
+  client: "",
+});
+
+const profileFields: Array<{ key: keyof P4Profile; label: string; placeholder: string }> = [
+  { key: "name", label: "Name", placeholder: "e.g. main workspace" },
+  { key: "rootPattern", label: "Client root", placeholder: "e.g. D:/p4/*/main" },
+  { key: "port", label: "P4PORT", placeholder: "e.g. ssl:perforce:1666" },
+  { key: "user", label: "P4USER", placeholder: "username" },
+  { key: "client", label: "P4CLIENT", placeholder: "workspace name" },
+];
+
+/** Named P4 connections picked by file location before the fallback settings. */
+export default function P4ProfilesEditor({ profiles, onSave, showStatus }: P4ProfilesEditorProps) {
+  const [drafts, setDrafts] = useState<P4Profile[]>(profiles);
+
+  useEffect(() => {
+    setDrafts(profiles);
+  }, [profiles]);
+
+  const updateDraft = (index: number, key: keyof P4Profile, value: string) => {
+    setDrafts((prev) =>
+      prev.map((profile, current) => (current === index ? { ...profile, [key]: value } : profile)),
+    );
+  };
+
+  const save = () => {
+    const cleaned = drafts
+      .map((profile) => ({
+        name: profile.name.trim(),
+        rootPattern: profile.rootPattern.trim(),
+        port: profile.port.trim(),
+        user: profile.user.trim(),
+        client: profile.client.trim(),
+      }))
+      .filter((profile) => profile.name || profile.rootPattern);
+    void onSave(cleaned).then(() => {
+      showStatus("P4 profiles saved");
+    });
+  };
+
+  return (
+    <div className="p4-profiles">
+      <p className="p4-settings-hint">
+        Profiles whose client root matches the file's folder are used before the fallback above.
+      </p>
+      {drafts.map((profile, index) => (
+        <div className="p4-profile" key={index}>
+          {profileFields.map((field) => (
+            <label className="p4-setting-field" key={field.key}>
+              <span>{field.label}</span>
+              <input
+                type="text"
+                placeholder={field.placeholder}
+                value={profile[field.key]}
+                onChange={(e) => updateDraft(index, field.key, e.target.value)}
+              />
+            </label>
+          ))}
+          <button
+            type="button"
+            className="p4-settings-clear"
+            onClick={() => setDrafts((prev) => prev.filter((_, current) => current !== index))}
+          >
+            Remove
+          </button>
+        </div>
+      ))}
+      <div className="p4-settings-actions">
+        <button
+          type="button"
+          className="p4-settings-clear"
+          onClick={() => setDrafts((prev) => [...prev, emptyProfile()])}
+        >
+          Add profile
+        </button>
+        <button type="button" className="p4-settings-save" onClick={save}>
+          Save profiles
+        </button>
+      </div>
+    </div>
+  );
+}
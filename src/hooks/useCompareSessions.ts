@@ -0,0 +1,173 @@
+import { useCallback, useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { ComparePaneState, CompareSession, CompareTab } from "../types/sessions";
+
+type UseCompareSessionsOptions = {
+  /** Current pane contents, including unsaved edits. */
+  capturePanes: () => ComparePaneState;
+  restorePanes: (state: ComparePaneState | null) => void;
+  loadPaths: (paths: string[]) => Promise<void>;
+};
+
+const hasContent = (state: ComparePaneState) =>
+  Boolean(state.originalPath || state.modifiedPath);
+
+export const useCompareSessions = (options: UseCompareSessionsOptions) => {
+  const [tabs, setTabs] = useState<CompareTab[]>([]);
+  const [activeTabId, setActiveTabId] = useState<number | null>(null);
+  // Refs are the source of truth so back-to-back events see each other's changes.
+  const tabsRef = useRef<CompareTab[]>([]);
+  const activeTabIdRef = useRef<number | null>(null);
+  const optionsRef = useRef(options);
+
+  useEffect(() => {
+    optionsRef.current = options;
+  }, [options]);
+
+  const commit = useCallback((nextTabs: CompareTab[], nextActiveId: number | null) => {
+    tabsRef.current = nextTabs;
+    activeTabIdRef.current = nextActiveId;
+    setTabs(nextTabs);
+    setActiveTabId(nextActiveId);
+  }, []);
+
+  /** The tab list with the active tab's panes saved into its snapshot. */
+  const withActiveSaved = useCallback(() => {
+    const activeId = activeTabIdRef.current;
+    if (activeId === null) {
+      return tabsRef.current;
+    }
+    const snapshot = optionsRef.current.capturePanes();
+    return tabsRef.current.map((tab) => (tab.id === activeId ? { ...tab, snapshot } : tab));
+  }, []);
+
+  const show = useCallback(
+    (nextTabs: CompareTab[], tab: CompareTab) => {
+      commit(nextTabs, tab.id);
+      optionsRef.current.restorePanes(tab.snapshot);
+      if (!tab.snapshot && tab.paths.length > 0) {
+        void optionsRef.current.loadPaths(tab.paths);
+      }
+    },
+    [commit],
+  );
+
+  const activateTab = useCallback(
+    (id: number) => {
+      if (id === activeTabIdRef.current) {
+        return;
+      }
+      const nextTabs = withActiveSaved();
+      const target = nextTabs.find((tab) => tab.id === id);
+      if (target) {
+        show(nextTabs, target);
+      }
+    },
+    [show, withActiveSaved],
+  );
+
+  const newTab = useCallback(async () => {
+    const session = await invoke<CompareSession>("create_session", { paths: [] });
+    const tab: CompareTab = { ...session, snapshot: null };
+    show([...withActiveSaved(), tab], tab);
+  }, [show, withActiveSaved]);
+
+  /** Shows a session opened from outside (launch, "Open with", `--wait`) in its own tab. */
+  const openSession = useCallback(
+    (session: CompareSession) => {
+      const existing = tabsRef.current.find((tab) => tab.id === session.id);
+      if (existing) {
+        if (existing.paths.join("\n") === session.paths.join("\n")) {
+          return;
+        }
+        // A second single-file launch completed the pair.
+        const updated: CompareTab = { ...existing, paths: session.paths, snapshot: null };
+        const nextTabs = tabsRef.current.map((tab) => (tab.id === session.id ? updated : tab));
+        if (activeTabIdRef.current === session.id) {
+          show(nextTabs, updated);
+        } else {
+          commit(nextTabs, activeTabIdRef.current);
+        }
+        return;
+      }
+
+      const tab: CompareTab = { ...session, snapshot: null };
+      const active = tabsRef.current.find((item) => item.id === activeTabIdRef.current);
+      // An untouched empty tab is reused instead of being left behind.
+      if (active && active.waitId === null && !hasContent(optionsRef.current.capturePanes())) {
+        void invoke("close_session", { id: active.id });
+        show(
+          tabsRef.current.map((item) => (item.id === active.id ? tab : item)),
+          tab,
+        );
+        return;
+      }
+      show([...withActiveSaved(), tab], tab);
+    },
+    [commit, show, withActiveSaved],
+  );
+
+  const closeTab = useCallback(
+    async (id: number) => {
+      await invoke("close_session", { id });
+      const index = tabsRef.current.findIndex((tab) => tab.id === id);
+      if (index === -1) {
+        return;
+      }
+      const remaining = tabsRef.current.filter((tab) => tab.id !== id);
+      if (id !== activeTabIdRef.current) {
+        commit(remaining, activeTabIdRef.current);
+        return;
+      }
+      const next = remaining[Math.min(index, remaining.length - 1)];
+      if (next) {
+        show(remaining, next);
+      } else {
+        commit([], null);
+        await newTab();
+      }
+    },
+    [commit, newTab, show],
+  );
+
+  /** Keeps the backend session (and tab title) in step with files opened in the tab. */
+  const syncActivePaths = useCallback(
+    (paths: string[]) => {
+      const activeId = activeTabIdRef.current;
+      const active = tabsRef.current.find((tab) => tab.id === activeId);
+      if (!active || activeId === null || paths.join("\n") === active.paths.join("\n")) {
+        return;
+      }
+      commit(
+        tabsRef.current.map((tab) => (tab.id === activeId ? { ...tab, paths } : tab)),
+        activeId,
+      );
+      void invoke("update_session", { id: activeId, paths }).catch((error) =>
+        console.error(error),
+      );
+    },
+    [commit],
+  );
+
+  /** Opens sessions queued before the window was listening, or a first empty tab. */
+  const initSessions = useCallback(async () => {
+    const pending = await invoke<CompareSession[]>("consume_open_sessions");
+    pending.forEach(openSession);
+    if (tabsRef.current.length === 0) {
+      // The first tab keeps whatever the panes already show.
+      const session = await invoke<CompareSession>("create_session", { paths: [] });
+      commit([{ ...session, snapshot: null }], session.id);
+    }
+  }, [commit, openSession]);
+
+  return {
+    tabs,
+    activeTabId,
+    activateTab,
+    closeTab,
+    initSessions,
+    newTab,
+    openSession,
+    syncActivePaths,
+  };
+};
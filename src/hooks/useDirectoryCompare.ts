@@ -0,0 +1,109 @@
+import { useCallback, useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type {
+  DirCompareBatch,
+  DirCompareSummary,
+  DirEntryDiff,
+} from "../types/dircmp";
+
+type UseDirectoryCompareOptions = {
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+export const useDirectoryCompare = ({ showStatus }: UseDirectoryCompareOptions) => {
+  const [active, setActive] = useState(false);
+  const [busy, setBusy] = useState(false);
+  const [roots, setRoots] = useState<{ left: string; right: string } | null>(null);
+  const [entries, setEntries] = useState<DirEntryDiff[]>([]);
+  const [summary, setSummary] = useState<DirCompareSummary | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const requestIdRef = useRef<string | null>(null);
+
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    let disposed = false;
+    listen<DirCompareBatch>("gcompare://dir-compare-entries", (event) => {
+      if (event.payload.requestId !== requestIdRef.current) {
+        return;
+      }
+      setEntries((prev) => prev.concat(event.payload.entries));
+    })
+      .then((fn) => {
+        if (disposed) {
+          fn();
+        } else {
+          unlisten = fn;
+        }
+      })
+      .catch((listenError) => console.error(listenError));
+    return () => {
+      disposed = true;
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  const compareDirectories = useCallback(
+    async (left: string, right: string) => {
+      const requestId = `${Date.now()}-${Math.random().toString(36).slice(2)}`;
+      requestIdRef.current = requestId;
+      setActive(true);
+      setBusy(true);
+      setRoots({ left, right });
+      setEntries([]);
+      setSummary(null);
+      setError(null);
+      showStatus("Comparing folders...");
+      try {
+        const result = await invoke<DirCompareSummary>("compare_directories", {
+          requestId,
+          left,
+          right,
+        });
+        if (requestIdRef.current !== requestId) {
+          return;
+        }
+        setSummary(result);
+        showStatus(
+          `Folders compared: ${result.modified} modified, ${result.added} added, ${result.removed} removed.`,
+          4000,
+        );
+      } catch (compareError) {
+        if (requestIdRef.current !== requestId) {
+          return;
+        }
+        const message = compareError instanceof Error ? compareError.message : String(compareError);
+        setError(message);
+        showStatus(`Folder compare failed: ${message}`, 6000);
+      } finally {
+        if (requestIdRef.current === requestId) {
+          setBusy(false);
+        }
+      }
+    },
+    [showStatus],
+  );
+
+  const closeDirectoryCompare = useCallback(() => {
+    requestIdRef.current = null;
+    setActive(false);
+    setBusy(false);
+    setRoots(null);
+    setEntries([]);
+    setSummary(null);
+    setError(null);
+  }, []);
+
+  return {
+    dirCompareActive: active,
+    dirCompareBusy: busy,
+    dirCompareRoots: roots,
+    dirCompareEntries: entries,
+    dirCompareSummary: summary,
+    dirCompareError: error,
+    compareDirectories,
+    closeDirectoryCompare,
+  };
+};
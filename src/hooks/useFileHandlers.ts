@@ -1,6 +1,8 @@
 import { useCallback, useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { open } from "@tauri-apps/plugin-dialog";
-import { readFile } from "@tauri-apps/plugin-fs";
+import type { ComparePaneState } from "../types/sessions";
+import type { TextFile, TextFileMeta } from "../types/textfile";
 
 type Side = "original" | "modified";
 
@@ -21,6 +23,11 @@ export const useFileHandlers = ({
   const [modifiedText, setModifiedText] = useState(initialModifiedText);
   const [originalPath, setOriginalPath] = useState<string | null>(null);
   const [modifiedPath, setModifiedPath] = useState<string | null>(null);
+  // How each side's file is stored on disk; null for virtual/unsaved content.
+  const [originalMeta, setOriginalMeta] = useState<TextFileMeta | null>(null);
+  const [modifiedMeta, setModifiedMeta] = useState<TextFileMeta | null>(null);
+  const [originalBinary, setOriginalBinary] = useState(false);
+  const [modifiedBinary, setModifiedBinary] = useState(false);
   const openSlotRef = useRef<Side>("original");
   const pathStateRef = useRef({ original: false, modified: false });
   const openQueueRef = useRef<string[]>([]);
@@ -63,22 +70,27 @@ export const useFileHandlers = ({
 
   const loadFileToSide = useCallback(async (path: string, side: Side) => {
     try {
-      const bytes = await readFile(path);
-      const decoder = new TextDecoder("utf-8", { fatal: false });
-      const contents = decoder.decode(bytes).replace(/\r\n?/g, "\n");
+      const file = await invoke<TextFile>("read_text_file", { path });
       if (side === "original") {
         setOriginalPath(path);
-        setOriginalText(contents);
+        setOriginalText(file.text);
+        setOriginalMeta(file.meta);
+        setOriginalBinary(file.binary);
       } else {
         setModifiedPath(path);
-        setModifiedText(contents);
+        setModifiedText(file.text);
+        setModifiedMeta(file.meta);
+        setModifiedBinary(file.binary);
       }
-      return { ok: true, size: bytes.length };
+      if (file.meta.hadErrors) {
+        showStatus(`Some bytes are not valid ${file.meta.encoding} and were replaced.`, 5000);
+      }
+      return { ok: true, size: file.size };
     } catch (error) {
       console.error(`Failed to load file: ${path}`, error);
       return { ok: false, size: 0 };
     }
-  }, []);
+  }, [showStatus]);
 
   const resolveOpenSide = useCallback(() => {
     const state = pathStateRef.current;
@@ -266,15 +278,39 @@ export const useFileHandlers = ({
     [loadFileToSide, reserveSide, showLoadedStatus, showStatus],
   );
 
-  const setSideContent = useCallback((side: Side, contents: string, path: string | null) => {
-    if (side === "original") {
-      setOriginalText(contents);
-      setOriginalPath(path);
-    } else {
-      setModifiedText(contents);
-      setModifiedPath(path);
-    }
-    pathStateRef.current[side] = Boolean(path);
+  const setSideContent = useCallback(
+    (side: Side, contents: string, path: string | null, meta: TextFileMeta | null = null) => {
+      if (side === "original") {
+        setOriginalText(contents);
+        setOriginalPath(path);
+        setOriginalMeta(meta);
+        setOriginalBinary(false);
+      } else {
+        setModifiedText(contents);
+        setModifiedPath(path);
+        setModifiedMeta(meta);
+        setModifiedBinary(false);
+      }
+      pathStateRef.current[side] = Boolean(path);
+    },
+    [],
+  );
+
+  /** Swaps in another tab's panes; null leaves both sides empty. */
+  const restorePanes = useCallback((state: ComparePaneState | null) => {
+    setOriginalText(state?.originalText ?? "");
+    setModifiedText(state?.modifiedText ?? "");
+    setOriginalPath(state?.originalPath ?? null);
+    setModifiedPath(state?.modifiedPath ?? null);
+    setOriginalMeta(state?.originalMeta ?? null);
+    setModifiedMeta(state?.modifiedMeta ?? null);
+    setOriginalBinary(state?.originalBinary ?? false);
+    setModifiedBinary(state?.modifiedBinary ?? false);
+    pathStateRef.current = {
+      original: Boolean(state?.originalPath),
+      modified: Boolean(state?.modifiedPath),
+    };
+    openSlotRef.current = "original";
   }, []);
 
   return {
@@ -282,10 +318,15 @@ export const useFileHandlers = ({
     modifiedText,
     originalPath,
     modifiedPath,
+    originalMeta,
+    modifiedMeta,
+    originalBinary,
+    modifiedBinary,
     applyPaths,
     enqueueOpenPaths,
     handleOpenFile,
     openFilePath,
+    restorePanes,
     setSideContent,
   };
 };
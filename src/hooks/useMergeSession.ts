@@ -0,0 +1,124 @@
+import { useCallback, useEffect, useMemo, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { MergeChoice, MergeRegion, MergeSessionInfo } from "../types/merge";
+
+type UseMergeSessionOptions = {
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+const joinSections = (first: string, second: string) => {
+  if (!first || first.endsWith("\n")) {
+    return first + second;
+  }
+  return `${first}${first.includes("\r\n") ? "\r\n" : "\n"}${second}`;
+};
+
+const renderRegion = (region: MergeRegion, choice: MergeChoice | undefined) => {
+  if (region.kind !== "conflict") {
+    return region.text;
+  }
+  switch (choice) {
+    case "left":
+      return region.left;
+    case "right":
+      return region.right;
+    case "both":
+      return joinSections(region.left, region.right);
+    case "base":
+      return region.base;
+    default:
+      return "";
+  }
+};
+
+export const useMergeSession = ({ showStatus }: UseMergeSessionOptions) => {
+  const [session, setSession] = useState<MergeSessionInfo | null>(null);
+  const [choices, setChoices] = useState<Record<number, MergeChoice>>({});
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    let active = true;
+    invoke<MergeSessionInfo | null>("load_merge_session")
+      .then((info) => {
+        if (!active || !info) {
+          return;
+        }
+        setSession(info);
+        setChoices({});
+        showStatus(
+          info.result.conflicts > 0
+            ? `Merge loaded: ${info.result.conflicts} conflict(s) to resolve.`
+            : "Merge loaded: no conflicts.",
+          4000,
+        );
+      })
+      .catch((error) => {
+        const message = error instanceof Error ? error.message : String(error);
+        showStatus(`Failed to load merge: ${message}`, 6000);
+      });
+    return () => {
+      active = false;
+    };
+  }, [showStatus]);
+
+  const conflictIndexes = useMemo(
+    () =>
+      session
+        ? session.result.regions.flatMap((region, index) =>
+            region.kind === "conflict" ? [index] : [],
+          )
+        : [],
+    [session],
+  );
+
+  const unresolvedCount = conflictIndexes.filter((index) => !choices[index]).length;
+
+  const mergedText = useMemo(
+    () =>
+      session
+        ? session.result.regions.map((region, index) => renderRegion(region, choices[index])).join("")
+        : "",
+    [choices, session],
+  );
+
+  const chooseConflict = useCallback((index: number, choice: MergeChoice | null) => {
+    setChoices((prev) => {
+      const next = { ...prev };
+      if (choice) {
+        next[index] = choice;
+      } else {
+        delete next[index];
+      }
+      return next;
+    });
+  }, []);
+
+  const finishMerge = useCallback(async () => {
+    if (!session || unresolvedCount > 0) {
+      return;
+    }
+    setSaving(true);
+    try {
+      await invoke("finish_merge", { contents: mergedText });
+    } catch (error) {
+      const message = error instanceof Error ? error.message : String(error);
+      showStatus(`Failed to save merge: ${message}`, 6000);
+      setSaving(false);
+    }
+  }, [mergedText, session, showStatus, unresolvedCount]);
+
+  const abortMerge = useCallback(async () => {
+    await invoke("abort_merge");
+  }, []);
+
+  return {
+    mergeSession: session,
+    mergeConflictIndexes: conflictIndexes,
+    mergeChoices: choices,
+    mergeUnresolvedCount: unresolvedCount,
+    mergeSaving: saving,
+    chooseConflict,
+    finishMerge,
+    abortMerge,
+  };
+};
@@ -0,0 +1,49 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+import type { P4LoginPrompt } from "../types/vcs";
+
+/** Shows the backend's P4 password prompts; the waiting command retries after login. */
+export const useP4Login = () => {
+  const [loginPrompt, setLoginPrompt] = useState<P4LoginPrompt | null>(null);
+
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    let active = true;
+    listen<P4LoginPrompt>("gcompare://p4-login-required", (event) => {
+      setLoginPrompt(event.payload);
+    })
+      .then((fn) => {
+        if (active) {
+          unlisten = fn;
+        } else {
+          fn();
+        }
+      })
+      .catch((error) => console.error(error));
+    return () => {
+      active = false;
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, []);
+
+  const answerLogin = useCallback(
+    (password: string | null) => {
+      if (!loginPrompt) {
+        return;
+      }
+      setLoginPrompt(null);
+      invoke("p4_login_reply", { id: loginPrompt.id, password }).catch((error) => {
+        console.error("Failed to answer P4 login prompt:", error);
+      });
+    },
+    [loginPrompt],
+  );
+
+  /** Logs in for the workspace of `path`; the backend opens the prompt itself. */
+  const logIn = useCallback((path: string) => invoke<void>("p4_login", { path }), []);
+
+  return { loginPrompt, answerLogin, logIn };
+};
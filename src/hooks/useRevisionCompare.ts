@@ -0,0 +1,50 @@
+import { useCallback, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { VcsRevisionCompare } from "../types/vcs";
+import { describeInvokeError } from "../utils/vcsError";
+
+type UseRevisionCompareOptions = {
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+export const useRevisionCompare = ({ showStatus }: UseRevisionCompareOptions) => {
+  const [originalRevision, setOriginalRevision] = useState("HEAD~1");
+  const [modifiedRevision, setModifiedRevision] = useState("HEAD");
+  const [loading, setLoading] = useState(false);
+
+  /** Resolves both revision specs for `path`; returns null (with a status message) on failure. */
+  const compareRevisions = useCallback(
+    async (path: string): Promise<VcsRevisionCompare | null> => {
+      const original = originalRevision.trim();
+      const modified = modifiedRevision.trim();
+      if (!original || !modified) {
+        showStatus("Enter both revisions first.", 2500);
+        return null;
+      }
+      setLoading(true);
+      try {
+        return await invoke<VcsRevisionCompare>("vcs_compare_revisions", {
+          path,
+          originalRevision: original,
+          modifiedRevision: modified,
+        });
+      } catch (error) {
+        const message = describeInvokeError(error);
+        showStatus(`Revision compare failed: ${message}`, 6000);
+        return null;
+      } finally {
+        setLoading(false);
+      }
+    },
+    [modifiedRevision, originalRevision, showStatus],
+  );
+
+  return {
+    originalRevision,
+    setOriginalRevision,
+    modifiedRevision,
+    setModifiedRevision,
+    revisionCompareLoading: loading,
+    compareRevisions,
+  };
+};
@@ -1,6 +1,6 @@
 import { useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import type { AppSettings, P4Settings } from '../types/settings';
+import type { AppSettings, P4Profile, P4Settings } from '../types/settings';
 import { getSettings, updateSettings as updateSettingsStore } from '../services/settingsStore';
 
 export function useSettings() {
@@ -27,11 +27,29 @@ export function useSettings() {
               port: loadedSettings.p4.port || '',
               user: loadedSettings.p4.user || '',
               client: loadedSettings.p4.client || '',
+              tickets: loadedSettings.p4.tickets || '',
+              trust: loadedSettings.p4.trust || '',
             });
           } catch (error) {
             console.error('Failed to sync P4 settings:', error);
           }
         }
+        // 同步 P4 命名配置到后端
+        if (loadedSettings.p4Profiles?.length) {
+          try {
+            await invoke('update_p4_profiles', { profiles: loadedSettings.p4Profiles });
+          } catch (error) {
+            console.error('Failed to sync P4 profiles:', error);
+          }
+        }
+        // 同步 VCS 命令超时到后端
+        if (loadedSettings.vcsTimeoutSecs !== undefined) {
+          try {
+            await invoke('update_vcs_settings', { timeoutSecs: loadedSettings.vcsTimeoutSecs });
+          } catch (error) {
+            console.error('Failed to sync VCS settings:', error);
+          }
+        }
       })
       .catch((error) => {
         console.error('Failed to load settings:', error);
@@ -69,6 +87,8 @@ export function useSettings() {
         port: p4.port || '',
         user: p4.user || '',
         client: p4.client || '',
+        tickets: p4.tickets || '',
+        trust: p4.trust || '',
       });
     } catch (error) {
       console.error('Failed to update P4 settings:', error);
@@ -76,5 +96,35 @@ export function useSettings() {
     }
   };
 
-  return { settings, loading, updateTheme, updateViewMode, updateP4Settings };
+  const updateP4Profiles = async (p4Profiles: P4Profile[]) => {
+    try {
+      await updateSettingsStore({ p4Profiles });
+      setSettings((prev) => ({ ...prev, p4Profiles }));
+      await invoke('update_p4_profiles', { profiles: p4Profiles });
+    } catch (error) {
+      console.error('Failed to update P4 profiles:', error);
+      throw error;
+    }
+  };
+
+  const updateVcsTimeout = async (vcsTimeoutSecs: number) => {
+    try {
+      await updateSettingsStore({ vcsTimeoutSecs });
+      setSettings((prev) => ({ ...prev, vcsTimeoutSecs }));
+      await invoke('update_vcs_settings', { timeoutSecs: vcsTimeoutSecs });
+    } catch (error) {
+      console.error('Failed to update VCS timeout:', error);
+      throw error;
+    }
+  };
+
+  return {
+    settings,
+    loading,
+    updateTheme,
+    updateViewMode,
+    updateP4Settings,
+    updateP4Profiles,
+    updateVcsTimeout,
+  };
 }
@@ -0,0 +1,117 @@
+import { useCallback, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { VcsChangeset, VcsChangesetFile } from "../types/vcs";
+import { describeInvokeError } from "../utils/vcsError";
+
+type UseVcsChangesetOptions = {
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+type ChangesetSource = {
+  provider: VcsChangeset["provider"];
+  revision: string;
+  repoRoot: string | null;
+  workingPath: string | null;
+  /** Lists the files shelved in a pending P4 changelist instead of a submitted change. */
+  shelved?: boolean;
+};
+
+export type ChangesetFileContents = {
+  original: string;
+  modified: string;
+};
+
+export const useVcsChangeset = ({ showStatus }: UseVcsChangesetOptions) => {
+  const [changeset, setChangeset] = useState<VcsChangeset | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [fileIndex, setFileIndex] = useState<number | null>(null);
+  const [shelved, setShelved] = useState(false);
+  const workingPathRef = useRef<string | null>(null);
+  const requestRef = useRef(0);
+
+  const loadChangeset = useCallback(
+    async (source: ChangesetSource) => {
+      const requestId = requestRef.current + 1;
+      requestRef.current = requestId;
+      setLoading(true);
+      setFileIndex(null);
+      try {
+        const result = source.shelved
+          ? await invoke<VcsChangeset>("vcs_shelved_changeset", {
+              provider: source.provider,
+              revision: source.revision,
+              workingPath: source.workingPath,
+            })
+          : await invoke<VcsChangeset>("vcs_changeset", {
+              provider: source.provider,
+              revision: source.revision,
+              repoRoot: source.repoRoot,
+              workingPath: source.workingPath,
+            });
+        if (requestRef.current !== requestId) {
+          return;
+        }
+        workingPathRef.current = source.workingPath;
+        setShelved(Boolean(source.shelved));
+        setChangeset(result);
+        showStatus(
+          `${result.files.length} file(s) ${source.shelved ? "shelved in" : "in"} ${result.revision.slice(0, 12)}.`,
+          2500,
+        );
+      } catch (error) {
+        if (requestRef.current !== requestId) {
+          return;
+        }
+        const message = describeInvokeError(error);
+        showStatus(`Failed to load changeset: ${message}`, 6000);
+      } finally {
+        if (requestRef.current === requestId) {
+          setLoading(false);
+        }
+      }
+    },
+    [showStatus],
+  );
+
+  const fetchChangesetFile = useCallback(
+    async (file: VcsChangesetFile): Promise<ChangesetFileContents> => {
+      if (!changeset) {
+        return { original: "", modified: "" };
+      }
+      const show = (revision: string | null, path: string) =>
+        revision
+          ? invoke<string>("vcs_show_file", {
+              provider: changeset.provider,
+              revision,
+              path,
+              repoRoot: changeset.repoRoot,
+              workingPath: workingPathRef.current,
+            })
+          : Promise.resolve("");
+      const [original, modified] = await Promise.all([
+        show(file.baseRevision, file.originalPath ?? file.path),
+        show(file.revision, file.path),
+      ]);
+      return { original, modified };
+    },
+    [changeset],
+  );
+
+  const closeChangeset = useCallback(() => {
+    requestRef.current += 1;
+    setChangeset(null);
+    setLoading(false);
+    setFileIndex(null);
+  }, []);
+
+  return {
+    changeset,
+    changesetShelved: shelved,
+    changesetLoading: loading,
+    changesetFileIndex: fileIndex,
+    setChangesetFileIndex: setFileIndex,
+    loadChangeset,
+    fetchChangesetFile,
+    closeChangeset,
+  };
+};
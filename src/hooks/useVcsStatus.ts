@@ -0,0 +1,64 @@
+import { useCallback, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type { VcsStatusResult } from "../types/vcs";
+import { describeInvokeError } from "../utils/vcsError";
+
+type UseVcsStatusOptions = {
+  showStatus: (message: string, timeout?: number) => void;
+};
+
+export const useVcsStatus = ({ showStatus }: UseVcsStatusOptions) => {
+  const [open, setOpen] = useState(false);
+  const [loading, setLoading] = useState(false);
+  const [result, setResult] = useState<VcsStatusResult | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const requestRef = useRef(0);
+
+  const refreshChanges = useCallback(
+    async (path: string) => {
+      const requestId = requestRef.current + 1;
+      requestRef.current = requestId;
+      setOpen(true);
+      setLoading(true);
+      setError(null);
+      try {
+        const status = await invoke<VcsStatusResult>("vcs_status", { path });
+        if (requestRef.current !== requestId) {
+          return;
+        }
+        setResult(status);
+        if (status.provider === "none") {
+          showStatus("Not inside a Git/P4/SVN/Mercurial/Jujutsu/Fossil working copy.", 3000);
+        }
+      } catch (statusError) {
+        if (requestRef.current !== requestId) {
+          return;
+        }
+        const message = describeInvokeError(statusError);
+        setResult(null);
+        setError(message);
+        showStatus(`Failed to load changes: ${message}`, 6000);
+      } finally {
+        if (requestRef.current === requestId) {
+          setLoading(false);
+        }
+      }
+    },
+    [showStatus],
+  );
+
+  const closeChanges = useCallback(() => {
+    requestRef.current += 1;
+    setOpen(false);
+    setLoading(false);
+  }, []);
+
+  return {
+    changesOpen: open,
+    changesLoading: loading,
+    changesResult: result,
+    changesError: error,
+    refreshChanges,
+    closeChanges,
+  };
+};
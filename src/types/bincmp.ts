@@ -0,0 +1,14 @@
+export interface ByteRange {
+  offset: number;
+  length: number;
+}
+
+export interface BinaryCompareSummary {
+  leftSize: number;
+  rightSize: number;
+  identical: boolean;
+  firstDifference: number | null;
+  ranges: ByteRange[];
+  rangeCount: number;
+  differingBytes: number;
+}
@@ -0,0 +1,41 @@
+export type DiffAlgorithm = "myers" | "patience";
+export type DiffGranularity = "line" | "word";
+
+export interface DiffOptions {
+  algorithm?: DiffAlgorithm;
+  granularity?: DiffGranularity;
+  contextLines?: number;
+}
+
+export interface DiffSegment {
+  changed: boolean;
+  text: string;
+}
+
+export interface DiffLine {
+  kind: "equal" | "delete" | "insert";
+  oldLine: number | null;
+  newLine: number | null;
+  text: string;
+  missingNewline: boolean;
+  segments?: DiffSegment[];
+}
+
+export interface DiffHunk {
+  oldStart: number;
+  oldLines: number;
+  newStart: number;
+  newLines: number;
+  lines: DiffLine[];
+}
+
+export interface DiffResult {
+  identical: boolean;
+  ratio: number;
+  stats: {
+    insertions: number;
+    deletions: number;
+    hunks: number;
+  };
+  hunks: DiffHunk[];
+}
@@ -0,0 +1,25 @@
+export type DirEntryStatus = "added" | "removed" | "modified" | "identical";
+
+export interface DirEntryDiff {
+  relativePath: string;
+  status: DirEntryStatus;
+  leftPath: string | null;
+  rightPath: string | null;
+  leftSize: number | null;
+  rightSize: number | null;
+  error: string | null;
+}
+
+export interface DirCompareBatch {
+  requestId: string;
+  entries: DirEntryDiff[];
+}
+
+export interface DirCompareSummary {
+  leftRoot: string;
+  rightRoot: string;
+  added: number;
+  removed: number;
+  modified: number;
+  identical: number;
+}
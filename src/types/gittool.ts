@@ -0,0 +1,9 @@
+export interface GitConfigEntry {
+  key: string;
+  value: string;
+}
+
+export interface GitToolInstall {
+  executable: string;
+  entries: GitConfigEntry[];
+}
@@ -0,0 +1,37 @@
+export type ImageSource =
+  | { kind: "file"; path: string }
+  | {
+      kind: "revision";
+      provider: string;
+      revision: string;
+      path: string;
+      repoRoot: string | null;
+      workingPath: string | null;
+    };
+
+export interface ImageInfo {
+  width: number;
+  height: number;
+  dataUrl: string;
+}
+
+export interface PixelRect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface ImageDiffResult {
+  original: ImageInfo;
+  modified: ImageInfo;
+  width: number;
+  height: number;
+  changedPixels: number;
+  totalPixels: number;
+  maskUrl: string;
+  boxes: PixelRect[];
+  boxCount: number;
+}
+
+export type ImageViewMode = "side-by-side" | "onion-skin" | "highlight";
@@ -0,0 +1,23 @@
+export type MergeSide = "left" | "right" | "both";
+
+export type MergeRegion =
+  | { kind: "unchanged"; text: string }
+  | { kind: "resolved"; side: MergeSide; text: string }
+  | { kind: "conflict"; base: string; left: string; right: string };
+
+export interface MergeResult {
+  regions: MergeRegion[];
+  conflicts: number;
+  merged: string;
+}
+
+export interface MergeSessionInfo {
+  basePath: string;
+  leftPath: string;
+  rightPath: string;
+  outputPath: string;
+  result: MergeResult;
+}
+
+/** How a conflict region is resolved in the output. */
+export type MergeChoice = "left" | "right" | "both" | "base";
@@ -0,0 +1,25 @@
+import type { TextFileMeta } from "./textfile";
+
+/** A comparison pair as tracked by the backend; one per tab. */
+export interface CompareSession {
+  id: number;
+  paths: string[];
+  waitId: number | null;
+}
+
+/** Everything needed to bring a tab back after switching away from it. */
+export interface ComparePaneState {
+  originalText: string;
+  modifiedText: string;
+  originalPath: string | null;
+  modifiedPath: string | null;
+  originalMeta: TextFileMeta | null;
+  modifiedMeta: TextFileMeta | null;
+  originalBinary: boolean;
+  modifiedBinary: boolean;
+}
+
+export interface CompareTab extends CompareSession {
+  /** Saved pane state of an inactive tab; null until first shown, so `paths` load then. */
+  snapshot: ComparePaneState | null;
+}
@@ -2,15 +2,30 @@ export interface P4Settings {
   port: string;  // P4PORT
   user: string;  // P4USER
   client: string;  // P4CLIENT
+  tickets?: string;  // P4TICKETS
+  trust?: string;  // P4TRUST
+}
+
+/** Connection used for files under `rootPattern` (`*` / `?` match within one path component). */
+export interface P4Profile {
+  name: string;
+  rootPattern: string;
+  port: string;
+  user: string;
+  client: string;
 }
 
 export interface AppSettings {
   theme: 'system' | 'light' | 'dark';
   viewMode: 'side-by-side' | 'inline';
   p4?: P4Settings;
+  p4Profiles?: P4Profile[];
+  vcsTimeoutSecs?: number;  // 0 = no limit
 }
 
 export const DEFAULT_SETTINGS: AppSettings = {
   theme: 'system',
   viewMode: 'side-by-side',
 };
+
+export const DEFAULT_VCS_TIMEOUT_SECS = 120;
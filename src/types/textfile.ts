@@ -0,0 +1,16 @@
+export type LineEnding = "lf" | "crlf" | "cr";
+
+export interface TextFileMeta {
+  encoding: string;
+  bom: boolean;
+  lineEnding: LineEnding;
+  mixedLineEndings: boolean;
+  hadErrors: boolean;
+}
+
+export interface TextFile {
+  text: string;
+  meta: TextFileMeta;
+  size: number;
+  binary: boolean;
+}
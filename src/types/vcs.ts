@@ -0,0 +1,70 @@
+export type VcsChangeKind =
+  | "modified"
+  | "added"
+  | "deleted"
+  | "renamed"
+  | "untracked"
+  | "conflicted";
+
+export interface VcsStatusEntry {
+  kind: VcsChangeKind;
+  path: string;
+  workingPath: string;
+  originalPath: string | null;
+  baseRevision: string | null;
+}
+
+export interface VcsStatusResult {
+  provider: "git" | "p4" | "svn" | "hg" | "jj" | "fossil" | "none";
+  repoRoot: string | null;
+  entries: VcsStatusEntry[];
+}
+
+export interface VcsChangesetFile {
+  kind: VcsChangeKind;
+  path: string;
+  originalPath: string | null;
+  baseRevision: string | null;
+  revision: string | null;
+}
+
+export interface VcsChangeset {
+  provider: "git" | "p4" | "svn" | "hg" | "jj" | "fossil";
+  revision: string;
+  author: string;
+  timestamp: number;
+  description: string;
+  repoRoot: string | null;
+  files: VcsChangesetFile[];
+}
+
+export interface VcsRevisionFile {
+  revision: string;
+  path: string;
+  content: string;
+}
+
+export interface VcsRevisionCompare {
+  provider: "git" | "p4" | "svn" | "hg" | "jj" | "fossil";
+  original: VcsRevisionFile;
+  modified: VcsRevisionFile;
+}
+
+/** Payload of `gcompare://p4-login-required`; answered with `p4_login_reply`. */
+export interface P4LoginPrompt {
+  id: number;
+  message: string;
+}
+
+/** Rejection value of the `vcs_*` commands, tagged by what went wrong. */
+export type VcsError =
+  | { kind: "notInstalled"; tool: string }
+  | { kind: "notARepository"; message: string }
+  | { kind: "untracked"; message: string }
+  | { kind: "authRequired"; tool: string; message: string }
+  | { kind: "serverUnreachable"; tool: string; message: string }
+  | { kind: "invalidRevision"; message: string }
+  | { kind: "commandFailed"; tool: string; stderr: string; status: number | null }
+  | { kind: "cancelled" }
+  | { kind: "timedOut"; message: string }
+  | { kind: "other"; message: string };
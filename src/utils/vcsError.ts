@@ -0,0 +1,26 @@
+import type { VcsError } from "../types/vcs";
+
+export function isVcsError(error: unknown): error is VcsError {
+  return typeof error === "object" && error !== null && "kind" in error;
+}
+
+/** Message for a rejected `invoke`, with the next step for errors the user can fix. */
+export function describeInvokeError(error: unknown): string {
+  if (!isVcsError(error)) {
+    return error instanceof Error ? error.message : String(error);
+  }
+  switch (error.kind) {
+    case "notInstalled":
+      return `${error.tool} is not installed or not on PATH. Install the ${error.tool} command-line client and restart GCompare.`;
+    case "authRequired":
+      return `${error.tool} login required: ${error.message}`;
+    case "serverUnreachable":
+      return `Cannot reach the ${error.tool} server: ${error.message}`;
+    case "commandFailed":
+      return error.stderr || `${error.tool} exited with status ${error.status ?? "unknown"}`;
+    case "cancelled":
+      return "The request was cancelled.";
+    default:
+      return error.message;
+  }
+}